@@ -0,0 +1,639 @@
+use crate::PROGRAM_START;
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// How deep `include`s may nest before we assume they include each other.
+const MAX_INCLUDE_DEPTH: usize = 16;
+
+/// Names that can't be used for labels or constants because operands already mean something by them.
+const RESERVED: [&str; 9] = ["I", "DT", "ST", "K", "F", "HF", "B", "R", "LONG"];
+
+/// A problem with the source, `line` counts from 1 in `file`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AsmError {
+    pub file: String,
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for AsmError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}:{}: {}", self.file, self.line, self.message)
+    }
+}
+
+impl std::error::Error for AsmError {}
+
+/// An assembled program, ready to be loaded at `PROGRAM_START`.
+#[derive(Debug, PartialEq)]
+pub struct Assembly {
+    pub program: Vec<u8>,
+
+    /// Every label with the address it points at, ordered by address.
+    pub symbols: Vec<(u16, String)>,
+}
+
+impl Assembly {
+    /// Contents of a symbol file, one `<address> <label>` pair per line.
+    pub fn symbol_file(&self) -> String {
+        self.symbols
+            .iter()
+            .map(|(address, label)| format!("{:#06X} {}\n", address, label))
+            .collect()
+    }
+}
+
+/// Assemble `source`, `include` paths are relative to the working directory.
+///
+/// The syntax is the one `disasm` prints: one instruction per line, `;` starts a comment,
+/// `name:` defines a label, `NAME equ 42` a constant, `db`/`dw` emit bytes and words and
+/// `include "file.asm"` pastes in another file. Numbers are decimal, `0x` hex or `0b` binary.
+pub fn assemble(source: &str) -> Result<Assembly, AsmError> {
+    let mut assembler = Assembler::new();
+    assembler.read(source, "<source>", Path::new(""), 0)?;
+    assembler.finish()
+}
+
+/// Assemble the file at `path`, `include` paths are relative to the including file.
+pub fn assemble_file(path: &Path) -> Result<Assembly, AsmError> {
+    let source = fs::read_to_string(path).map_err(|e| AsmError {
+        file: path.display().to_string(),
+        line: 0,
+        message: format!("could not read: {}", e),
+    })?;
+
+    let mut assembler = Assembler::new();
+    let dir = path.parent().unwrap_or_else(|| Path::new(""));
+    assembler.read(&source, &path.display().to_string(), dir, 0)?;
+    assembler.finish()
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Value {
+    Number(u32),
+    Name(String),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Operand {
+    Register(u8),
+    I,
+    IndirectI,
+    Delay,
+    Sound,
+    Key,
+    Font,
+    BigFont,
+    Bcd,
+    Flags,
+    Long(Value),
+    Value(Value),
+}
+
+#[derive(Debug)]
+enum Statement {
+    Instruction {
+        mnemonic: String,
+        operands: Vec<Operand>,
+    },
+    Bytes(Vec<Value>),
+    Words(Vec<Value>),
+}
+
+/// A statement from the first pass, waiting for every label to be known.
+#[derive(Debug)]
+struct Line {
+    file: String,
+    number: usize,
+    statement: Statement,
+}
+
+/// Why the first pass couldn't read a line.
+#[derive(Debug)]
+enum LineError {
+    /// Something wrong with the line itself.
+    Here(String),
+
+    /// Something wrong in a file the line includes, already saying where.
+    Included(AsmError),
+}
+
+impl From<String> for LineError {
+    fn from(message: String) -> Self {
+        LineError::Here(message)
+    }
+}
+
+struct Assembler {
+    lines: Vec<Line>,
+    labels: HashMap<String, u16>,
+    constants: HashMap<String, u32>,
+    symbols: Vec<(u16, String)>,
+    address: u32,
+}
+
+impl Assembler {
+    fn new() -> Self {
+        Assembler {
+            lines: Vec::new(),
+            labels: HashMap::new(),
+            constants: HashMap::new(),
+            symbols: Vec::new(),
+            address: u32::from(PROGRAM_START),
+        }
+    }
+
+    /// First pass, collect labels and constants and work out where everything goes.
+    fn read(&mut self, source: &str, file: &str, dir: &Path, depth: usize) -> Result<(), AsmError> {
+        for (line_idx, line) in source.lines().enumerate() {
+            let error = |message: String| AsmError {
+                file: file.to_string(),
+                line: line_idx + 1,
+                message,
+            };
+
+            let line = line.split(';').next().unwrap_or_default();
+            let statement = match self.read_line(line, dir, depth) {
+                Ok(statement) => statement,
+                Err(LineError::Here(message)) => return Err(error(message)),
+                Err(LineError::Included(inner)) => return Err(inner),
+            };
+            if let Some(statement) = statement {
+                let size = self.size(&statement);
+                self.lines.push(Line {
+                    file: file.to_string(),
+                    number: line_idx + 1,
+                    statement,
+                });
+                self.advance(size).map_err(&error)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn read_line(
+        &mut self,
+        line: &str,
+        dir: &Path,
+        depth: usize,
+    ) -> Result<Option<Statement>, LineError> {
+        let mut line = line.trim();
+
+        while let Some((label, rest)) = split_label(line) {
+            self.define_label(label)?;
+            line = rest.trim();
+        }
+
+        if line.is_empty() {
+            return Ok(None);
+        }
+
+        let (first, rest) = split_word(line);
+        let (second, value) = split_word(rest);
+
+        match first.to_lowercase().as_str() {
+            "include" => {
+                self.include(rest, dir, depth)?;
+                Ok(None)
+            }
+            "db" => Ok(Some(Statement::Bytes(parse_values(rest)?))),
+            "dw" => Ok(Some(Statement::Words(parse_values(rest)?))),
+            _ if second.eq_ignore_ascii_case("equ") => {
+                self.define_constant(first, value)?;
+                Ok(None)
+            }
+            _ => {
+                let operands = if rest.is_empty() {
+                    Vec::new()
+                } else {
+                    rest.split(',')
+                        .map(|operand| parse_operand(operand.trim()))
+                        .collect::<Result<_, _>>()?
+                };
+
+                Ok(Some(Statement::Instruction {
+                    mnemonic: first.to_uppercase(),
+                    operands,
+                }))
+            }
+        }
+    }
+
+    fn include(&mut self, path: &str, dir: &Path, depth: usize) -> Result<(), LineError> {
+        if depth >= MAX_INCLUDE_DEPTH {
+            return Err("includes are nested too deeply".to_string().into());
+        }
+
+        let path = path
+            .strip_prefix('"')
+            .and_then(|path| path.strip_suffix('"'))
+            .ok_or_else(|| "expected 'include \"<file>\"'".to_string())?;
+
+        let path: PathBuf = dir.join(path);
+        let source = fs::read_to_string(&path)
+            .map_err(|e| format!("could not read '{}': {}", path.display(), e))?;
+
+        let included_dir = path.parent().unwrap_or_else(|| Path::new(""));
+        self.read(
+            &source,
+            &path.display().to_string(),
+            included_dir,
+            depth + 1,
+        )
+        .map_err(LineError::Included)
+    }
+
+    fn define_label(&mut self, label: &str) -> Result<(), String> {
+        self.check_name(label)?;
+
+        let address = self.address as u16;
+        self.labels.insert(label.to_string(), address);
+        self.symbols.push((address, label.to_string()));
+
+        Ok(())
+    }
+
+    fn define_constant(&mut self, name: &str, value: &str) -> Result<(), String> {
+        self.check_name(name)?;
+
+        let value = match parse_value(value)? {
+            Value::Number(number) => number,
+            Value::Name(other) => *self
+                .constants
+                .get(&other)
+                .ok_or_else(|| format!("'{}' must be a number or an earlier constant", other))?,
+        };
+
+        self.constants.insert(name.to_string(), value);
+
+        Ok(())
+    }
+
+    fn check_name(&self, name: &str) -> Result<(), String> {
+        if !is_identifier(name) || parse_register(name).is_some() {
+            return Err(format!("'{}' is not a valid name", name));
+        }
+
+        if RESERVED.iter().any(|word| word.eq_ignore_ascii_case(name)) {
+            return Err(format!("'{}' is reserved", name));
+        }
+
+        if self.labels.contains_key(name) || self.constants.contains_key(name) {
+            return Err(format!("'{}' is already defined", name));
+        }
+
+        Ok(())
+    }
+
+    fn size(&self, statement: &Statement) -> u32 {
+        match statement {
+            Statement::Instruction { operands, .. } => {
+                if operands.iter().any(|op| matches!(op, Operand::Long(_))) {
+                    4
+                } else {
+                    2
+                }
+            }
+            Statement::Bytes(values) => values.len() as u32,
+            Statement::Words(values) => 2 * values.len() as u32,
+        }
+    }
+
+    fn advance(&mut self, size: u32) -> Result<(), String> {
+        self.address += size;
+
+        if self.address > 0x10000 {
+            return Err("program does not fit in memory".to_string());
+        }
+
+        Ok(())
+    }
+
+    /// Second pass, encode everything now that all labels have addresses.
+    fn finish(self) -> Result<Assembly, AsmError> {
+        let mut program = Vec::new();
+
+        for line in &self.lines {
+            let bytes = self.encode(&line.statement).map_err(|message| AsmError {
+                file: line.file.clone(),
+                line: line.number,
+                message,
+            })?;
+
+            program.extend(bytes);
+        }
+
+        let mut symbols = self.symbols;
+        symbols.sort_by_key(|(address, _)| *address);
+
+        Ok(Assembly { program, symbols })
+    }
+
+    fn encode(&self, statement: &Statement) -> Result<Vec<u8>, String> {
+        match statement {
+            Statement::Bytes(values) => values
+                .iter()
+                .map(|value| Ok(self.number(value, 8)? as u8))
+                .collect(),
+            Statement::Words(values) => {
+                let mut bytes = Vec::new();
+                for value in values {
+                    bytes.extend(&self.number(value, 16)?.to_be_bytes());
+                }
+                Ok(bytes)
+            }
+            Statement::Instruction { mnemonic, operands } => {
+                if let [Operand::I, Operand::Long(address)] = operands.as_slice() {
+                    if mnemonic != "LD" {
+                        return Err(format!("invalid operands for {}", mnemonic));
+                    }
+
+                    let [high, low] = self.number(address, 16)?.to_be_bytes();
+                    return Ok(vec![0xF0, 0x00, high, low]);
+                }
+
+                Ok(self
+                    .encode_instruction(mnemonic, operands)?
+                    .to_be_bytes()
+                    .to_vec())
+            }
+        }
+    }
+
+    fn encode_instruction(&self, mnemonic: &str, operands: &[Operand]) -> Result<u16, String> {
+        use Operand::*;
+
+        let xy = |x: &u8, y: &u8| u16::from(*x) << 8 | u16::from(*y) << 4;
+        let x = |x: &u8| u16::from(*x) << 8;
+
+        let opcode = match (mnemonic, operands) {
+            ("CLS", []) => 0x00E0,
+            ("RET", []) => 0x00EE,
+            ("SCR", []) => 0x00FB,
+            ("SCL", []) => 0x00FC,
+            ("EXIT", []) => 0x00FD,
+            ("LOW", []) => 0x00FE,
+            ("HIGH", []) => 0x00FF,
+            ("SCD", [Value(n)]) => 0x00C0 | self.number(n, 4)?,
+            ("SCU", [Value(n)]) => 0x00D0 | self.number(n, 4)?,
+            ("JP", [Value(nnn)]) => 0x1000 | self.number(nnn, 12)?,
+            ("JP", [Register(0), Value(nnn)]) => 0xB000 | self.number(nnn, 12)?,
+            ("CALL", [Value(nnn)]) => 0x2000 | self.number(nnn, 12)?,
+            ("SE", [Register(vx), Value(nn)]) => 0x3000 | x(vx) | self.number(nn, 8)?,
+            ("SNE", [Register(vx), Value(nn)]) => 0x4000 | x(vx) | self.number(nn, 8)?,
+            ("SE", [Register(vx), Register(vy)]) => 0x5000 | xy(vx, vy),
+            ("SAVE", [Register(vx), Register(vy)]) => 0x5002 | xy(vx, vy),
+            ("LOAD", [Register(vx), Register(vy)]) => 0x5003 | xy(vx, vy),
+            ("LD", [Register(vx), Value(nn)]) => 0x6000 | x(vx) | self.number(nn, 8)?,
+            ("ADD", [Register(vx), Value(nn)]) => 0x7000 | x(vx) | self.number(nn, 8)?,
+            ("LD", [Register(vx), Register(vy)]) => 0x8000 | xy(vx, vy),
+            ("OR", [Register(vx), Register(vy)]) => 0x8001 | xy(vx, vy),
+            ("AND", [Register(vx), Register(vy)]) => 0x8002 | xy(vx, vy),
+            ("XOR", [Register(vx), Register(vy)]) => 0x8003 | xy(vx, vy),
+            ("ADD", [Register(vx), Register(vy)]) => 0x8004 | xy(vx, vy),
+            ("SUB", [Register(vx), Register(vy)]) => 0x8005 | xy(vx, vy),
+            ("SHR", [Register(vx), Register(vy)]) => 0x8006 | xy(vx, vy),
+            ("SUBN", [Register(vx), Register(vy)]) => 0x8007 | xy(vx, vy),
+            ("SHL", [Register(vx), Register(vy)]) => 0x800E | xy(vx, vy),
+            ("SNE", [Register(vx), Register(vy)]) => 0x9000 | xy(vx, vy),
+            ("LD", [I, Value(nnn)]) => 0xA000 | self.number(nnn, 12)?,
+            ("RND", [Register(vx), Value(nn)]) => 0xC000 | x(vx) | self.number(nn, 8)?,
+            ("DRW", [Register(vx), Register(vy), Value(n)]) => {
+                0xD000 | xy(vx, vy) | self.number(n, 4)?
+            }
+            ("SKP", [Register(vx)]) => 0xE09E | x(vx),
+            ("SKNP", [Register(vx)]) => 0xE0A1 | x(vx),
+            ("PLANE", [Value(n)]) => 0xF001 | self.number(n, 4)? << 8,
+            ("AUDIO", []) => 0xF002,
+            ("LD", [Register(vx), Delay]) => 0xF007 | x(vx),
+            ("LD", [Register(vx), Key]) => 0xF00A | x(vx),
+            ("LD", [Delay, Register(vx)]) => 0xF015 | x(vx),
+            ("LD", [Sound, Register(vx)]) => 0xF018 | x(vx),
+            ("ADD", [I, Register(vx)]) => 0xF01E | x(vx),
+            ("LD", [Font, Register(vx)]) => 0xF029 | x(vx),
+            ("LD", [BigFont, Register(vx)]) => 0xF030 | x(vx),
+            ("LD", [Bcd, Register(vx)]) => 0xF033 | x(vx),
+            ("PITCH", [Register(vx)]) => 0xF03A | x(vx),
+            ("LD", [IndirectI, Register(vx)]) => 0xF055 | x(vx),
+            ("LD", [Register(vx), IndirectI]) => 0xF065 | x(vx),
+            ("LD", [Flags, Register(vx)]) => 0xF075 | x(vx),
+            ("LD", [Register(vx), Flags]) => 0xF085 | x(vx),
+            _ if MNEMONICS.contains(&mnemonic) => {
+                return Err(format!("invalid operands for {}", mnemonic))
+            }
+            _ => return Err(format!("unknown instruction '{}'", mnemonic)),
+        };
+
+        Ok(opcode)
+    }
+
+    /// Resolve `value`, making sure it fits in `bits` bits.
+    fn number(&self, value: &Value, bits: u32) -> Result<u16, String> {
+        let number = match value {
+            Value::Number(number) => *number,
+            Value::Name(name) => match (self.constants.get(name), self.labels.get(name)) {
+                (Some(constant), _) => *constant,
+                (None, Some(address)) => u32::from(*address),
+                (None, None) => return Err(format!("unknown name '{}'", name)),
+            },
+        };
+
+        if number >= 1 << bits {
+            return Err(format!("{:#X} does not fit in {} bits", number, bits));
+        }
+
+        Ok(number as u16)
+    }
+}
+
+const MNEMONICS: [&str; 31] = [
+    "CLS", "RET", "SCR", "SCL", "EXIT", "LOW", "HIGH", "SCD", "SCU", "JP", "CALL", "SE", "SNE",
+    "SAVE", "LOAD", "LD", "ADD", "OR", "AND", "XOR", "SUB", "SHR", "SUBN", "SHL", "RND", "DRW",
+    "SKP", "SKNP", "PLANE", "AUDIO", "PITCH",
+];
+
+/// `label: rest` split into its parts, if the line starts with a label.
+fn split_label(line: &str) -> Option<(&str, &str)> {
+    let (first, _) = split_word(line);
+    let label = first.strip_suffix(':')?;
+
+    Some((label, &line[first.len()..]))
+}
+
+/// The first word and everything after it, trimmed.
+fn split_word(line: &str) -> (&str, &str) {
+    match line.find(char::is_whitespace) {
+        Some(idx) => (&line[..idx], line[idx..].trim()),
+        None => (line, ""),
+    }
+}
+
+fn is_identifier(name: &str) -> bool {
+    let mut chars = name.chars();
+
+    match chars.next() {
+        Some(c) if c.is_ascii_alphabetic() || c == '_' => {
+            chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+        }
+        _ => false,
+    }
+}
+
+fn parse_register(operand: &str) -> Option<u8> {
+    let mut chars = operand.chars();
+
+    match (chars.next(), chars.next(), chars.next()) {
+        (Some('V'), Some(digit), None) | (Some('v'), Some(digit), None) => {
+            digit.to_digit(16).map(|digit| digit as u8)
+        }
+        _ => None,
+    }
+}
+
+fn parse_operand(operand: &str) -> Result<Operand, String> {
+    if let Some(register) = parse_register(operand) {
+        return Ok(Operand::Register(register));
+    }
+
+    let (first, rest) = split_word(operand);
+    if first.eq_ignore_ascii_case("LONG") && !rest.is_empty() {
+        return Ok(Operand::Long(parse_value(rest)?));
+    }
+
+    Ok(match operand.to_uppercase().as_str() {
+        "I" => Operand::I,
+        "[I]" => Operand::IndirectI,
+        "DT" => Operand::Delay,
+        "ST" => Operand::Sound,
+        "K" => Operand::Key,
+        "F" => Operand::Font,
+        "HF" => Operand::BigFont,
+        "B" => Operand::Bcd,
+        "R" => Operand::Flags,
+        _ => Operand::Value(parse_value(operand)?),
+    })
+}
+
+fn parse_values(list: &str) -> Result<Vec<Value>, String> {
+    if list.is_empty() {
+        return Err("expected at least one value".to_string());
+    }
+
+    list.split(',')
+        .map(|value| parse_value(value.trim()))
+        .collect()
+}
+
+fn parse_value(value: &str) -> Result<Value, String> {
+    if is_identifier(value) {
+        return Ok(Value::Name(value.to_string()));
+    }
+
+    let lower = value.to_lowercase();
+    let parsed = if let Some(hex) = lower.strip_prefix("0x") {
+        u32::from_str_radix(hex, 16)
+    } else if let Some(binary) = lower.strip_prefix("0b") {
+        u32::from_str_radix(binary, 2)
+    } else {
+        lower.parse()
+    };
+
+    parsed
+        .map(Value::Number)
+        .map_err(|_| format!("expected a number or a name, got '{}'", value))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::disasm::disassemble;
+    use crate::mode::Mode;
+
+    #[test]
+    fn assembles_labels_and_constants() {
+        let source = "
+            SPEED equ 3        ; frames per move
+            start:
+                LD V0, SPEED
+                LD I, sprite
+                DRW V0, V1, 2
+                JP start
+            sprite: db 0b11000000, 0xFF
+            dw sprite
+        ";
+
+        let assembly = assemble(source).unwrap();
+
+        assert_eq!(
+            assembly.program,
+            [0x60, 0x03, 0xA2, 0x08, 0xD0, 0x12, 0x12, 0x00, 0xC0, 0xFF, 0x02, 0x08]
+        );
+        assert_eq!(assembly.symbol_file(), "0x0200 start\n0x0208 sprite\n");
+    }
+
+    #[test]
+    fn assembles_long_load() {
+        let assembly = assemble("LD I, LONG data\nCLS\ndata: db 1").unwrap();
+
+        assert_eq!(assembly.program, [0xF0, 0x00, 0x02, 0x06, 0x00, 0xE0, 0x01]);
+    }
+
+    #[test]
+    fn reassembles_disassembly() {
+        let rom = fs::read("pong.ch8").unwrap();
+        let source: String = disassemble(&rom, Mode::Chip8)
+            .lines()
+            .map(|line| format!("{}\n", &line[18..]))
+            .collect();
+
+        assert_eq!(assemble(&source).unwrap().program, rom);
+    }
+
+    #[test]
+    fn includes_files() {
+        let dir = std::env::temp_dir().join("chip8rust-asm-include");
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("main.asm"), "include \"font.asm\"\nLD I, digit").unwrap();
+        fs::write(dir.join("font.asm"), "digit: db 0xF0").unwrap();
+
+        let assembly = assemble_file(&dir.join("main.asm")).unwrap();
+
+        assert_eq!(assembly.program, [0xF0, 0xA2, 0x00]);
+
+        fs::write(dir.join("font.asm"), "digit: db 0xF0\nLD V0").unwrap();
+        let error = assemble_file(&dir.join("main.asm")).unwrap_err();
+        assert_eq!(error.file, dir.join("font.asm").display().to_string());
+        assert_eq!(error.line, 2);
+        assert_eq!(error.message, "invalid operands for LD");
+    }
+
+    #[test]
+    fn reports_line_numbers() {
+        let error = |source| assemble(source).unwrap_err().to_string();
+
+        assert_eq!(
+            error("CLS\nFOO V1"),
+            "<source>:2: unknown instruction 'FOO'"
+        );
+        assert_eq!(error("LD V1"), "<source>:1: invalid operands for LD");
+        assert_eq!(
+            error("\n\nJP nowhere"),
+            "<source>:3: unknown name 'nowhere'"
+        );
+        assert_eq!(
+            error("LD V0, 256"),
+            "<source>:1: 0x100 does not fit in 8 bits"
+        );
+        assert_eq!(
+            error("a: CLS\na: RET"),
+            "<source>:2: 'a' is already defined"
+        );
+        assert_eq!(
+            error("db 1, ?"),
+            "<source>:1: expected a number or a name, got '?'"
+        );
+    }
+}
@@ -0,0 +1,440 @@
+use crate::mode::Mode;
+use crate::Chip;
+use std::io::{self, Seek, SeekFrom, Write};
+
+/// Samples per second of the generated audio.
+pub const SAMPLE_RATE: u32 = 44_100;
+
+/// Samples per 60 Hz frame, the resolution of the sound timer. 44.1 kHz divides evenly,
+/// so a tone is exactly as long as the timer ran.
+pub const SAMPLES_PER_FRAME: usize = SAMPLE_RATE as usize / 60;
+
+/// What the buzzer sounds like.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Tone {
+    /// Pitch in Hz.
+    pub frequency: f64,
+
+    /// Loudness from 0 (silent) to 1 (full scale).
+    pub volume: f64,
+}
+
+impl Default for Tone {
+    fn default() -> Self {
+        Tone {
+            frequency: 440.0,
+            volume: 0.25,
+        }
+    }
+}
+
+/// Where the buzzer goes, fed `Chip::buzzer` once per 60 Hz frame.
+pub trait Sink {
+    /// Play one frame, with the buzzer sounding if `on`.
+    fn frame(&mut self, on: bool) -> io::Result<()>;
+
+    /// Play the frame `chip` just finished. XO-CHIP programs choose what the buzzer
+    /// sounds like, sinks that can play their audio pattern do so.
+    fn play(&mut self, chip: &Chip) -> io::Result<()> {
+        self.frame(chip.buzzer)
+    }
+
+    /// Called once after the last frame.
+    fn finish(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Plays nothing, only counts frames.
+#[derive(Debug, Default)]
+pub struct Null {
+    pub frames: u64,
+
+    /// Frames the buzzer sounded in.
+    pub sounding: u64,
+}
+
+impl Sink for Null {
+    fn frame(&mut self, on: bool) -> io::Result<()> {
+        self.frames += 1;
+        if on {
+            self.sounding += 1;
+        }
+        Ok(())
+    }
+}
+
+/// Rings the terminal bell whenever the buzzer starts, the length of the tone is lost.
+pub struct Bell<W: Write> {
+    out: W,
+    on: bool,
+}
+
+impl<W: Write> Bell<W> {
+    pub fn new(out: W) -> Self {
+        Bell { out, on: false }
+    }
+}
+
+impl<W: Write> Sink for Bell<W> {
+    fn frame(&mut self, on: bool) -> io::Result<()> {
+        if on && !self.on {
+            self.out.write_all(b"\x07")?;
+            self.out.flush()?;
+        }
+        self.on = on;
+        Ok(())
+    }
+}
+
+/// Square wave at a `Tone`, in whole frames of samples.
+#[derive(Debug, Clone)]
+pub struct SquareWave {
+    pub tone: Tone,
+
+    /// Position within the current period, from 0 to 1.
+    phase: f64,
+}
+
+impl SquareWave {
+    pub fn new(tone: Tone) -> Self {
+        SquareWave { tone, phase: 0.0 }
+    }
+
+    /// Append one frame of samples to `samples`, silence unless `on`.
+    ///
+    /// Every tone starts at the beginning of a period, so the same sound timer
+    /// value always produces the same samples.
+    pub fn frame(&mut self, on: bool, samples: &mut Vec<i16>) {
+        if !on {
+            self.phase = 0.0;
+            samples.extend(std::iter::repeat_n(0, SAMPLES_PER_FRAME));
+            return;
+        }
+
+        let amplitude = (self.tone.volume.clamp(0.0, 1.0) * f64::from(i16::MAX)) as i16;
+        let step = self.tone.frequency / f64::from(SAMPLE_RATE);
+
+        for _ in 0..SAMPLES_PER_FRAME {
+            samples.push(if self.phase < 0.5 {
+                amplitude
+            } else {
+                -amplitude
+            });
+            self.phase = (self.phase + step).fract();
+        }
+    }
+}
+
+/// XO-CHIP's 1-bit audio pattern looped at the rate its pitch sets, in whole frames of
+/// samples.
+#[derive(Debug, Clone)]
+pub struct PatternWave {
+    /// Loudness from 0 (silent) to 1 (full scale).
+    pub volume: f64,
+
+    /// Bits of the pattern played so far in the current tone, from 0 to 128.
+    position: f64,
+}
+
+impl PatternWave {
+    pub fn new(volume: f64) -> Self {
+        PatternWave {
+            volume,
+            position: 0.0,
+        }
+    }
+
+    /// Bits per second `pitch` plays the pattern at, 4000 for pitch 64 and twice as
+    /// many every 48 steps up.
+    pub fn rate(pitch: u8) -> f64 {
+        4000.0 * 2f64.powf((f64::from(pitch) - 64.0) / 48.0)
+    }
+
+    /// Append one frame of samples to `samples`, silence unless `on`. Like with
+    /// `SquareWave` every tone starts at the beginning of the pattern.
+    pub fn frame(&mut self, on: bool, pattern: &[u8; 16], pitch: u8, samples: &mut Vec<i16>) {
+        if !on {
+            self.position = 0.0;
+            samples.extend(std::iter::repeat_n(0, SAMPLES_PER_FRAME));
+            return;
+        }
+
+        let bits = (pattern.len() * 8) as f64;
+        let amplitude = (self.volume.clamp(0.0, 1.0) * f64::from(i16::MAX)) as i16;
+        let step = Self::rate(pitch) / f64::from(SAMPLE_RATE);
+
+        for _ in 0..SAMPLES_PER_FRAME {
+            let bit = self.position as usize;
+            let set = pattern[bit / 8] & 0x80 >> (bit % 8) != 0;
+            samples.push(if set { amplitude } else { -amplitude });
+            self.position = (self.position + step) % bits;
+        }
+    }
+}
+
+/// Writes the buzzer as raw signed 16-bit little-endian mono samples at `SAMPLE_RATE`,
+/// e.g. into a pipe to a sound player.
+pub struct Pcm<W: Write> {
+    out: W,
+    wave: SquareWave,
+    pattern: PatternWave,
+    samples: Vec<i16>,
+
+    /// Samples written so far.
+    written: u64,
+}
+
+impl<W: Write> Pcm<W> {
+    pub fn new(out: W, tone: Tone) -> Self {
+        Pcm {
+            out,
+            wave: SquareWave::new(tone),
+            pattern: PatternWave::new(tone.volume),
+            samples: Vec::with_capacity(SAMPLES_PER_FRAME),
+            written: 0,
+        }
+    }
+}
+
+impl<W: Write> Pcm<W> {
+    fn write_samples(&mut self) -> io::Result<()> {
+        let bytes: Vec<u8> = self
+            .samples
+            .iter()
+            .flat_map(|sample| sample.to_le_bytes())
+            .collect();
+        self.out.write_all(&bytes)?;
+        self.written += self.samples.len() as u64;
+
+        Ok(())
+    }
+}
+
+impl<W: Write> Sink for Pcm<W> {
+    fn frame(&mut self, on: bool) -> io::Result<()> {
+        self.samples.clear();
+        self.wave.frame(on, &mut self.samples);
+        self.write_samples()
+    }
+
+    /// XO-CHIP programs play their audio pattern at their pitch instead of the tone.
+    fn play(&mut self, chip: &Chip) -> io::Result<()> {
+        if chip.mode != Mode::XoChip {
+            return self.frame(chip.buzzer);
+        }
+
+        self.samples.clear();
+        self.pattern.frame(
+            chip.buzzer,
+            &chip.audio_pattern,
+            chip.pitch,
+            &mut self.samples,
+        );
+        self.write_samples()
+    }
+
+    fn finish(&mut self) -> io::Result<()> {
+        self.out.flush()
+    }
+}
+
+/// Bytes of the WAV header before the samples.
+const WAV_HEADER_SIZE: u32 = 44;
+
+/// Writes the buzzer as a WAV file of `Pcm` samples, `finish` fills in its length.
+pub struct Wav<W: Write + Seek> {
+    pcm: Pcm<W>,
+}
+
+impl<W: Write + Seek> Wav<W> {
+    /// Start the file with a header for no samples yet.
+    pub fn new(mut out: W, tone: Tone) -> io::Result<Self> {
+        out.write_all(&wav_header(0))?;
+
+        Ok(Wav {
+            pcm: Pcm::new(out, tone),
+        })
+    }
+}
+
+impl<W: Write + Seek> Sink for Wav<W> {
+    fn frame(&mut self, on: bool) -> io::Result<()> {
+        self.pcm.frame(on)
+    }
+
+    fn play(&mut self, chip: &Chip) -> io::Result<()> {
+        self.pcm.play(chip)
+    }
+
+    fn finish(&mut self) -> io::Result<()> {
+        let data_size = (self.pcm.written * 2).min(u64::from(u32::MAX)) as u32;
+        let out = &mut self.pcm.out;
+
+        out.seek(SeekFrom::Start(0))?;
+        out.write_all(&wav_header(data_size))?;
+        out.seek(SeekFrom::End(0))?;
+        out.flush()
+    }
+}
+
+/// RIFF header of a mono 16-bit WAV file with `data_size` bytes of samples.
+fn wav_header(data_size: u32) -> Vec<u8> {
+    let mut header = Vec::with_capacity(WAV_HEADER_SIZE as usize);
+
+    header.extend(b"RIFF");
+    header.extend(
+        (WAV_HEADER_SIZE - 8)
+            .saturating_add(data_size)
+            .to_le_bytes(),
+    );
+    header.extend(b"WAVEfmt ");
+    header.extend(16u32.to_le_bytes());
+    // PCM, one channel.
+    header.extend(1u16.to_le_bytes());
+    header.extend(1u16.to_le_bytes());
+    header.extend(SAMPLE_RATE.to_le_bytes());
+    // Bytes per second and per sample.
+    header.extend((SAMPLE_RATE * 2).to_le_bytes());
+    header.extend(2u16.to_le_bytes());
+    header.extend(16u16.to_le_bytes());
+    header.extend(b"data");
+    header.extend(data_size.to_le_bytes());
+
+    header
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Chip;
+    use std::io::Cursor;
+
+    #[test]
+    fn square_wave_lasts_whole_frames() {
+        let mut wave = SquareWave::new(Tone {
+            frequency: 11_025.0,
+            volume: 1.0,
+        });
+        let mut samples = Vec::new();
+
+        wave.frame(true, &mut samples);
+        wave.frame(false, &mut samples);
+
+        assert_eq!(samples.len(), 2 * SAMPLES_PER_FRAME);
+        assert_eq!(samples[..4], [i16::MAX, i16::MAX, -i16::MAX, -i16::MAX]);
+        assert_eq!(samples[SAMPLES_PER_FRAME - 1], -i16::MAX);
+        assert!(samples[SAMPLES_PER_FRAME..]
+            .iter()
+            .all(|sample| *sample == 0));
+    }
+
+    #[test]
+    fn tones_start_at_the_same_phase() {
+        let mut wave = SquareWave::new(Tone::default());
+        let mut first = Vec::new();
+        let mut second = Vec::new();
+
+        wave.frame(true, &mut first);
+        wave.frame(false, &mut Vec::new());
+        wave.frame(true, &mut second);
+
+        assert_eq!(first, second);
+    }
+
+    #[test]
+    fn plays_audio_patterns_at_their_pitch() {
+        assert_eq!(PatternWave::rate(64), 4000.0);
+        assert_eq!(PatternWave::rate(112), 8000.0);
+
+        // 4000 bits per second are 11.025 samples per bit, so 12 samples of the first.
+        let mut pattern = [0; 16];
+        pattern[0] = 0x80;
+        let mut wave = PatternWave::new(1.0);
+        let mut samples = Vec::new();
+        wave.frame(true, &pattern, 64, &mut samples);
+
+        assert_eq!(samples.len(), SAMPLES_PER_FRAME);
+        assert!(samples[..12].iter().all(|sample| *sample == i16::MAX));
+        assert_eq!(samples[12], -i16::MAX);
+
+        // The 128 bits loop after 128 * 11.025 samples, in the next frame.
+        wave.frame(true, &pattern, 64, &mut samples);
+        assert_eq!(samples[1411..1413], [-i16::MAX, i16::MAX]);
+    }
+
+    #[test]
+    fn plays_xo_chip_sound_from_the_chip() {
+        let mut chip = Chip::with_mode(Mode::XoChip);
+        chip.buzzer = true;
+        chip.audio_pattern = [0; 16];
+        chip.pitch = 0;
+
+        let mut pcm = Pcm::new(Vec::new(), Tone::default());
+        pcm.play(&chip).unwrap();
+
+        // A pattern of 0 bits is a constant level rather than the tone's square wave.
+        let volume = (Tone::default().volume * f64::from(i16::MAX)) as i16;
+        assert_eq!(pcm.out.len(), SAMPLES_PER_FRAME * 2);
+        assert!(pcm
+            .out
+            .chunks(2)
+            .all(|sample| sample == (-volume).to_le_bytes()));
+
+        chip.mode = Mode::Chip8;
+        let mut tone = Pcm::new(Vec::new(), Tone::default());
+        tone.play(&chip).unwrap();
+        let mut square = Pcm::new(Vec::new(), Tone::default());
+        square.frame(true).unwrap();
+        assert_eq!(tone.out, square.out);
+    }
+
+    #[test]
+    fn follows_the_sound_timer() {
+        // LD V0, 3; LD ST, V0; JP 0x204
+        let mut chip = Chip::new();
+        chip.load_program(&[0x60, 0x03, 0xF0, 0x18, 0x12, 0x04])
+            .unwrap();
+        let mut sink = Null::default();
+
+        chip.step().unwrap();
+        chip.step().unwrap();
+        for _ in 0..5 {
+            chip.update_timers();
+            sink.frame(chip.buzzer).unwrap();
+        }
+
+        assert_eq!(sink.frames, 5);
+        assert_eq!(sink.sounding, 3);
+    }
+
+    #[test]
+    fn rings_bell_once_per_tone() {
+        let mut out = Vec::new();
+        let mut bell = Bell::new(&mut out);
+
+        for on in [true, true, false, true] {
+            bell.frame(on).unwrap();
+        }
+
+        assert_eq!(out, b"\x07\x07");
+    }
+
+    #[test]
+    fn writes_wav_file() {
+        let mut wav = Wav::new(Cursor::new(Vec::new()), Tone::default()).unwrap();
+        wav.frame(true).unwrap();
+        wav.frame(false).unwrap();
+        wav.finish().unwrap();
+
+        let bytes = wav.pcm.out.into_inner();
+        let data_size = (2 * SAMPLES_PER_FRAME * 2) as u32;
+
+        assert_eq!(bytes.len(), WAV_HEADER_SIZE as usize + data_size as usize);
+        assert_eq!(&bytes[..4], b"RIFF");
+        assert_eq!(bytes[4..8], (36 + data_size).to_le_bytes());
+        assert_eq!(&bytes[36..40], b"data");
+        assert_eq!(bytes[40..44], data_size.to_le_bytes());
+        assert_eq!(bytes[44..46], 8191i16.to_le_bytes());
+    }
+}
@@ -0,0 +1,767 @@
+use crate::headless;
+use crate::image_file::{self, Format};
+use crate::terminal::input::DEFAULT_HOLD_TIMEOUT;
+use crate::terminal::screen::RenderMode;
+use chip8rust::audio::Tone;
+use chip8rust::mode::Mode;
+use chip8rust::quirks::Profile;
+use chip8rust::random::Algorithm;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::time::Duration;
+
+pub const USAGE: &str = "\
+Usage: chip8rust [OPTIONS] <ROM>
+       chip8rust disasm [--mode <NAME>] <ROM>
+       chip8rust asm [--output <FILE>] <SOURCE>
+       chip8rust keys [--keymap <FILE>] [<ROM>]
+
+Arguments:
+  <ROM>                 Path to the .ch8 program to run
+
+Options:
+  -i, --ipf <N>         Instructions executed per 60 Hz frame [default: 10]
+  -c, --clock <HZ>      Clock speed in instructions per second, overrides --ipf
+  -q, --quirks <NAME>   Interpreter the ROM was written for: classic, vip, chip48, schip,
+                        modern [default: classic]
+  -m, --mode <NAME>     Machine to emulate: chip8 (with SUPER-CHIP) or xochip [default: chip8]
+  -k, --keymap <FILE>   Load key bindings from FILE instead of ~/.config/chip8rust/keys.conf
+                        or the default layout
+      --render <NAME>   How pixels are drawn: block, halfblock, quadrant or braille
+                        [default: halfblock]
+      --audio <SINK>    Where the buzzer goes: bell (the terminal bell), pcm (raw 16-bit
+                        44.1 kHz samples on stdout, with --headless), wav:<FILE> or none
+                        [default: bell, none with --headless]
+      --tone <HZ>       Pitch of the buzzer for pcm and wav, XO-CHIP programs set their own
+                        [default: 440]
+      --volume <PERCENT>
+                        Loudness of the buzzer for pcm and wav [default: 25]
+      --hold <MS>       How long a key stays pressed after the terminal last reported it,
+                        used when the terminal doesn't report key releases [default: 600]
+  -s, --seed <N>        Seed the random number generator used by CXNN
+  -r, --rng <NAME>      Random number generator, only splitmix so far [default: splitmix]
+  -l, --load-state <FILE>
+                        Start from the save state in FILE
+      --record <FILE>   Record the keypad state of every frame to the movie FILE
+      --play <FILE>     Play the keypad back from the movie FILE, with the ROM settings it
+                        was recorded with, then hand over to the keyboard
+  -d, --debug           Start paused in the debugger
+  -b, --break <ADDR>    Pause at the hex address ADDR, can be repeated, implies --debug
+      --headless        Run without the terminal until the program halts, then print the screen
+      --instructions <N>
+                        Stop after N instructions, implies --headless
+      --frames <N>      Stop after N frames, implies --headless
+      --until-pc <ADDR> Stop before running the instruction at the hex address ADDR,
+                        implies --headless
+      --until-opcode <OPCODE>
+                        Stop before running the hex OPCODE, implies --headless
+      --input <FILE>    Hold keypad keys as scripted in FILE, implies --headless
+      --dump <ADDR:LEN> Include LEN bytes of memory from the hex address ADDR in the JSON
+                        output, can be repeated, implies --json
+      --json            Print registers, timers, stack, memory and screen as JSON,
+                        implies --headless
+      --screenshot <FILE>
+                        Save the final screen to the .png or .pbm FILE, implies --headless
+      --video <PATH>    Record the screen of every frame to the animated GIF PATH, or as
+                        numbered PPM images into the directory PATH if it doesn't end in .gif
+      --scale <N>       Image pixels per screen pixel in screenshots, per hi-res pixel in
+                        videos [default: 4]
+      --colors <BG,FG[,C2,C3]>
+                        Hex colors of screenshots, background first, XO-CHIP colors after
+                        [default: 000000,ffffff,808000,800000]
+  -h, --help            Print this help
+
+Keys:
+  F3                    Switch to the next render mode
+  F2, F4                Save and load the state in the --load-state FILE, or next to the ROM
+                        with a .state extension
+  F6                    Rewind one second, up to 30 seconds back
+  F12                   Save a screenshot next to the ROM as <ROM>-<N>.png
+  F1                    Start or stop recording a video next to the ROM as <ROM>-<N>.gif
+
+Commands:
+  disasm                Print a listing of the ROM with addresses, raw bytes and mnemonics
+  asm                   Assemble SOURCE into a .ch8 file [default: SOURCE with a .ch8 extension]
+                        and write its labels next to it in a .sym file
+  keys                  Show which host keys the keypad keys are bound to for ROM and warn
+                        about duplicated bindings and unbound keypad keys";
+
+const DEFAULT_IPF: u32 = 10;
+
+/// Where the buzzer goes, each is one of the `chip8rust::audio` sinks.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Audio {
+    Bell,
+    /// Raw samples on stdout.
+    Pcm,
+    Wav(PathBuf),
+    None,
+}
+
+impl FromStr for Audio {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "bell" => Ok(Audio::Bell),
+            "pcm" => Ok(Audio::Pcm),
+            "none" => Ok(Audio::None),
+            _ => match s.strip_prefix("wav:") {
+                Some(path) if !path.is_empty() => Ok(Audio::Wav(PathBuf::from(path))),
+                _ => Err(format!(
+                    "unknown audio output '{}', expected bell, pcm, wav:<FILE> or none",
+                    s
+                )),
+            },
+        }
+    }
+}
+
+/// Settings picked on the command line.
+#[derive(Debug, PartialEq)]
+pub struct Args {
+    pub rom: PathBuf,
+    pub instructions_per_frame: u32,
+    pub quirks: Profile,
+    pub mode: Mode,
+    pub key_map: Option<PathBuf>,
+    pub hold_timeout: Duration,
+    pub render: RenderMode,
+    /// `None` picks the bell in the terminal and no sound without it.
+    pub audio: Option<Audio>,
+    pub tone: Tone,
+    /// Scale and colors of screenshots.
+    pub image_style: image_file::Style,
+    pub video: Option<PathBuf>,
+    pub seed: Option<u64>,
+    pub rng: Algorithm,
+    /// Run without the terminal, see `headless::Options`.
+    pub headless: Option<headless::Options>,
+    pub debug: bool,
+    pub breakpoints: Vec<u16>,
+    pub load_state: Option<PathBuf>,
+    pub record: Option<PathBuf>,
+    pub play: Option<PathBuf>,
+}
+
+/// What the user asked the binary to do.
+#[derive(Debug, PartialEq)]
+pub enum Command {
+    Run(Box<Args>),
+    Disasm {
+        rom: PathBuf,
+        mode: Mode,
+    },
+    Asm {
+        source: PathBuf,
+        output: PathBuf,
+    },
+    Keys {
+        key_map: Option<PathBuf>,
+        rom: Option<PathBuf>,
+    },
+    Help,
+}
+
+impl Command {
+    /// Parse the arguments, without the program name.
+    pub fn parse<I: IntoIterator<Item = String>>(args: I) -> Result<Command, String> {
+        let mut args = args.into_iter().peekable();
+
+        match args.peek().map(String::as_str) {
+            Some("disasm") => {
+                args.next();
+                return Command::parse_disasm(args);
+            }
+            Some("asm") => {
+                args.next();
+                return Command::parse_asm(args);
+            }
+            Some("keys") => {
+                args.next();
+                return Command::parse_keys(args);
+            }
+            _ => (),
+        }
+
+        let mut rom = None;
+        let mut instructions_per_frame = DEFAULT_IPF;
+        let mut quirks = Profile::Classic;
+        let mut mode = Mode::Chip8;
+        let mut key_map = None;
+        let mut hold_timeout = DEFAULT_HOLD_TIMEOUT;
+        let mut render = RenderMode::default();
+        let mut audio = None;
+        let mut tone = Tone::default();
+        let mut image_style = image_file::Style::default();
+        let mut video = None;
+        let mut seed = None;
+        let mut rng = Algorithm::default();
+        let mut headless = None;
+        let mut debug = false;
+        let mut breakpoints = Vec::new();
+        let mut load_state = None;
+        let mut record = None;
+        let mut play = None;
+
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "-h" | "--help" => return Ok(Command::Help),
+                "-i" | "--ipf" => {
+                    instructions_per_frame = parse_number(&arg, args.next())?;
+                    if instructions_per_frame == 0 {
+                        return Err(format!("'{}' must be at least 1", arg));
+                    }
+                }
+                "-c" | "--clock" => {
+                    let clock: u32 = parse_number(&arg, args.next())?;
+                    if clock < 60 {
+                        return Err(format!("'{}' must be at least 60", arg));
+                    }
+                    instructions_per_frame = clock / 60;
+                }
+                "-q" | "--quirks" => quirks = value(&arg, args.next())?.parse()?,
+                "-m" | "--mode" => mode = value(&arg, args.next())?.parse()?,
+                "-k" | "--keymap" => key_map = Some(PathBuf::from(value(&arg, args.next())?)),
+                "--render" => render = value(&arg, args.next())?.parse()?,
+                "--audio" => audio = Some(value(&arg, args.next())?.parse()?),
+                "--tone" => {
+                    tone.frequency = parse_number(&arg, args.next())?;
+                    if !tone.frequency.is_finite() || tone.frequency <= 0.0 {
+                        return Err(format!("'{}' must be above 0", arg));
+                    }
+                }
+                "--volume" => {
+                    let percent: u8 = parse_number(&arg, args.next())?;
+                    if percent > 100 {
+                        return Err(format!("'{}' must be at most 100", arg));
+                    }
+                    tone.volume = f64::from(percent) / 100.0;
+                }
+                "--hold" => hold_timeout = Duration::from_millis(parse_number(&arg, args.next())?),
+                "-s" | "--seed" => seed = Some(parse_number(&arg, args.next())?),
+                "-r" | "--rng" => rng = value(&arg, args.next())?.parse()?,
+                "--headless" => {
+                    headless.get_or_insert_with(headless::Options::default);
+                }
+                "--instructions" => {
+                    let options = headless.get_or_insert_with(headless::Options::default);
+                    options.stop.instructions = Some(parse_number(&arg, args.next())?);
+                }
+                "--frames" => {
+                    let options = headless.get_or_insert_with(headless::Options::default);
+                    options.stop.frames = Some(parse_number(&arg, args.next())?);
+                }
+                "--until-pc" => {
+                    let options = headless.get_or_insert_with(headless::Options::default);
+                    options.stop.pc = Some(parse_address(&arg, args.next())?);
+                }
+                "--until-opcode" => {
+                    let options = headless.get_or_insert_with(headless::Options::default);
+                    options.stop.opcode = Some(parse_address(&arg, args.next())?);
+                }
+                "--input" => {
+                    let options = headless.get_or_insert_with(headless::Options::default);
+                    options.input = Some(PathBuf::from(value(&arg, args.next())?));
+                }
+                "--dump" => {
+                    let options = headless.get_or_insert_with(headless::Options::default);
+                    options.dumps.push(parse_range(&arg, args.next())?);
+                    options.json = true;
+                }
+                "--json" => headless.get_or_insert_with(headless::Options::default).json = true,
+                "--screenshot" => {
+                    let path = PathBuf::from(value(&arg, args.next())?);
+                    Format::from_path(&path)?;
+                    headless
+                        .get_or_insert_with(headless::Options::default)
+                        .screenshot = Some(path);
+                }
+                "--video" => video = Some(PathBuf::from(value(&arg, args.next())?)),
+                "--scale" => {
+                    image_style.scale = parse_number(&arg, args.next())?;
+                    if image_style.scale == 0 {
+                        return Err(format!("'{}' must be at least 1", arg));
+                    }
+                }
+                "--colors" => image_style.palette = value(&arg, args.next())?.parse()?,
+                "-l" | "--load-state" => {
+                    load_state = Some(PathBuf::from(value(&arg, args.next())?))
+                }
+                "--record" => record = Some(PathBuf::from(value(&arg, args.next())?)),
+                "--play" => play = Some(PathBuf::from(value(&arg, args.next())?)),
+                "-d" | "--debug" => debug = true,
+                "-b" | "--break" => {
+                    breakpoints.push(parse_address(&arg, args.next())?);
+                    debug = true;
+                }
+                _ if arg.starts_with('-') => return Err(format!("unknown option '{}'", arg)),
+                _ if rom.is_some() => return Err(format!("unexpected argument '{}'", arg)),
+                _ => rom = Some(PathBuf::from(arg)),
+            }
+        }
+
+        let rom = rom.ok_or_else(|| "no ROM given".to_string())?;
+
+        let headless_input = headless.as_ref().is_some_and(|h| h.input.is_some());
+        let conflicts = [
+            (headless.is_some() && debug, "'--debug'", "'--headless'"),
+            (record.is_some() && play.is_some(), "'--record'", "'--play'"),
+            (
+                record.is_some() && headless.is_some(),
+                "'--record'",
+                "'--headless'",
+            ),
+            (play.is_some() && headless_input, "'--play'", "'--input'"),
+            (
+                (record.is_some() || play.is_some()) && debug,
+                "'--debug'",
+                "movies",
+            ),
+            (
+                (record.is_some() || play.is_some()) && load_state.is_some(),
+                "'--load-state'",
+                "movies",
+            ),
+        ];
+        if let Some((_, first, second)) = conflicts.iter().find(|(conflict, _, _)| *conflict) {
+            return Err(format!("{} can't be used with {}", first, second));
+        }
+
+        if audio == Some(Audio::Pcm) && headless.is_none() {
+            return Err("'--audio pcm' writes to stdout, which needs '--headless'".to_string());
+        }
+
+        Ok(Command::Run(Box::new(Args {
+            rom,
+            instructions_per_frame,
+            quirks,
+            mode,
+            key_map,
+            hold_timeout,
+            render,
+            audio,
+            tone,
+            image_style,
+            video,
+            seed,
+            rng,
+            headless,
+            debug,
+            breakpoints,
+            load_state,
+            record,
+            play,
+        })))
+    }
+
+    fn parse_disasm<I: Iterator<Item = String>>(mut args: I) -> Result<Command, String> {
+        let mut rom = None;
+        let mut mode = Mode::Chip8;
+
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "-h" | "--help" => return Ok(Command::Help),
+                "-m" | "--mode" => mode = value(&arg, args.next())?.parse()?,
+                _ if arg.starts_with('-') => return Err(format!("unknown option '{}'", arg)),
+                _ if rom.is_some() => return Err(format!("unexpected argument '{}'", arg)),
+                _ => rom = Some(PathBuf::from(arg)),
+            }
+        }
+
+        let rom = rom.ok_or_else(|| "no ROM given".to_string())?;
+
+        Ok(Command::Disasm { rom, mode })
+    }
+
+    fn parse_asm<I: Iterator<Item = String>>(mut args: I) -> Result<Command, String> {
+        let mut source = None;
+        let mut output = None;
+
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "-h" | "--help" => return Ok(Command::Help),
+                "-o" | "--output" => output = Some(PathBuf::from(value(&arg, args.next())?)),
+                _ if arg.starts_with('-') => return Err(format!("unknown option '{}'", arg)),
+                _ if source.is_some() => return Err(format!("unexpected argument '{}'", arg)),
+                _ => source = Some(PathBuf::from(arg)),
+            }
+        }
+
+        let source: PathBuf = source.ok_or_else(|| "no source file given".to_string())?;
+        let output = output.unwrap_or_else(|| source.with_extension("ch8"));
+
+        Ok(Command::Asm { source, output })
+    }
+
+    fn parse_keys<I: Iterator<Item = String>>(mut args: I) -> Result<Command, String> {
+        let mut key_map = None;
+        let mut rom = None;
+
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "-h" | "--help" => return Ok(Command::Help),
+                "-k" | "--keymap" => key_map = Some(PathBuf::from(value(&arg, args.next())?)),
+                _ if arg.starts_with('-') => return Err(format!("unknown option '{}'", arg)),
+                _ if rom.is_some() => return Err(format!("unexpected argument '{}'", arg)),
+                _ => rom = Some(PathBuf::from(arg)),
+            }
+        }
+
+        Ok(Command::Keys { key_map, rom })
+    }
+}
+
+fn value(option: &str, value: Option<String>) -> Result<String, String> {
+    value.ok_or_else(|| format!("'{}' expects a value", option))
+}
+
+fn parse_number<T: std::str::FromStr>(option: &str, raw: Option<String>) -> Result<T, String> {
+    let raw = value(option, raw)?;
+    raw.parse()
+        .map_err(|_| format!("'{}' expects a number, got '{}'", option, raw))
+}
+
+fn parse_address(option: &str, raw: Option<String>) -> Result<u16, String> {
+    let raw = value(option, raw)?;
+    let digits = raw.trim_start_matches("0x").trim_start_matches("0X");
+
+    u16::from_str_radix(digits, 16)
+        .map_err(|_| format!("'{}' expects a hex address, got '{}'", option, raw))
+}
+
+/// A memory range written as `<hex address>:<decimal length>`.
+fn parse_range(option: &str, raw: Option<String>) -> Result<(u16, u16), String> {
+    let raw = value(option, raw)?;
+    let error = || format!("'{}' expects ADDR:LEN, got '{}'", option, raw);
+
+    let mut parts = raw.splitn(2, ':');
+    let address = parse_address(option, parts.next().map(str::to_string)).map_err(|_| error())?;
+    let len = parts
+        .next()
+        .and_then(|len| len.parse().ok())
+        .ok_or_else(error)?;
+
+    Ok((address, len))
+}
+
+impl Args {
+    /// Where the save state hotkeys write to and read from.
+    pub fn state_path(&self) -> PathBuf {
+        self.load_state
+            .clone()
+            .unwrap_or_else(|| self.rom.with_extension("state"))
+    }
+}
+
+/// Read a program from disk, making sure it fits in the memory of `mode`.
+pub fn read_rom(path: &Path, mode: Mode) -> Result<Vec<u8>, String> {
+    let bytes =
+        fs::read(path).map_err(|e| format!("could not read '{}': {}", path.display(), e))?;
+
+    if bytes.is_empty() {
+        return Err(format!("'{}' is empty", path.display()));
+    }
+
+    if bytes.len() > mode.max_program_size() {
+        return Err(format!(
+            "'{}' is {} bytes, only {} bytes fit in {} memory above 0x200",
+            path.display(),
+            bytes.len(),
+            mode.max_program_size(),
+            mode
+        ));
+    }
+
+    Ok(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<Command, String> {
+        Command::parse(args.iter().map(|a| a.to_string()))
+    }
+
+    #[test]
+    fn parses_rom_with_defaults() {
+        let expected = Command::Run(Box::new(Args {
+            rom: PathBuf::from("pong.ch8"),
+            instructions_per_frame: DEFAULT_IPF,
+            quirks: Profile::Classic,
+            mode: Mode::Chip8,
+            key_map: None,
+            hold_timeout: DEFAULT_HOLD_TIMEOUT,
+            render: RenderMode::HalfBlock,
+            audio: None,
+            tone: Tone::default(),
+            image_style: image_file::Style::default(),
+            video: None,
+            seed: None,
+            rng: Algorithm::SplitMix,
+            headless: None,
+            debug: false,
+            breakpoints: Vec::new(),
+            load_state: None,
+            record: None,
+            play: None,
+        }));
+
+        assert_eq!(parse(&["pong.ch8"]), Ok(expected));
+    }
+
+    #[test]
+    fn parses_options() {
+        let expected = Command::Run(Box::new(Args {
+            rom: PathBuf::from("race.ch8"),
+            instructions_per_frame: 12,
+            quirks: Profile::CosmacVip,
+            mode: Mode::XoChip,
+            key_map: Some(PathBuf::from("keys.txt")),
+            hold_timeout: Duration::from_millis(400),
+            render: RenderMode::Braille,
+            audio: Some(Audio::Wav(PathBuf::from("race.wav"))),
+            tone: Tone {
+                frequency: 880.0,
+                volume: 0.5,
+            },
+            image_style: image_file::Style {
+                scale: 2,
+                palette: "112233,ffffff".parse().unwrap(),
+            },
+            video: Some(PathBuf::from("race.gif")),
+            seed: Some(42),
+            rng: Algorithm::SplitMix,
+            headless: Some(headless::Options::default()),
+            debug: false,
+            breakpoints: Vec::new(),
+            load_state: None,
+            record: None,
+            play: None,
+        }));
+
+        let parsed = parse(&[
+            "--headless",
+            "-k",
+            "keys.txt",
+            "--hold",
+            "400",
+            "--render",
+            "braille",
+            "--audio",
+            "wav:race.wav",
+            "--tone",
+            "880",
+            "--volume",
+            "50",
+            "--scale",
+            "2",
+            "--colors",
+            "112233,ffffff",
+            "--video",
+            "race.gif",
+            "race.ch8",
+            "--seed",
+            "42",
+            "--rng",
+            "splitmix",
+            "--clock",
+            "720",
+            "--quirks",
+            "vip",
+            "-m",
+            "xochip",
+        ]);
+
+        assert_eq!(parsed, Ok(expected));
+    }
+
+    #[test]
+    fn picks_state_path() {
+        let args = |extra: &[&str]| match parse(&[&["games/pong.ch8"], extra].concat()) {
+            Ok(Command::Run(args)) => args,
+            other => panic!("unexpected {:?}", other),
+        };
+
+        assert_eq!(args(&[]).state_path(), PathBuf::from("games/pong.state"));
+        assert_eq!(
+            args(&["--load-state", "level2.state"]).state_path(),
+            PathBuf::from("level2.state")
+        );
+    }
+
+    #[test]
+    fn parses_breakpoints() {
+        match parse(&["race.ch8", "-b", "2a0", "--break", "0x300"]) {
+            Ok(Command::Run(args)) => {
+                assert!(args.debug);
+                assert_eq!(args.breakpoints, [0x2A0, 0x300]);
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+
+        assert!(parse(&["race.ch8", "-b", "zz"]).is_err());
+        assert!(parse(&["race.ch8", "--debug", "--headless"]).is_err());
+    }
+
+    #[test]
+    fn parses_headless_options() {
+        let expected = headless::Options {
+            stop: headless::StopAt {
+                instructions: None,
+                frames: Some(600),
+                pc: Some(0x2A0),
+                opcode: Some(0x00FD),
+            },
+            input: Some(PathBuf::from("keys.script")),
+            dumps: vec![(0x300, 16), (0xF00, 2)],
+            json: true,
+            screenshot: Some(PathBuf::from("end.png")),
+        };
+
+        let parsed = parse(&[
+            "race.ch8",
+            "--frames",
+            "600",
+            "--until-pc",
+            "2a0",
+            "--until-opcode",
+            "00FD",
+            "--input",
+            "keys.script",
+            "--dump",
+            "300:16",
+            "--dump",
+            "0xF00:2",
+            "--screenshot",
+            "end.png",
+        ]);
+
+        match parsed {
+            Ok(Command::Run(args)) => assert_eq!(args.headless, Some(expected)),
+            other => panic!("unexpected {:?}", other),
+        }
+
+        assert!(parse(&["race.ch8", "--dump", "300"]).is_err());
+        assert!(parse(&["race.ch8", "--dump", "300:lots"]).is_err());
+        assert!(parse(&["race.ch8", "--screenshot", "end.gif"]).is_err());
+        assert!(parse(&["race.ch8", "--scale", "0"]).is_err());
+        assert!(parse(&["race.ch8", "--colors", "red,blue"]).is_err());
+        assert!(parse(&["race.ch8", "--frames", "600", "--debug"]).is_err());
+    }
+
+    #[test]
+    fn parses_disasm() {
+        let expected = Command::Disasm {
+            rom: PathBuf::from("pong.ch8"),
+            mode: Mode::XoChip,
+        };
+
+        assert_eq!(parse(&["disasm", "-m", "xochip", "pong.ch8"]), Ok(expected));
+        assert!(parse(&["disasm", "--ipf", "5", "pong.ch8"]).is_err());
+    }
+
+    #[test]
+    fn parses_asm() {
+        let expected = Command::Asm {
+            source: PathBuf::from("tests/keypad.asm"),
+            output: PathBuf::from("tests/keypad.ch8"),
+        };
+
+        assert_eq!(parse(&["asm", "tests/keypad.asm"]), Ok(expected));
+        assert_eq!(
+            parse(&["asm", "-o", "out.ch8", "keypad.asm"]),
+            Ok(Command::Asm {
+                source: PathBuf::from("keypad.asm"),
+                output: PathBuf::from("out.ch8"),
+            })
+        );
+        assert!(parse(&["asm"]).is_err());
+    }
+
+    #[test]
+    fn parses_keys() {
+        assert_eq!(
+            parse(&["keys", "-k", "keys.conf", "pong.ch8"]),
+            Ok(Command::Keys {
+                key_map: Some(PathBuf::from("keys.conf")),
+                rom: Some(PathBuf::from("pong.ch8")),
+            })
+        );
+        assert_eq!(
+            parse(&["keys"]),
+            Ok(Command::Keys {
+                key_map: None,
+                rom: None,
+            })
+        );
+    }
+
+    #[test]
+    fn help_wins() {
+        assert_eq!(parse(&["pong.ch8", "--help"]), Ok(Command::Help));
+    }
+
+    #[test]
+    fn rejects_bad_input() {
+        assert!(parse(&[]).is_err());
+        assert!(parse(&["a.ch8", "b.ch8"]).is_err());
+        assert!(parse(&["a.ch8", "--ipf"]).is_err());
+        assert!(parse(&["a.ch8", "--ipf", "fast"]).is_err());
+        assert!(parse(&["a.ch8", "--ipf", "0"]).is_err());
+        assert!(parse(&["a.ch8", "--frobnicate"]).is_err());
+        assert!(parse(&["a.ch8", "--quirks", "amiga"]).is_err());
+        assert!(parse(&["a.ch8", "--mode", "megachip"]).is_err());
+        assert!(parse(&["a.ch8", "--rng", "dice"]).is_err());
+        assert!(parse(&["a.ch8", "--audio", "wav:"]).is_err());
+        assert!(parse(&["a.ch8", "--volume", "101"]).is_err());
+        assert!(parse(&["a.ch8", "--tone", "0"]).is_err());
+        assert_eq!(
+            parse(&["a.ch8", "--audio", "pcm"]),
+            Err("'--audio pcm' writes to stdout, which needs '--headless'".to_string())
+        );
+        assert!(parse(&["a.ch8", "--play", "a.movie", "--record", "b.movie"]).is_err());
+        assert_eq!(
+            parse(&["a.ch8", "--record", "a.movie", "-l", "a.state"]),
+            Err("'--load-state' can't be used with movies".to_string())
+        );
+    }
+
+    #[test]
+    fn rejects_missing_rom_file() {
+        let err = read_rom(Path::new("does-not-exist.ch8"), Mode::Chip8).unwrap_err();
+
+        assert!(err.starts_with("could not read 'does-not-exist.ch8'"));
+    }
+
+    #[test]
+    fn rejects_empty_rom() {
+        let path = std::env::temp_dir().join("chip8rust-empty.ch8");
+        fs::write(&path, []).unwrap();
+
+        let err = read_rom(&path, Mode::Chip8).unwrap_err();
+
+        assert_eq!(err, format!("'{}' is empty", path.display()));
+    }
+
+    #[test]
+    fn rejects_oversized_rom() {
+        let path = std::env::temp_dir().join("chip8rust-oversized.ch8");
+        fs::write(&path, vec![0; Mode::Chip8.max_program_size() + 1]).unwrap();
+
+        let err = read_rom(&path, Mode::Chip8).unwrap_err();
+        assert!(err.ends_with("is 3585 bytes, only 3584 bytes fit in chip8 memory above 0x200"));
+
+        assert!(read_rom(&path, Mode::XoChip).is_ok());
+    }
+
+    #[test]
+    fn reads_rom() {
+        assert_eq!(
+            read_rom(Path::new("pong.ch8"), Mode::Chip8).unwrap().len(),
+            246
+        );
+    }
+}
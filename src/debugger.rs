@@ -0,0 +1,358 @@
+use crate::display::HIRES_HEIGHT;
+use crate::error::Chip8Error;
+use crate::opcode::{decode, Instruction};
+use crate::random::Random;
+use crate::{Chip, KeyWait, Stack};
+use std::collections::{BTreeSet, VecDeque};
+
+/// Instructions `step_back` can undo.
+pub const HISTORY_LENGTH: usize = 4096;
+
+/// Bytes from I on that one instruction can store, all 16 registers.
+const MAX_STORE: usize = 16;
+
+/// What one instruction can change, saved before it runs so `step_back` can put it back.
+#[derive(Debug)]
+struct Undo {
+    v: [u8; 16],
+    i: u16,
+    program_counter: u16,
+    stack: Stack,
+    stack_pointer: u8,
+    delay_timer: u8,
+    sound_timer: u8,
+    key_wait: Option<KeyWait>,
+    rng: Random,
+    rpl_flags: [u8; 16],
+    exited: bool,
+    audio_pattern: [u8; 16],
+    pitch: u8,
+
+    /// Memory from I on, as far as an instruction stores registers.
+    memory: Vec<u8>,
+    hires: bool,
+    selected_planes: u8,
+
+    /// The screen rows the instruction can change, with both planes of each.
+    rows: Vec<(usize, u128, u128)>,
+}
+
+impl Undo {
+    fn save(chip: &Chip) -> Result<Self, Chip8Error> {
+        let screen = &chip.screen;
+        let rows: Vec<usize> = match decode(chip.decode_opcode()?) {
+            Instruction::Draw { y, n, .. } => {
+                let top = usize::from(chip.v[y]) % screen.height();
+                let count = if n == 0 { 16 } else { usize::from(n) };
+                (top..top + count)
+                    .map(|row| row % screen.height())
+                    .collect()
+            }
+            Instruction::Clear
+            | Instruction::ScrollDown { .. }
+            | Instruction::ScrollUp { .. }
+            | Instruction::ScrollRight
+            | Instruction::ScrollLeft
+            | Instruction::LowRes
+            | Instruction::HighRes => (0..HIRES_HEIGHT).collect(),
+            _ => Vec::new(),
+        };
+
+        let start = usize::from(chip.i).min(chip.memory.len());
+        let end = (start + MAX_STORE).min(chip.memory.len());
+
+        Ok(Undo {
+            v: std::array::from_fn(|register| chip.v[register as u8]),
+            i: chip.i,
+            program_counter: chip.program_counter.get(),
+            stack: chip.stack,
+            stack_pointer: chip.stack_pointer,
+            delay_timer: chip.delay_timer,
+            sound_timer: chip.sound_timer,
+            key_wait: chip.key_wait,
+            rng: chip.rng.clone(),
+            rpl_flags: chip.rpl_flags,
+            exited: chip.exited,
+            audio_pattern: chip.audio_pattern,
+            pitch: chip.pitch,
+            memory: chip.memory[start..end].to_vec(),
+            hires: screen.hires,
+            selected_planes: screen.selected_planes,
+            rows: rows
+                .into_iter()
+                .map(|row| (row, screen.contents[row], screen.second_plane[row]))
+                .collect(),
+        })
+    }
+
+    fn restore(self, chip: &mut Chip) {
+        for (register, value) in self.v.iter().enumerate() {
+            chip.v[register as u8] = *value;
+        }
+        let start = usize::from(self.i).min(chip.memory.len());
+        chip.memory[start..start + self.memory.len()].copy_from_slice(&self.memory);
+        chip.i = self.i;
+        chip.program_counter.set(self.program_counter);
+        chip.stack = self.stack;
+        chip.stack_pointer = self.stack_pointer;
+        chip.delay_timer = self.delay_timer;
+        chip.sound_timer = self.sound_timer;
+        chip.key_wait = self.key_wait;
+        chip.rng = self.rng;
+        chip.rpl_flags = self.rpl_flags;
+        chip.exited = self.exited;
+        chip.audio_pattern = self.audio_pattern;
+        chip.pitch = self.pitch;
+
+        let screen = &mut chip.screen;
+        screen.hires = self.hires;
+        screen.selected_planes = self.selected_planes;
+        for (row, first, second) in self.rows {
+            screen.contents[row] = first;
+            screen.second_plane[row] = second;
+        }
+        screen.should_redraw = true;
+    }
+}
+
+/// Execution control on top of `Chip::step`: pausing, single steps and breakpoints.
+#[derive(Debug)]
+pub struct Debugger {
+    pub paused: bool,
+
+    /// Addresses execution pauses at, before the instruction there runs.
+    pub breakpoints: BTreeSet<u16>,
+
+    /// Return address and stack depth of the `2NNN` being stepped over.
+    step_over: Option<(u16, u8)>,
+
+    /// How to undo each of the last executed instructions, the newest last.
+    history: VecDeque<Undo>,
+}
+
+impl Debugger {
+    pub fn new() -> Self {
+        Debugger {
+            paused: false,
+            breakpoints: BTreeSet::new(),
+            step_over: None,
+            history: VecDeque::new(),
+        }
+    }
+
+    pub fn toggle_breakpoint(&mut self, address: u16) {
+        if !self.breakpoints.remove(&address) {
+            self.breakpoints.insert(address);
+        }
+    }
+
+    pub fn pause(&mut self) {
+        self.paused = true;
+        self.step_over = None;
+    }
+
+    pub fn resume(&mut self) {
+        self.paused = false;
+    }
+
+    /// Run a single instruction and stay paused.
+    pub fn step(&mut self, chip: &mut Chip) -> Result<(), Chip8Error> {
+        self.pause();
+        self.execute(chip)
+    }
+
+    /// Undo the last instruction, returns `false` once the history is used up.
+    pub fn step_back(&mut self, chip: &mut Chip) -> bool {
+        self.pause();
+        match self.history.pop_back() {
+            Some(undo) => {
+                undo.restore(chip);
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn execute(&mut self, chip: &mut Chip) -> Result<(), Chip8Error> {
+        if chip.exited {
+            return Ok(());
+        }
+
+        // Steps while halted on `FX0A` only check the keypad, undoing the `FX0A` itself
+        // goes back to before the wait.
+        if chip.waiting_for_key() {
+            return chip.step();
+        }
+
+        let undo = Undo::save(chip)?;
+        if let Err(e) = chip.step() {
+            // Nothing to undo if the instruction failed.
+            undo.restore(chip);
+            return Err(e);
+        }
+
+        self.history.push_back(undo);
+        if self.history.len() > HISTORY_LENGTH {
+            self.history.pop_front();
+        }
+
+        Ok(())
+    }
+
+    /// Like `step`, but a `2NNN` call runs until it returns.
+    pub fn step_over(&mut self, chip: &mut Chip) -> Result<(), Chip8Error> {
+        let opcode = chip.decode_opcode()?;
+
+        match decode(opcode) {
+            Instruction::Call { .. } => {
+                let pc = chip.program_counter.get();
+                self.step_over = Some((pc.wrapping_add(2), chip.stack_pointer));
+                self.paused = false;
+                Ok(())
+            }
+            _ => self.step(chip),
+        }
+    }
+
+    /// Run up to `instructions` instructions, stopping early at a breakpoint, once a
+    /// step-over is done or when the CPU halts waiting for a key.
+    pub fn run(&mut self, chip: &mut Chip, instructions: u32) -> Result<(), Chip8Error> {
+        for _ in 0..instructions {
+            if self.paused || chip.exited {
+                break;
+            }
+
+            self.execute(chip)?;
+
+            if chip.waiting_for_key() {
+                break;
+            }
+
+            let pc = chip.program_counter.get();
+            if self.breakpoints.contains(&pc) || self.step_over == Some((pc, chip.stack_pointer)) {
+                self.pause();
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl Default for Debugger {
+    fn default() -> Self {
+        Debugger::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn prepare(program: &[u8]) -> Chip {
+        let mut chip = Chip::new();
+        chip.load_program(program).unwrap();
+        chip
+    }
+
+    #[test]
+    fn stops_at_breakpoint() {
+        let mut chip = prepare(&[0x60, 0x01, 0x61, 0x02, 0x62, 0x03, 0x12, 0x06]);
+        let mut debugger = Debugger::new();
+        debugger.toggle_breakpoint(0x204);
+
+        debugger.run(&mut chip, 10).unwrap();
+
+        assert!(debugger.paused);
+        assert_eq!(chip.program_counter, 0x204);
+        assert_eq!(chip.v[2], 0);
+
+        debugger.resume();
+        debugger.run(&mut chip, 1).unwrap();
+
+        assert_eq!(chip.v[2], 3);
+    }
+
+    #[test]
+    fn steps_over_call() {
+        // CALL 0x206, LD V1, 2, JP 0x204, LD V0, 1, RET
+        let mut chip = prepare(&[0x22, 0x06, 0x61, 0x02, 0x12, 0x04, 0x60, 0x01, 0x00, 0xEE]);
+        let mut debugger = Debugger::new();
+        debugger.pause();
+
+        debugger.step_over(&mut chip).unwrap();
+        debugger.run(&mut chip, 10).unwrap();
+
+        assert!(debugger.paused);
+        assert_eq!(chip.program_counter, 0x202);
+        assert_eq!(chip.v[0], 1);
+        assert_eq!(chip.v[1], 0);
+
+        debugger.step_over(&mut chip).unwrap();
+
+        assert!(debugger.paused);
+        assert_eq!(chip.program_counter, 0x204);
+    }
+
+    #[test]
+    fn steps_back() {
+        let mut chip = prepare(&[0x60, 0x01, 0xA3, 0x00, 0xF0, 0x55, 0x00, 0xE0]);
+        let mut debugger = Debugger::new();
+
+        debugger.run(&mut chip, 3).unwrap();
+        assert_eq!(chip.memory[0x300], 1);
+
+        assert!(debugger.step_back(&mut chip));
+        assert_eq!(chip.memory[0x300], 0);
+        assert_eq!(chip.i, 0x300);
+        assert_eq!(chip.program_counter, 0x204);
+
+        assert!(debugger.step_back(&mut chip));
+        assert!(debugger.step_back(&mut chip));
+        assert_eq!(chip.v[0], 0);
+        assert_eq!(chip.program_counter, 0x200);
+
+        assert!(!debugger.step_back(&mut chip));
+    }
+
+    #[test]
+    fn steps_back_over_drawing() {
+        // LD V1, 30; LD F, V0; DRW V0, V1, 5; CLS
+        let mut chip = prepare(&[0x61, 0x1E, 0xF0, 0x29, 0xD0, 0x15, 0x00, 0xE0]);
+        let mut debugger = Debugger::new();
+
+        debugger.run(&mut chip, 3).unwrap();
+        assert!(chip.screen.is_lit(0, 31));
+        assert!(chip.screen.is_lit(0, 0));
+
+        debugger.run(&mut chip, 1).unwrap();
+        assert!(!chip.screen.is_lit(0, 0));
+
+        assert!(debugger.step_back(&mut chip));
+        assert!(chip.screen.is_lit(0, 31));
+        assert!(chip.screen.is_lit(0, 0));
+
+        // The sprite wrapped around to the top rows.
+        assert!(debugger.step_back(&mut chip));
+        assert!(!chip.screen.is_lit(0, 31));
+        assert!(!chip.screen.is_lit(0, 0));
+        assert_eq!(chip.program_counter, 0x204);
+    }
+
+    #[test]
+    fn keeps_no_history_while_waiting_for_a_key() {
+        // LD V0, 7; LD V1, K
+        let mut chip = prepare(&[0x60, 0x07, 0xF1, 0x0A]);
+        let mut debugger = Debugger::new();
+
+        debugger.step(&mut chip).unwrap();
+        for _ in 0..5 {
+            debugger.step(&mut chip).unwrap();
+        }
+        assert!(chip.waiting_for_key());
+        assert_eq!(debugger.history.len(), 2);
+
+        assert!(debugger.step_back(&mut chip));
+        assert!(!chip.waiting_for_key());
+        assert_eq!(chip.program_counter, 0x202);
+    }
+}
@@ -0,0 +1,89 @@
+use crate::mode::Mode;
+use crate::opcode::{decode, Instruction};
+use crate::PROGRAM_START;
+use std::fmt::Write;
+
+/// Listing of `program` as loaded at `PROGRAM_START`, one instruction per line with its
+/// address and raw bytes.
+///
+/// Instructions `mode` doesn't have are listed as data, like anything else that doesn't decode.
+pub fn disassemble(program: &[u8], mode: Mode) -> String {
+    let mut listing = String::new();
+    let mut offset = 0;
+
+    while offset < program.len() {
+        let address = PROGRAM_START as usize + offset;
+        let bytes = &program[offset..];
+
+        if bytes.len() == 1 {
+            writeln!(
+                listing,
+                "{:#06X}  {:02X}        DB {:#04X}",
+                address, bytes[0], bytes[0]
+            )
+            .unwrap();
+            break;
+        }
+
+        let opcode = u16::from(bytes[0]) << 8 | u16::from(bytes[1]);
+        let instruction = match decode(opcode) {
+            instruction if instruction.is_xo_chip() && mode != Mode::XoChip => {
+                Instruction::Unknown(opcode)
+            }
+            instruction => instruction,
+        };
+
+        let size = instruction.size() as usize;
+        if instruction == Instruction::LoadILong && bytes.len() >= size {
+            let long = u16::from(bytes[2]) << 8 | u16::from(bytes[3]);
+            writeln!(
+                listing,
+                "{:#06X}  {:04X}{:04X}  LD I, LONG {:#06X}",
+                address, opcode, long, long
+            )
+            .unwrap();
+        } else {
+            writeln!(
+                listing,
+                "{:#06X}  {:04X}      {}",
+                address, opcode, instruction
+            )
+            .unwrap();
+        }
+
+        offset += size;
+    }
+
+    listing
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lists_instructions() {
+        let listing = disassemble(&[0x6A, 0x02, 0x83, 0x44, 0xFF], Mode::Chip8);
+
+        assert_eq!(
+            listing,
+            "0x0200  6A02      LD VA, 0x02\n\
+             0x0202  8344      ADD V3, V4\n\
+             0x0204  FF        DB 0xFF\n"
+        );
+    }
+
+    #[test]
+    fn lists_long_load_only_for_xo_chip() {
+        let program = [0xF0, 0x00, 0xAB, 0xCD];
+
+        assert_eq!(
+            disassemble(&program, Mode::XoChip),
+            "0x0200  F000ABCD  LD I, LONG 0xABCD\n"
+        );
+        assert_eq!(
+            disassemble(&program, Mode::Chip8),
+            "0x0200  F000      DW 0xF000\n0x0202  ABCD      LD I, 0xBCD\n"
+        );
+    }
+}
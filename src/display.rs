@@ -0,0 +1,639 @@
+type Font = [u8; 80];
+
+#[rustfmt::skip]
+// credits to the http://devernay.free.fr/hacks/chip8/C8TECH10.HTM#dispcoords
+pub const FONT: Font = [
+// ****
+// *  *
+// *  *
+// *  *
+// ****
+0xF0,0x90,0x90,0x90,0xF0,
+//   * 
+//  ** 
+//   * 
+//   * 
+//  ***
+0x20,0x60,0x20,0x20,0x70,
+// ****
+//    *
+// ****
+// *   
+// ****
+0xF0,0x10,0xF0,0x80,0xF0,
+// ****
+//    *
+// ****
+//    *
+// ****
+0xF0,0x10,0xF0,0x10,0xF0,
+
+// *  *
+// *  *
+// ****
+//    *
+//    *
+0x90,0x90,0xF0,0x10,0x10,
+
+// ****
+// *   
+// ****
+//    *
+// ****
+0xF0,0x80,0xF0,0x10,0xF0,
+
+// ****
+// *   
+// ****
+// *  *
+// ****
+0xF0,0x80,0xF0,0x90,0xF0,
+
+// ****
+//    *
+//   * 
+//  *  
+//  * 
+0xF0,0x10,0x20,0x40,0x40,
+
+// ****
+// *  *
+// ****
+// *  *
+// ****
+0xF0,0x90,0xF0,0x90,0xF0,
+
+// ****
+// *  *
+// ****
+//    *
+// ****
+0xF0,0x90,0xF0,0x10,0xF0,
+
+// ****
+// *  *
+// ****
+// *  *
+// *  *
+0xF0,0x90,0xF0,0x90,0x90,
+
+// *** 
+// *  *
+// *** 
+// *  *
+// *** 
+0xE0,0x90,0xE0,0x90,0xE0,
+
+// ****
+// *   
+// *   
+// *   
+// ****
+0xF0,0x80,0x80,0x80,0xF0,
+
+// *** 
+// *  *
+// *  *
+// *  *
+// ***
+0xE0,0x90,0x90,0x90,0xE0,
+
+// ****
+// *   
+// ****
+// *   
+// ****
+0xF0,0x80,0xF0,0x80,0xF0,
+
+// ****
+// *   
+// ****
+// *   
+// * 
+0xF0,0x80,0xF0,0x80,0x80
+];
+
+/// Address of the small font in memory.
+pub const FONT_START: u16 = 0;
+
+/// SUPER-CHIP 8x10 digits, loaded right after the small font.
+pub const BIG_FONT_START: u16 = FONT_START + 80;
+
+#[rustfmt::skip]
+// credits to Octo, https://github.com/JohnEarnest/Octo
+pub const BIG_FONT: [u8; 160] = [
+/* 0 */ 0xFF,0xFF,0xC3,0xC3,0xC3,0xC3,0xC3,0xC3,0xFF,0xFF,
+/* 1 */ 0x18,0x78,0x78,0x18,0x18,0x18,0x18,0x18,0xFF,0xFF,
+/* 2 */ 0xFF,0xFF,0x03,0x03,0xFF,0xFF,0xC0,0xC0,0xFF,0xFF,
+/* 3 */ 0xFF,0xFF,0x03,0x03,0xFF,0xFF,0x03,0x03,0xFF,0xFF,
+/* 4 */ 0xC3,0xC3,0xC3,0xC3,0xFF,0xFF,0x03,0x03,0x03,0x03,
+/* 5 */ 0xFF,0xFF,0xC0,0xC0,0xFF,0xFF,0x03,0x03,0xFF,0xFF,
+/* 6 */ 0xFF,0xFF,0xC0,0xC0,0xFF,0xFF,0xC3,0xC3,0xFF,0xFF,
+/* 7 */ 0xFF,0xFF,0x03,0x03,0x06,0x0C,0x18,0x18,0x18,0x18,
+/* 8 */ 0xFF,0xFF,0xC3,0xC3,0xFF,0xFF,0xC3,0xC3,0xFF,0xFF,
+/* 9 */ 0xFF,0xFF,0xC3,0xC3,0xFF,0xFF,0x03,0x03,0xFF,0xFF,
+/* A */ 0x7E,0xFF,0xC3,0xC3,0xC3,0xFF,0xFF,0xC3,0xC3,0xC3,
+/* B */ 0xFC,0xFC,0xC3,0xC3,0xFC,0xFC,0xC3,0xC3,0xFC,0xFC,
+/* C */ 0x3C,0xFF,0xC3,0xC0,0xC0,0xC0,0xC0,0xC3,0xFF,0x3C,
+/* D */ 0xFC,0xFE,0xC3,0xC3,0xC3,0xC3,0xC3,0xC3,0xFE,0xFC,
+/* E */ 0xFF,0xFF,0xC0,0xC0,0xFF,0xFF,0xC0,0xC0,0xFF,0xFF,
+/* F */ 0xFF,0xFF,0xC0,0xC0,0xFF,0xFF,0xC0,0xC0,0xC0,0xC0
+];
+
+pub const LORES_WIDTH: usize = 64;
+pub const LORES_HEIGHT: usize = 32;
+pub const HIRES_WIDTH: usize = 128;
+pub const HIRES_HEIGHT: usize = 64;
+
+/// One line of pixels, pixel `x` is bit `width - 1 - x`.
+type Row = u128;
+
+/// One bitplane, only the first `height()` rows and the lowest `width()` bits of each are in use.
+pub type Plane = [Row; HIRES_HEIGHT];
+
+#[derive(Debug)]
+pub struct Display {
+    /// First bitplane, the only one plain CHIP-8 and SUPER-CHIP programs use.
+    pub contents: Plane,
+
+    /// XO-CHIP second bitplane, a pixel's color is `first | second << 1`.
+    pub second_plane: Plane,
+
+    /// Bitmask of the planes drawing, clearing and scrolling act on, set by XO-CHIP `FN01`.
+    pub selected_planes: u8,
+
+    /// SUPER-CHIP 128x64 mode, 64x32 otherwise.
+    pub hires: bool,
+
+    pub should_redraw: bool,
+}
+
+impl Display {
+    pub fn new() -> Self {
+        Display {
+            contents: [0; HIRES_HEIGHT],
+            second_plane: [0; HIRES_HEIGHT],
+            selected_planes: 1,
+            hires: false,
+            should_redraw: false,
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        if self.hires {
+            HIRES_WIDTH
+        } else {
+            LORES_WIDTH
+        }
+    }
+
+    pub fn height(&self) -> usize {
+        if self.hires {
+            HIRES_HEIGHT
+        } else {
+            LORES_HEIGHT
+        }
+    }
+
+    /// Color of a pixel, 0 is background and 1-3 are the bitplanes combined.
+    pub fn pixel(&self, x: usize, y: usize) -> u8 {
+        let shift = self.width() - 1 - x;
+        let first = (self.contents[y] >> shift) & 1;
+        let second = (self.second_plane[y] >> shift) & 1;
+
+        (first | second << 1) as u8
+    }
+
+    pub fn is_lit(&self, x: usize, y: usize) -> bool {
+        self.pixel(x, y) != 0
+    }
+
+    /// Switch between 64x32 and 128x64, all planes are cleared.
+    pub fn set_hires(&mut self, hires: bool) {
+        self.hires = hires;
+        self.contents = [0; HIRES_HEIGHT];
+        self.second_plane = [0; HIRES_HEIGHT];
+        self.should_redraw = true;
+    }
+
+    pub fn selected_plane_count(&self) -> usize {
+        self.selected().len()
+    }
+
+    fn selected(&self) -> Vec<usize> {
+        (0..2)
+            .filter(|plane| self.selected_planes & (1 << plane) != 0)
+            .collect()
+    }
+
+    fn plane_mut(&mut self, plane: usize) -> &mut Plane {
+        match plane {
+            0 => &mut self.contents,
+            _ => &mut self.second_plane,
+        }
+    }
+
+    /// XOR the sprite onto the screen at (`x`, `y`), parts going off an edge wrap around
+    /// to the opposite one. Returns `true` if any lit pixel got turned off.
+    pub fn draw(&mut self, x: u8, y: u8, sprites: &[&u8]) -> bool {
+        let sprite: Vec<u8> = sprites.iter().map(|row| **row).collect();
+        self.draw_sprite(x, y, &sprite, false, false)
+    }
+
+    /// Like `draw`, but parts going off the right or bottom edge are cut off.
+    /// The starting position still wraps around.
+    pub fn draw_clipped(&mut self, x: u8, y: u8, sprites: &[&u8]) -> bool {
+        let sprite: Vec<u8> = sprites.iter().map(|row| **row).collect();
+        self.draw_sprite(x, y, &sprite, false, true)
+    }
+
+    /// Draw a sprite onto every selected plane, `sprite` holds the data for each plane in turn.
+    ///
+    /// `large` sprites are SUPER-CHIP 16x16 ones, stored as 16 rows of two bytes.
+    pub fn draw_sprite(&mut self, x: u8, y: u8, sprite: &[u8], large: bool, clip: bool) -> bool {
+        self.should_redraw = true;
+
+        let planes = self.selected();
+        if planes.is_empty() {
+            return false;
+        }
+
+        let mut collision = false;
+        let per_plane = sprite.len() / planes.len();
+
+        for (plane, data) in planes.into_iter().zip(sprite.chunks(per_plane.max(1))) {
+            collision |= if large {
+                let rows = data
+                    .chunks(2)
+                    .map(|row| u16::from(row[0]) << 8 | u16::from(*row.get(1).unwrap_or(&0)));
+                self.blit(plane, x, y, rows, 16, clip)
+            } else {
+                self.blit(plane, x, y, data.iter().map(|row| u16::from(*row)), 8, clip)
+            };
+        }
+
+        collision
+    }
+
+    fn blit<I>(
+        &mut self,
+        plane: usize,
+        x: u8,
+        y: u8,
+        rows: I,
+        sprite_width: usize,
+        clip: bool,
+    ) -> bool
+    where
+        I: Iterator<Item = u16>,
+    {
+        let mut collision = false;
+
+        let width = self.width();
+        let height = self.height();
+        let mask = self.row_mask();
+
+        let x = x as usize % width;
+        let y = y as usize % height;
+
+        let plane = self.plane_mut(plane);
+
+        for (row_idx, part) in rows.enumerate() {
+            let row_idx = row_idx + y;
+            if clip && row_idx >= height {
+                break;
+            }
+
+            let row_idx = row_idx % height;
+            let row = plane[row_idx];
+
+            let at_origin = Row::from(part) << (width - sprite_width);
+            let mut part = at_origin >> x;
+            if !clip && x > 0 {
+                part |= (at_origin << (width - x)) & mask;
+            }
+
+            if row & part != 0 {
+                collision = true;
+            }
+
+            plane[row_idx] = row ^ part
+        }
+
+        collision
+    }
+
+    /// `00CN` - move everything down by `n` pixels.
+    pub fn scroll_down(&mut self, n: usize) {
+        let height = self.height();
+        let n = n.min(height);
+
+        for plane in self.selected() {
+            let plane = self.plane_mut(plane);
+            plane.copy_within(0..height - n, n);
+            for row in plane[..n].iter_mut() {
+                *row = 0;
+            }
+        }
+
+        self.should_redraw = true;
+    }
+
+    /// XO-CHIP `00DN` - move everything up by `n` pixels.
+    pub fn scroll_up(&mut self, n: usize) {
+        let height = self.height();
+        let n = n.min(height);
+
+        for plane in self.selected() {
+            let plane = self.plane_mut(plane);
+            plane.copy_within(n..height, 0);
+            for row in plane[height - n..height].iter_mut() {
+                *row = 0;
+            }
+        }
+
+        self.should_redraw = true;
+    }
+
+    /// `00FB` - move everything right by 4 pixels.
+    pub fn scroll_right(&mut self) {
+        for plane in self.selected() {
+            for row in self.plane_mut(plane).iter_mut() {
+                *row >>= 4;
+            }
+        }
+
+        self.should_redraw = true;
+    }
+
+    /// `00FC` - move everything left by 4 pixels.
+    pub fn scroll_left(&mut self) {
+        let mask = self.row_mask();
+        for plane in self.selected() {
+            for row in self.plane_mut(plane).iter_mut() {
+                *row = (*row << 4) & mask;
+            }
+        }
+
+        self.should_redraw = true;
+    }
+
+    fn row_mask(&self) -> Row {
+        if self.width() == HIRES_WIDTH {
+            !0
+        } else {
+            (1 << self.width()) - 1
+        }
+    }
+
+    /// Clear the selected planes.
+    pub fn clear(&mut self) {
+        for plane in self.selected() {
+            *self.plane_mut(plane) = [0; HIRES_HEIGHT];
+        }
+
+        self.should_redraw = true;
+    }
+}
+
+impl Default for Display {
+    fn default() -> Self {
+        Display::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn does_not_set_collision() {
+        let mut disp = Display::new();
+
+        disp.contents[0] = 0b_00001111;
+        let sprite = vec![&0b_11110000];
+
+        let col = disp.draw(0, 0, &sprite);
+
+        assert!(!col);
+    }
+
+    #[test]
+    fn sets_collision() {
+        let mut disp = Display::new();
+
+        disp.contents[0] = 0b_00001111 << 56;
+        let sprite = vec![&0b_0000_1001];
+
+        let col = disp.draw(0, 0, &sprite);
+
+        assert!(col);
+    }
+
+    #[test]
+    fn y_moves_to_the_other_side() {
+        let mut disp = Display::new();
+
+        disp.contents[31] = 0b_0000_0000;
+
+        let sprite = vec![&0b0000_1111, &0b0000_1111];
+
+        disp.draw(0, 31, &sprite);
+
+        println!("{:?}", disp);
+        assert_eq!(disp.contents[0], 0b0000_1111 << 56);
+    }
+
+    #[test]
+    fn x_moves_to_the_other_side() {
+        let mut disp = Display::new();
+
+        disp.draw(60, 0, &[&0b1111_1111]);
+
+        assert_eq!(disp.contents[0], 0xF000_0000_0000_000F);
+    }
+
+    #[test]
+    fn clipped_sprite_is_cut_off() {
+        let mut disp = Display::new();
+
+        disp.draw_clipped(60, 31, &[&0b1111_1111, &0b1111_1111]);
+
+        assert_eq!(disp.contents[31], 0x0000_0000_0000_000F);
+        assert_eq!(disp.contents[0], 0);
+    }
+
+    #[test]
+    fn clipped_sprite_position_wraps() {
+        let mut disp = Display::new();
+
+        disp.draw_clipped(64 + 8, 32 + 1, &[&0b1000_0001]);
+
+        assert_eq!(disp.contents[1], 0b1000_0001 << 48);
+    }
+
+    #[test]
+    fn hires_uses_whole_row() {
+        let mut disp = Display::new();
+        disp.set_hires(true);
+
+        disp.draw(124, 63, &[&0b1111_1111, &0b1000_0001]);
+
+        assert_eq!(disp.contents[63], 0xF000_0000_0000_0000_0000_0000_0000_000F);
+        assert_eq!(disp.contents[0], 0x1000_0000_0000_0000_0000_0000_0000_0008);
+        assert!(disp.is_lit(127, 63));
+        assert!(disp.is_lit(0, 63));
+        assert!(!disp.is_lit(4, 63));
+    }
+
+    #[test]
+    fn draws_large_sprite() {
+        let mut disp = Display::new();
+        disp.set_hires(true);
+
+        let mut sprite = [0; 32];
+        sprite[0] = 0x80;
+        sprite[31] = 0x01;
+
+        let col = disp.draw_sprite(120, 60, &sprite, true, true);
+
+        assert!(!col);
+        assert!(disp.is_lit(120, 60));
+        assert_eq!(disp.contents.iter().filter(|row| **row != 0).count(), 1);
+    }
+
+    #[test]
+    fn scrolls() {
+        let mut disp = Display::new();
+        disp.draw(0, 0, &[&0b1000_0001]);
+
+        disp.scroll_down(2);
+        assert_eq!(disp.contents[0], 0);
+        assert_eq!(disp.contents[2], 0x8100_0000_0000_0000);
+
+        disp.scroll_right();
+        assert_eq!(disp.contents[2], 0x0810_0000_0000_0000);
+
+        disp.scroll_left();
+        disp.scroll_left();
+        assert_eq!(disp.contents[2], 0x1000_0000_0000_0000);
+    }
+
+    #[test]
+    fn draws_on_selected_planes() {
+        let mut disp = Display::new();
+        disp.selected_planes = 0b11;
+
+        disp.draw_sprite(0, 0, &[0b1100_0000, 0b1010_0000], false, false);
+
+        assert_eq!(disp.pixel(0, 0), 3);
+        assert_eq!(disp.pixel(1, 0), 1);
+        assert_eq!(disp.pixel(2, 0), 2);
+        assert_eq!(disp.pixel(3, 0), 0);
+
+        disp.selected_planes = 0b10;
+        disp.scroll_up(0);
+        disp.clear();
+
+        assert_eq!(disp.pixel(0, 0), 1);
+        assert_eq!(disp.pixel(2, 0), 0);
+    }
+
+    #[test]
+    fn scrolls_up() {
+        let mut disp = Display::new();
+        disp.draw(0, 5, &[&0xFF]);
+
+        disp.scroll_up(3);
+
+        assert!(disp.is_lit(0, 2));
+        assert!(!disp.is_lit(0, 5));
+    }
+
+    #[test]
+    fn prints_symbol() {
+        let mut disp = Display::new();
+        // Eyes
+        disp.draw(12, 5, &[&0xFF, &0xFF, &0xFF]);
+        disp.draw(32, 5, &[&0xFF, &0xFF, &0xFF]);
+        // Mouth, left to right
+        disp.draw(12, 13, &[&0xF0, &0xF0, &0xFF]);
+        disp.draw(20, 15, &[&0xFF, &0xFF]);
+        disp.draw(28, 15, &[&0xF0, &0xF0]);
+        disp.draw(32, 13, &[&0x0F, &0x0F, &0xFF]);
+
+        let expected_screen: Vec<u64> = vec![
+            //            0    4    8    12   16   20   24   28   32   36   40   44   48   52   56   60
+            /*0*/
+            0b0000_0000_0000_0000_0000_0000_0000_0000_0000_0000_0000_0000_0000_0000_0000_0000,
+            /*1*/
+            0b0000_0000_0000_0000_0000_0000_0000_0000_0000_0000_0000_0000_0000_0000_0000_0000,
+            /*2*/
+            0b0000_0000_0000_0000_0000_0000_0000_0000_0000_0000_0000_0000_0000_0000_0000_0000,
+            /*3*/
+            0b0000_0000_0000_0000_0000_0000_0000_0000_0000_0000_0000_0000_0000_0000_0000_0000,
+            /*4*/
+            0b0000_0000_0000_0000_0000_0000_0000_0000_0000_0000_0000_0000_0000_0000_0000_0000,
+            /*5*/
+            0b0000_0000_0000_1111_1111_0000_0000_0000_1111_1111_0000_0000_0000_0000_0000_0000,
+            /*6*/
+            0b0000_0000_0000_1111_1111_0000_0000_0000_1111_1111_0000_0000_0000_0000_0000_0000,
+            /*7*/
+            0b0000_0000_0000_1111_1111_0000_0000_0000_1111_1111_0000_0000_0000_0000_0000_0000,
+            /*8*/
+            0b0000_0000_0000_0000_0000_0000_0000_0000_0000_0000_0000_0000_0000_0000_0000_0000,
+            /*9*/
+            0b0000_0000_0000_0000_0000_0000_0000_0000_0000_0000_0000_0000_0000_0000_0000_0000,
+            /*10*/
+            0b0000_0000_0000_0000_0000_0000_0000_0000_0000_0000_0000_0000_0000_0000_0000_0000,
+            /*11*/
+            0b0000_0000_0000_0000_0000_0000_0000_0000_0000_0000_0000_0000_0000_0000_0000_0000,
+            /*12*/
+            0b0000_0000_0000_0000_0000_0000_0000_0000_0000_0000_0000_0000_0000_0000_0000_0000,
+            /*13*/
+            0b0000_0000_0000_1111_0000_0000_0000_0000_0000_1111_0000_0000_0000_0000_0000_0000,
+            /*14*/
+            0b0000_0000_0000_1111_0000_0000_0000_0000_0000_1111_0000_0000_0000_0000_0000_0000,
+            /*15*/
+            0b0000_0000_0000_1111_1111_1111_1111_1111_1111_1111_0000_0000_0000_0000_0000_0000,
+            /*16*/
+            0b0000_0000_0000_0000_0000_1111_1111_1111_0000_0000_0000_0000_0000_0000_0000_0000,
+            /*17*/
+            0b0000_0000_0000_0000_0000_0000_0000_0000_0000_0000_0000_0000_0000_0000_0000_0000,
+            /*18*/
+            0b0000_0000_0000_0000_0000_0000_0000_0000_0000_0000_0000_0000_0000_0000_0000_0000,
+            /*19*/
+            0b0000_0000_0000_0000_0000_0000_0000_0000_0000_0000_0000_0000_0000_0000_0000_0000,
+            /*20*/
+            0b0000_0000_0000_0000_0000_0000_0000_0000_0000_0000_0000_0000_0000_0000_0000_0000,
+            /*21*/
+            0b0000_0000_0000_0000_0000_0000_0000_0000_0000_0000_0000_0000_0000_0000_0000_0000,
+            /*22*/
+            0b0000_0000_0000_0000_0000_0000_0000_0000_0000_0000_0000_0000_0000_0000_0000_0000,
+            /*23*/
+            0b0000_0000_0000_0000_0000_0000_0000_0000_0000_0000_0000_0000_0000_0000_0000_0000,
+            /*24*/
+            0b0000_0000_0000_0000_0000_0000_0000_0000_0000_0000_0000_0000_0000_0000_0000_0000,
+            /*25*/
+            0b0000_0000_0000_0000_0000_0000_0000_0000_0000_0000_0000_0000_0000_0000_0000_0000,
+            /*26*/
+            0b0000_0000_0000_0000_0000_0000_0000_0000_0000_0000_0000_0000_0000_0000_0000_0000,
+            /*27*/
+            0b0000_0000_0000_0000_0000_0000_0000_0000_0000_0000_0000_0000_0000_0000_0000_0000,
+            /*28*/
+            0b0000_0000_0000_0000_0000_0000_0000_0000_0000_0000_0000_0000_0000_0000_0000_0000,
+            /*29*/
+            0b0000_0000_0000_0000_0000_0000_0000_0000_0000_0000_0000_0000_0000_0000_0000_0000,
+            /*30*/
+            0b0000_0000_0000_0000_0000_0000_0000_0000_0000_0000_0000_0000_0000_0000_0000_0000,
+            /*31*/
+            0b0000_0000_0000_0000_0000_0000_0000_0000_0000_0000_0000_0000_0000_0000_0000_0000,
+        ];
+
+        let screen: Vec<u64> = disp.contents[..LORES_HEIGHT]
+            .iter()
+            .map(|row| *row as u64)
+            .collect();
+        assert_eq!(screen, expected_screen);
+    }
+}
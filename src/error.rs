@@ -0,0 +1,99 @@
+use super::opcode::Opcode;
+use std::error::Error;
+use std::fmt;
+
+/// Everything that can stop a program, `pc` is the address of the faulting instruction.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Chip8Error {
+    /// The program does not fit in memory above `PROGRAM_START`.
+    ProgramTooLarge {
+        size: usize,
+    },
+
+    /// The program counter points past the end of memory.
+    PcOutOfRange {
+        pc: u16,
+    },
+
+    UnknownOpcode {
+        pc: u16,
+        opcode: Opcode,
+    },
+
+    /// `2NNN` with every stack entry already in use.
+    StackOverflow {
+        pc: u16,
+        opcode: Opcode,
+    },
+
+    /// `00EE` outside of a subroutine.
+    StackUnderflow {
+        pc: u16,
+        opcode: Opcode,
+    },
+
+    /// The instruction reads or writes `address`, which is past the end of memory.
+    MemoryOutOfBounds {
+        pc: u16,
+        opcode: Opcode,
+        address: usize,
+    },
+}
+
+impl Chip8Error {
+    /// Address of the faulting instruction, if the error came from one.
+    pub fn pc(&self) -> Option<u16> {
+        match *self {
+            Chip8Error::ProgramTooLarge { .. } => None,
+            Chip8Error::PcOutOfRange { pc }
+            | Chip8Error::UnknownOpcode { pc, .. }
+            | Chip8Error::StackOverflow { pc, .. }
+            | Chip8Error::StackUnderflow { pc, .. }
+            | Chip8Error::MemoryOutOfBounds { pc, .. } => Some(pc),
+        }
+    }
+
+    /// The faulting instruction, if the error came from one.
+    pub fn opcode(&self) -> Option<Opcode> {
+        match *self {
+            Chip8Error::ProgramTooLarge { .. } | Chip8Error::PcOutOfRange { .. } => None,
+            Chip8Error::UnknownOpcode { opcode, .. }
+            | Chip8Error::StackOverflow { opcode, .. }
+            | Chip8Error::StackUnderflow { opcode, .. }
+            | Chip8Error::MemoryOutOfBounds { opcode, .. } => Some(opcode),
+        }
+    }
+}
+
+impl fmt::Display for Chip8Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Chip8Error::ProgramTooLarge { size } => {
+                write!(f, "program of {} bytes does not fit in memory", size)
+            }
+            Chip8Error::PcOutOfRange { pc } => {
+                write!(f, "program counter {:#05X} is out of memory", pc)
+            }
+            Chip8Error::UnknownOpcode { pc, opcode } => {
+                write!(f, "unknown opcode {:04X} at {:#05X}", opcode, pc)
+            }
+            Chip8Error::StackOverflow { pc, opcode } => {
+                write!(f, "stack overflow by {:04X} at {:#05X}", opcode, pc)
+            }
+            Chip8Error::StackUnderflow { pc, opcode } => {
+                write!(f, "stack underflow by {:04X} at {:#05X}", opcode, pc)
+            }
+            Chip8Error::MemoryOutOfBounds {
+                pc,
+                opcode,
+                address,
+            } => write!(
+                f,
+                "{:04X} at {:#05X} accesses memory out of bounds at {:#X}",
+                opcode, pc, address
+            ),
+        }
+    }
+}
+
+impl Error for Chip8Error {}
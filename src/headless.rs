@@ -0,0 +1,504 @@
+use chip8rust::movie::Movie;
+use chip8rust::Chip;
+use std::error::Error;
+use std::fmt::Write;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Settings for running without a terminal, picked on the command line.
+#[derive(Debug, Default, PartialEq)]
+pub struct Options {
+    pub stop: StopAt,
+
+    /// Scripted keypad input, see `Script::parse`.
+    pub input: Option<PathBuf>,
+
+    /// Memory ranges included in the JSON dump, as start address and length.
+    pub dumps: Vec<(u16, u16)>,
+
+    /// Print the final state as JSON instead of the screen as text.
+    pub json: bool,
+
+    /// Also save the final screen as an image, see `image_file::save`.
+    pub screenshot: Option<PathBuf>,
+}
+
+/// Extra reasons to stop a run, besides the program halting or exiting.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct StopAt {
+    pub instructions: Option<u64>,
+    pub frames: Option<u64>,
+
+    /// Stop before executing the instruction at this address.
+    pub pc: Option<u16>,
+
+    /// Stop before executing this opcode.
+    pub opcode: Option<u16>,
+}
+
+impl StopAt {
+    /// Whether the run is to go on for a given number of instructions or frames, also
+    /// through a program idling with a jump to itself.
+    fn counts(&self) -> bool {
+        self.instructions.is_some() || self.frames.is_some()
+    }
+}
+
+/// Why a run stopped.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Stop {
+    /// The program jumped to itself.
+    Halted,
+    /// `FX0A` with no scripted input left to wake it.
+    WaitingForKey,
+    Exited,
+    Instructions,
+    Frames,
+    Pc,
+    Opcode,
+}
+
+impl Stop {
+    pub fn name(self) -> &'static str {
+        match self {
+            Stop::Halted => "halted",
+            Stop::WaitingForKey => "waiting_for_key",
+            Stop::Exited => "exited",
+            Stop::Instructions => "instructions",
+            Stop::Frames => "frames",
+            Stop::Pc => "pc",
+            Stop::Opcode => "opcode",
+        }
+    }
+}
+
+/// How far a run got.
+#[derive(Debug, PartialEq)]
+pub struct Outcome {
+    pub stop: Stop,
+    pub instructions: u64,
+    pub frames: u64,
+}
+
+/// Keypad state over time, each entry lists the keys held from its frame on.
+#[derive(Debug, Default, PartialEq)]
+pub struct Script {
+    changes: Vec<(u64, [bool; 16])>,
+}
+
+impl Script {
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let source = fs::read_to_string(path)
+            .map_err(|e| format!("could not read '{}': {}", path.display(), e))?;
+
+        Script::parse(&source).map_err(|e| format!("{}:{}", path.display(), e))
+    }
+
+    /// One `<frame> <keys>` line per change, keys being hex digits separated by spaces
+    /// or `-` for none, e.g. `30 4 6`. Empty lines and lines starting with `#` are ignored.
+    pub fn parse(source: &str) -> Result<Self, String> {
+        let mut changes: Vec<(u64, [bool; 16])> = Vec::new();
+
+        for (line_idx, line) in source.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let error = |message: &str| format!("{}: {}", line_idx + 1, message);
+
+            let mut parts = line.split_whitespace();
+            let frame: u64 = parts
+                .next()
+                .and_then(|frame| frame.parse().ok())
+                .ok_or_else(|| error("expected '<frame> <keys>'"))?;
+
+            if changes.last().is_some_and(|(last, _)| *last >= frame) {
+                return Err(error("frames must be in increasing order"));
+            }
+
+            let mut keys = [false; 16];
+            for key in parts.filter(|key| *key != "-") {
+                let key = u8::from_str_radix(key, 16)
+                    .ok()
+                    .filter(|key| *key <= 0xF)
+                    .ok_or_else(|| error("keypad key must be a hex digit 0-F"))?;
+                keys[key as usize] = true;
+            }
+
+            changes.push((frame, keys));
+        }
+
+        Ok(Script { changes })
+    }
+
+    /// Script holding the keys of every frame of `movie`.
+    pub fn from_movie(movie: &Movie) -> Self {
+        let mut changes: Vec<(u64, [bool; 16])> = Vec::new();
+
+        for (frame, mask) in movie.frames.iter().enumerate() {
+            let mut keys = [false; 16];
+            for (key, pressed) in keys.iter_mut().enumerate() {
+                *pressed = mask & 1 << key != 0;
+            }
+
+            if changes.last().map(|(_, last)| last) != Some(&keys) {
+                changes.push((frame as u64, keys));
+            }
+        }
+
+        Script { changes }
+    }
+
+    /// Keys held during `frame`, `None` if the script doesn't say.
+    fn keys_at(&self, frame: u64) -> Option<&[bool; 16]> {
+        self.changes
+            .iter()
+            .rev()
+            .find(|(start, _)| *start <= frame)
+            .map(|(_, keys)| keys)
+    }
+
+    fn changes_after(&self, frame: u64) -> bool {
+        self.changes.iter().any(|(start, _)| *start > frame)
+    }
+}
+
+/// Run the chip without a terminal until it stops making progress, e.g. jumps to itself
+/// at the end of a program, waits for a key or exits, or until `stop` says so. A jump to
+/// itself doesn't end runs limited to a number of instructions or frames, and waiting for
+/// a key doesn't end runs limited to a number of frames.
+/// `each_frame` is called after every frame, to play or record it.
+pub fn run<F>(
+    chip: &mut Chip,
+    instructions_per_frame: u32,
+    stop: &StopAt,
+    script: &Script,
+    mut each_frame: F,
+) -> Result<Outcome, Box<dyn Error>>
+where
+    F: FnMut(&Chip) -> Result<(), Box<dyn Error>>,
+{
+    let mut outcome = Outcome {
+        stop: Stop::Halted,
+        instructions: 0,
+        frames: 0,
+    };
+
+    outcome.stop = 'frames: loop {
+        if stop.frames.is_some_and(|frames| outcome.frames >= frames) {
+            break Stop::Frames;
+        }
+
+        if let Some(keys) = script.keys_at(outcome.frames) {
+            for (key, pressed) in keys.iter().enumerate() {
+                chip.keyboard.set_state(key as u8, *pressed);
+            }
+        }
+
+        for _ in 0..instructions_per_frame {
+            if chip.exited {
+                break 'frames Stop::Exited;
+            }
+
+            // Steps while halted on FX0A only check the keypad, they aren't instructions.
+            let waiting = chip.waiting_for_key();
+            let program_counter = chip.program_counter.get();
+
+            if !waiting {
+                if stop.instructions == Some(outcome.instructions) {
+                    break 'frames Stop::Instructions;
+                }
+
+                if stop.pc == Some(program_counter) {
+                    break 'frames Stop::Pc;
+                }
+
+                if stop.opcode == Some(chip.decode_opcode()?) {
+                    break 'frames Stop::Opcode;
+                }
+            }
+
+            chip.step()?;
+            if !waiting {
+                outcome.instructions += 1;
+            }
+
+            // Only frames still pass with nothing left to press, so a run limited to a
+            // number of instructions alone would wait forever.
+            if chip.waiting_for_key() {
+                if script.changes_after(outcome.frames) || stop.frames.is_some() {
+                    break;
+                }
+                break 'frames Stop::WaitingForKey;
+            }
+
+            if !waiting && !stop.counts() && chip.program_counter == program_counter {
+                break 'frames Stop::Halted;
+            }
+        }
+
+        chip.update_timers();
+        each_frame(chip)?;
+        outcome.frames += 1;
+    };
+
+    Ok(outcome)
+}
+
+/// The screen as text, one line per row.
+pub fn screen(chip: &Chip) -> String {
+    let mut output = String::new();
+    for y in 0..chip.screen.height() {
+        for x in 0..chip.screen.width() {
+            output.push(if chip.screen.is_lit(x, y) { '█' } else { ' ' });
+        }
+        output.push('\n');
+    }
+
+    output
+}
+
+/// The machine state after a run as a JSON object, screen rows are strings of pixel colors.
+pub fn json(chip: &Chip, outcome: &Outcome, dumps: &[(u16, u16)]) -> String {
+    let list = |items: Vec<String>| format!("[{}]", items.join(", "));
+    let numbers = |numbers: &[u8]| list(numbers.iter().map(u8::to_string).collect());
+
+    let registers: Vec<u8> = (0..16).map(|idx| chip.v[idx]).collect();
+    let stack = list(
+        chip.stack[..chip.stack_pointer as usize]
+            .iter()
+            .map(u16::to_string)
+            .collect(),
+    );
+    let keys = list(
+        (0..16)
+            .filter(|key| chip.keyboard.is_pressed(*key))
+            .map(|key| key.to_string())
+            .collect(),
+    );
+
+    let memory = list(
+        dumps
+            .iter()
+            .map(|(start, len)| {
+                let start = *start as usize;
+                let end = (start + *len as usize).min(chip.memory.len());
+                let bytes = chip.memory.get(start..end).unwrap_or_default();
+                format!("{{\"address\": {}, \"bytes\": {}}}", start, numbers(bytes))
+            })
+            .collect(),
+    );
+
+    let rows = (0..chip.screen.height())
+        .map(|y| {
+            let row: String = (0..chip.screen.width())
+                .map(|x| char::from(b'0' + chip.screen.pixel(x, y)))
+                .collect();
+            format!("\"{}\"", row)
+        })
+        .collect::<Vec<_>>()
+        .join(",\n      ");
+
+    let mut out = String::new();
+    writeln!(out, "{{").unwrap();
+    writeln!(out, "  \"stop\": \"{}\",", outcome.stop.name()).unwrap();
+    writeln!(out, "  \"instructions\": {},", outcome.instructions).unwrap();
+    writeln!(out, "  \"frames\": {},", outcome.frames).unwrap();
+    writeln!(out, "  \"pc\": {},", chip.program_counter.get()).unwrap();
+    writeln!(out, "  \"i\": {},", chip.i).unwrap();
+    writeln!(out, "  \"v\": {},", numbers(&registers)).unwrap();
+    writeln!(out, "  \"stack\": {},", stack).unwrap();
+    writeln!(out, "  \"delay_timer\": {},", chip.delay_timer).unwrap();
+    writeln!(out, "  \"sound_timer\": {},", chip.sound_timer).unwrap();
+    writeln!(out, "  \"keys\": {},", keys).unwrap();
+    writeln!(out, "  \"memory\": {},", memory).unwrap();
+    writeln!(out, "  \"screen\": {{").unwrap();
+    writeln!(out, "    \"width\": {},", chip.screen.width()).unwrap();
+    writeln!(out, "    \"height\": {},", chip.screen.height()).unwrap();
+    writeln!(out, "    \"rows\": [\n      {}\n    ]", rows).unwrap();
+    writeln!(out, "  }}").unwrap();
+    writeln!(out, "}}").unwrap();
+
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn prepare(program: &[u8]) -> Chip {
+        let mut chip = Chip::new();
+        chip.load_program(program).unwrap();
+        chip
+    }
+
+    #[test]
+    fn stops_when_halted() {
+        let mut chip = prepare(&[0x60, 0x01, 0x12, 0x02]);
+
+        let outcome = run(
+            &mut chip,
+            10,
+            &StopAt::default(),
+            &Script::default(),
+            |_| Ok(()),
+        )
+        .unwrap();
+
+        assert_eq!(outcome.stop, Stop::Halted);
+        assert_eq!(outcome.instructions, 2);
+        assert_eq!(chip.v[0], 1);
+    }
+
+    #[test]
+    fn counts_through_jumps_to_itself() {
+        let mut chip = prepare(&[0x60, 0x01, 0x12, 0x02]);
+        let stop = StopAt {
+            frames: Some(3),
+            ..StopAt::default()
+        };
+
+        let outcome = run(&mut chip, 10, &stop, &Script::default(), |_| Ok(())).unwrap();
+
+        assert_eq!(outcome.stop, Stop::Frames);
+        assert_eq!(outcome.instructions, 30);
+    }
+
+    #[test]
+    fn stops_at_limits() {
+        // ADD V0, 1; JP 0x200
+        let program = [0x70, 0x01, 0x12, 0x00];
+        let script = Script::default();
+
+        let mut chip = prepare(&program);
+        let stop = StopAt {
+            instructions: Some(7),
+            ..StopAt::default()
+        };
+        let outcome = run(&mut chip, 10, &stop, &script, |_| Ok(())).unwrap();
+        assert_eq!(outcome.stop, Stop::Instructions);
+        assert_eq!(chip.v[0], 4);
+
+        let mut chip = prepare(&program);
+        let stop = StopAt {
+            frames: Some(3),
+            ..StopAt::default()
+        };
+        let outcome = run(&mut chip, 10, &stop, &script, |_| Ok(())).unwrap();
+        assert_eq!(outcome.stop, Stop::Frames);
+        assert_eq!(outcome.instructions, 30);
+
+        let mut chip = prepare(&program);
+        let stop = StopAt {
+            opcode: Some(0x1200),
+            ..StopAt::default()
+        };
+        let outcome = run(&mut chip, 10, &stop, &script, |_| Ok(())).unwrap();
+        assert_eq!(outcome.stop, Stop::Opcode);
+        assert_eq!(chip.program_counter, 0x202);
+    }
+
+    #[test]
+    fn feeds_scripted_input() {
+        // LD V0, K; JP 0x202
+        let mut chip = prepare(&[0xF0, 0x0A, 0x12, 0x02]);
+        let script = Script::parse("# press 7 a bit later\n0 -\n5 7\n8 -\n").unwrap();
+
+        let outcome = run(&mut chip, 10, &StopAt::default(), &script, |_| Ok(())).unwrap();
+
+        assert_eq!(outcome.stop, Stop::Halted);
+        assert_eq!(outcome.frames, 8);
+        assert_eq!(chip.v[0], 7);
+    }
+
+    #[test]
+    fn plays_movies() {
+        let mut movie = Movie::new(chip8rust::movie::Header {
+            rom_hash: 0,
+            mode: Default::default(),
+            quirks: chip8rust::quirks::Profile::Modern,
+            rng: Default::default(),
+            seed: 0,
+            instructions_per_frame: 10,
+        });
+        movie.frames = vec![0, 0, 0x80, 0x80, 0];
+
+        let script = Script::from_movie(&movie);
+
+        assert_eq!(script.changes.len(), 3);
+        assert_eq!(script.keys_at(1).unwrap(), &[false; 16]);
+        assert!(script.keys_at(3).unwrap()[7]);
+        assert!(!script.changes_after(4));
+    }
+
+    #[test]
+    fn gives_up_waiting_without_input() {
+        let mut chip = prepare(&[0xF0, 0x0A]);
+
+        let outcome = run(
+            &mut chip,
+            10,
+            &StopAt::default(),
+            &Script::default(),
+            |_| Ok(()),
+        )
+        .unwrap();
+
+        assert_eq!(outcome.stop, Stop::WaitingForKey);
+    }
+
+    #[test]
+    fn counts_frames_while_waiting() {
+        // LD DT, V0; LD V1, K
+        let mut chip = prepare(&[0xF0, 0x15, 0xF1, 0x0A]);
+        chip.v[0] = 10;
+        let stop = StopAt {
+            frames: Some(4),
+            ..StopAt::default()
+        };
+
+        let outcome = run(&mut chip, 10, &stop, &Script::default(), |_| Ok(())).unwrap();
+
+        assert_eq!(outcome.stop, Stop::Frames);
+        assert_eq!(outcome.frames, 4);
+        assert_eq!(outcome.instructions, 2);
+        assert_eq!(chip.delay_timer, 6);
+    }
+
+    #[test]
+    fn reports_bad_script_lines() {
+        assert_eq!(
+            Script::parse("5 1\n3 2").unwrap_err(),
+            "2: frames must be in increasing order"
+        );
+        assert_eq!(
+            Script::parse("5 G").unwrap_err(),
+            "1: keypad key must be a hex digit 0-F"
+        );
+        assert_eq!(
+            Script::parse("soon 1").unwrap_err(),
+            "1: expected '<frame> <keys>'"
+        );
+    }
+
+    #[test]
+    fn dumps_json() {
+        let mut chip = prepare(&[0x60, 0x2A, 0xA2, 0x00, 0xD0, 0x11, 0x12, 0x06]);
+        let outcome = run(
+            &mut chip,
+            10,
+            &StopAt::default(),
+            &Script::default(),
+            |_| Ok(()),
+        )
+        .unwrap();
+
+        let json = json(&chip, &outcome, &[(0x200, 2)]);
+
+        assert!(json.contains("\"stop\": \"halted\","));
+        assert!(json.contains("\"v\": [42, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0],"));
+        assert!(json.contains("\"memory\": [{\"address\": 512, \"bytes\": [96, 42]}],"));
+        assert!(json.contains(&format!("\"{}011{}\"", "0".repeat(42), "0".repeat(19))));
+    }
+}
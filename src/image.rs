@@ -0,0 +1,496 @@
+use crate::display::Display;
+use std::collections::HashMap;
+use std::fmt;
+use std::io::{self, Write};
+use std::str::FromStr;
+
+/// RGB colors of pixel values 0 (background) to 3, plain CHIP-8 only uses the first two.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Palette(pub [[u8; 3]; 4]);
+
+impl Default for Palette {
+    /// White on black, with the XO-CHIP colors the terminal uses.
+    fn default() -> Self {
+        Palette([[0, 0, 0], [0xFF, 0xFF, 0xFF], [0x80, 0x80, 0], [0x80, 0, 0]])
+    }
+}
+
+impl fmt::Display for Palette {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let colors: Vec<String> = self
+            .0
+            .iter()
+            .map(|[r, g, b]| format!("{:02x}{:02x}{:02x}", r, g, b))
+            .collect();
+        f.write_str(&colors.join(","))
+    }
+}
+
+impl FromStr for Palette {
+    type Err = String;
+
+    /// Comma separated hex colors, background first, e.g. `000000,ffffff`.
+    /// Colors left out keep their default.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut palette = Palette::default();
+        let colors: Vec<&str> = s.split(',').map(str::trim).collect();
+
+        if colors.len() < 2 || colors.len() > palette.0.len() {
+            return Err(format!(
+                "expected 2 to 4 comma separated colors, background first, got '{}'",
+                s
+            ));
+        }
+
+        for (color, text) in palette.0.iter_mut().zip(colors) {
+            let hex = text.trim_start_matches('#');
+            let rgb = u32::from_str_radix(hex, 16)
+                .ok()
+                .filter(|_| hex.len() == 6)
+                .ok_or_else(|| format!("expected a color like 'ff8000', got '{}'", text))?;
+            *color = [(rgb >> 16) as u8, (rgb >> 8) as u8, rgb as u8];
+        }
+
+        Ok(palette)
+    }
+}
+
+/// The screen as pixel values, every pixel repeated `scale` times in both directions.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Frame {
+    pub width: usize,
+    pub height: usize,
+
+    /// Pixel values 0 to 3, row by row.
+    pub pixels: Vec<u8>,
+}
+
+impl Frame {
+    pub fn capture(display: &Display, scale: usize) -> Self {
+        let width = display.width() * scale;
+        let height = display.height() * scale;
+
+        let mut pixels = Vec::with_capacity(width * height);
+        for y in 0..height {
+            for x in 0..width {
+                pixels.push(display.pixel(x / scale, y / scale));
+            }
+        }
+
+        Frame {
+            width,
+            height,
+            pixels,
+        }
+    }
+
+    fn rows(&self) -> impl Iterator<Item = &[u8]> {
+        self.pixels.chunks(self.width)
+    }
+
+    /// The frame as a 2-bit indexed PNG file.
+    pub fn png(&self, palette: &Palette) -> Vec<u8> {
+        let mut header = Vec::new();
+        header.extend((self.width as u32).to_be_bytes());
+        header.extend((self.height as u32).to_be_bytes());
+        // Bit depth 2, indexed color, default compression, filtering and no interlacing.
+        header.extend([2, 3, 0, 0, 0]);
+
+        // Every row starts with filter type 0, none.
+        let mut data = Vec::new();
+        for row in self.rows() {
+            data.push(0);
+            data.extend(pack(row, 2));
+        }
+
+        let mut png = b"\x89PNG\r\n\x1a\n".to_vec();
+        chunk(&mut png, b"IHDR", &header);
+        chunk(&mut png, b"PLTE", &palette.0.concat());
+        chunk(&mut png, b"IDAT", &zlib(&data));
+        chunk(&mut png, b"IEND", &[]);
+
+        png
+    }
+
+    /// The frame as a binary PBM file. PBM only knows black and white,
+    /// lit pixels are black whatever their color.
+    pub fn pbm(&self) -> Vec<u8> {
+        let mut pbm = format!("P4\n{} {}\n", self.width, self.height).into_bytes();
+
+        for row in self.rows() {
+            let lit: Vec<u8> = row.iter().map(|pixel| (*pixel != 0) as u8).collect();
+            pbm.extend(pack(&lit, 1));
+        }
+
+        pbm
+    }
+
+    /// The frame as a binary PPM file, in full color.
+    pub fn ppm(&self, palette: &Palette) -> Vec<u8> {
+        let mut ppm = format!("P6\n{} {}\n255\n", self.width, self.height).into_bytes();
+        ppm.extend(
+            self.pixels
+                .iter()
+                .flat_map(|pixel| palette.0[*pixel as usize]),
+        );
+        ppm
+    }
+}
+
+/// Animated GIF written frame by frame, one `frame` call per 60 Hz tick.
+///
+/// A frame is only written once the next different one arrives, so unchanged frames
+/// make the previous one last longer instead of repeating it.
+pub struct Gif<W: Write> {
+    out: W,
+    width: usize,
+    height: usize,
+
+    /// Frame waiting for its delay to be known.
+    pending: Option<Frame>,
+    ticks: u64,
+
+    /// Hundredths of a second the written frames last together.
+    written: u64,
+}
+
+impl<W: Write> Gif<W> {
+    /// Start a looping GIF of `width` by `height` frames in the colors of `palette`.
+    pub fn new(mut out: W, width: usize, height: usize, palette: &Palette) -> io::Result<Self> {
+        out.write_all(b"GIF89a")?;
+        out.write_all(&(width as u16).to_le_bytes())?;
+        out.write_all(&(height as u16).to_le_bytes())?;
+        // Global color table of 4 colors with 2 bits each, background color 0, square pixels.
+        out.write_all(&[0x91, 0, 0])?;
+        out.write_all(&palette.0.concat())?;
+        // Loop forever.
+        out.write_all(b"\x21\xFF\x0BNETSCAPE2.0\x03\x01\0\0\0")?;
+
+        Ok(Gif {
+            out,
+            width,
+            height,
+            pending: None,
+            ticks: 0,
+            written: 0,
+        })
+    }
+
+    /// Add the next tick, `frame` must be as large as the GIF.
+    pub fn frame(&mut self, frame: &Frame) -> io::Result<()> {
+        assert_eq!(
+            (frame.width, frame.height),
+            (self.width, self.height),
+            "GIF frames must all be the same size"
+        );
+
+        let changed = self.pending.as_ref().is_none_or(|pending| pending != frame);
+        if changed {
+            self.flush()?;
+            self.pending = Some(frame.clone());
+        }
+
+        self.ticks += 1;
+        Ok(())
+    }
+
+    /// Write the last frame and the trailer, returns the writer.
+    pub fn finish(mut self) -> io::Result<W> {
+        self.flush()?;
+        self.out.write_all(b"\x3B")?;
+        self.out.flush()?;
+        Ok(self.out)
+    }
+
+    /// Write the pending frame, which lasted until now.
+    fn flush(&mut self) -> io::Result<()> {
+        let frame = match self.pending.take() {
+            Some(pending) => pending,
+            None => return Ok(()),
+        };
+
+        // Delays are in hundredths of a second, and viewers play anything under 2 much
+        // slower. Frames lengthened to 2 are made up for by the ones after them, as each
+        // lasts until the time since the start of the recording, rounded.
+        let until = (self.ticks * 100 + 30) / 60;
+        let delay = until
+            .saturating_sub(self.written)
+            .clamp(MIN_GIF_DELAY, u64::from(u16::MAX));
+        self.written += delay;
+
+        // Graphic control extension with the delay, then an image covering the whole screen.
+        self.out.write_all(&[0x21, 0xF9, 0x04, 0])?;
+        self.out.write_all(&(delay as u16).to_le_bytes())?;
+        self.out.write_all(&[0, 0, 0x2C, 0, 0, 0, 0])?;
+        self.out.write_all(&(self.width as u16).to_le_bytes())?;
+        self.out.write_all(&(self.height as u16).to_le_bytes())?;
+        self.out.write_all(&[0, GIF_MIN_CODE_SIZE])?;
+
+        for block in lzw(&frame.pixels, GIF_MIN_CODE_SIZE).chunks(255) {
+            self.out.write_all(&[block.len() as u8])?;
+            self.out.write_all(block)?;
+        }
+        self.out.write_all(&[0])
+    }
+}
+
+/// Shortest frame delay, in hundredths of a second, that GIF viewers play as given.
+const MIN_GIF_DELAY: u64 = 2;
+
+/// Bits of the GIF pixel values, enough for 4 colors.
+const GIF_MIN_CODE_SIZE: u8 = 2;
+
+/// Largest code GIF's LZW allows, the table starts over once it's used.
+const MAX_LZW_CODE: u16 = 4095;
+
+/// GIF flavoured LZW compression of `pixels`, packed into bytes lowest bit first.
+fn lzw(pixels: &[u8], min_code_size: u8) -> Vec<u8> {
+    let clear = 1u16 << min_code_size;
+    let end = clear + 1;
+
+    let mut bits = BitWriter::default();
+    let mut table = HashMap::new();
+    let mut next = end + 1;
+    let mut code_size = min_code_size + 1;
+
+    bits.write(clear, code_size);
+
+    let mut pixels = pixels.iter();
+    let mut code = match pixels.next() {
+        Some(pixel) => u16::from(*pixel),
+        None => {
+            bits.write(end, code_size);
+            return bits.finish();
+        }
+    };
+
+    for pixel in pixels {
+        if let Some(longer) = table.get(&(code, *pixel)) {
+            code = *longer;
+            continue;
+        }
+
+        bits.write(code, code_size);
+        // Decoders widen their codes as soon as the code after this one could need it.
+        if next >= 1 << code_size && code_size < 12 {
+            code_size += 1;
+        }
+
+        if next >= MAX_LZW_CODE {
+            bits.write(clear, code_size);
+            table.clear();
+            next = end + 1;
+            code_size = min_code_size + 1;
+        } else {
+            table.insert((code, *pixel), next);
+            next += 1;
+        }
+
+        code = u16::from(*pixel);
+    }
+
+    bits.write(code, code_size);
+    if next >= 1 << code_size && code_size < 12 {
+        code_size += 1;
+    }
+    bits.write(end, code_size);
+    bits.finish()
+}
+
+#[derive(Default)]
+struct BitWriter {
+    bytes: Vec<u8>,
+    buffer: u32,
+    used: u8,
+}
+
+impl BitWriter {
+    fn write(&mut self, code: u16, size: u8) {
+        self.buffer |= u32::from(code) << self.used;
+        self.used += size;
+
+        while self.used >= 8 {
+            self.bytes.push(self.buffer as u8);
+            self.buffer >>= 8;
+            self.used -= 8;
+        }
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        if self.used > 0 {
+            self.bytes.push(self.buffer as u8);
+        }
+        self.bytes
+    }
+}
+
+/// Pack `bits` wide values into bytes, leftmost in the high bits, padding the last byte.
+fn pack(values: &[u8], bits: usize) -> Vec<u8> {
+    let per_byte = 8 / bits;
+
+    values
+        .chunks(per_byte)
+        .map(|chunk| {
+            chunk.iter().enumerate().fold(0, |byte, (idx, value)| {
+                byte | value << (8 - bits * (idx + 1))
+            })
+        })
+        .collect()
+}
+
+/// Append a PNG chunk: length, type, data and a CRC of the type and data.
+fn chunk(png: &mut Vec<u8>, kind: &[u8; 4], data: &[u8]) {
+    png.extend((data.len() as u32).to_be_bytes());
+
+    let start = png.len();
+    png.extend(kind);
+    png.extend(data);
+    let crc = crc32(&png[start..]);
+
+    png.extend(crc.to_be_bytes());
+}
+
+/// `data` in a zlib stream of uncompressed deflate blocks, which every decoder reads and
+/// needs no compressor. Screens are small enough that the size doesn't matter.
+fn zlib(data: &[u8]) -> Vec<u8> {
+    // Deflate with a 32 KiB window, no preset dictionary, header checksum to match.
+    let mut out = vec![0x78, 0x01];
+
+    let mut blocks = data.chunks(0xFFFF).peekable();
+    if blocks.peek().is_none() {
+        out.extend([1, 0, 0, 0xFF, 0xFF]);
+    }
+    while let Some(block) = blocks.next() {
+        let last = blocks.peek().is_none();
+        out.push(last as u8);
+        out.extend((block.len() as u16).to_le_bytes());
+        out.extend((!(block.len() as u16)).to_le_bytes());
+        out.extend(block);
+    }
+
+    out.extend(adler32(data).to_be_bytes());
+    out
+}
+
+fn crc32(data: &[u8]) -> u32 {
+    !data.iter().fold(!0, |crc, byte| {
+        (0..8).fold(crc ^ u32::from(*byte), |crc, _| {
+            if crc & 1 == 1 {
+                (crc >> 1) ^ 0xEDB8_8320
+            } else {
+                crc >> 1
+            }
+        })
+    })
+}
+
+fn adler32(data: &[u8]) -> u32 {
+    let (a, b) = data.iter().fold((1, 0), |(a, b), byte| {
+        let a = (a + u32::from(*byte)) % 65521;
+        (a, (b + a) % 65521)
+    });
+
+    b << 16 | a
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Lo-res screen with the top left pixel lit and the next one in color 2.
+    fn display() -> Display {
+        let mut display = Display::new();
+        display.contents[0] = 1 << 63;
+        display.second_plane[0] = 1 << 62;
+        display
+    }
+
+    #[test]
+    fn captures_scaled_frame() {
+        let frame = Frame::capture(&display(), 2);
+
+        assert_eq!((frame.width, frame.height), (128, 64));
+        assert_eq!(frame.pixels[..5], [1, 1, 2, 2, 0]);
+        assert_eq!(frame.pixels[128..133], [1, 1, 2, 2, 0]);
+        assert!(frame.pixels[256..].iter().all(|pixel| *pixel == 0));
+    }
+
+    #[test]
+    fn writes_pbm() {
+        let pbm = Frame::capture(&display(), 1).pbm();
+
+        assert!(pbm.starts_with(b"P4\n64 32\n"));
+        assert_eq!(pbm.len(), 9 + 8 * 32);
+        assert_eq!(pbm[9..11], [0b1100_0000, 0]);
+    }
+
+    #[test]
+    fn writes_png() {
+        let png = Frame::capture(&display(), 1).png(&Palette::default());
+
+        assert!(png.starts_with(b"\x89PNG\r\n\x1a\n\0\0\0\x0dIHDR\0\0\0\x40\0\0\0\x20\x02\x03"));
+        assert!(png.ends_with(b"\0\0\0\0IEND\xae\x42\x60\x82"));
+
+        // Filter byte, then pixels 1, 2, 0, 0 packed into one byte.
+        let idat = png.windows(4).position(|kind| kind == b"IDAT").unwrap();
+        assert_eq!(png[idat + 4..idat + 6], [0x78, 0x01]);
+        assert_eq!(png[idat + 11..idat + 13], [0, 0b0110_0000]);
+    }
+
+    #[test]
+    fn writes_ppm() {
+        let ppm = Frame::capture(&display(), 1).ppm(&Palette::default());
+
+        assert!(ppm.starts_with(b"P6\n64 32\n255\n"));
+        assert_eq!(ppm.len(), 13 + 64 * 32 * 3);
+        assert_eq!(ppm[13..22], [0xFF, 0xFF, 0xFF, 0x80, 0x80, 0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn compresses_like_gif() {
+        // Clear, 1, then the new codes for 1 1 and 1 1 1 in 3 bits, then 1 1 and end
+        // in 4 bits, since the next code would be 8.
+        assert_eq!(lzw(&[1; 8], 2), [0b1000_1100, 0b0110_1111, 0b0000_0101]);
+    }
+
+    #[test]
+    fn merges_unchanged_gif_frames() {
+        let blank = Frame::capture(&Display::new(), 1);
+        let lit = Frame::capture(&display(), 1);
+
+        let mut gif = Gif::new(Vec::new(), 64, 32, &Palette::default()).unwrap();
+        for frame in [&blank, &blank, &blank, &lit, &blank, &lit, &lit, &lit] {
+            gif.frame(frame).unwrap();
+        }
+        let bytes = gif.finish().unwrap();
+
+        assert!(bytes.starts_with(b"GIF89a\x40\0\x20\0\x91\0\0"));
+        assert_eq!(bytes.last(), Some(&0x3B));
+
+        // Rounded from the start: three ticks are 5 hundredths of a second and the next
+        // one 2. The one after that is 2 instead of 1, so the last three ticks get 4.
+        let delays: Vec<u16> = bytes
+            .windows(6)
+            .filter(|window| window[..4] == [0x21, 0xF9, 0x04, 0])
+            .map(|window| u16::from_le_bytes([window[4], window[5]]))
+            .collect();
+        assert_eq!(delays, [5, 2, 2, 4]);
+    }
+
+    #[test]
+    fn checksums() {
+        assert_eq!(crc32(b"IEND"), 0xAE42_6082);
+        assert_eq!(adler32(b"Wikipedia"), 0x11E6_0398);
+    }
+
+    #[test]
+    fn parses_palette() {
+        let palette: Palette = "#102030, ffffff".parse().unwrap();
+
+        assert_eq!(palette.0[0], [0x10, 0x20, 0x30]);
+        assert_eq!(palette.0[2], Palette::default().0[2]);
+        assert_eq!(palette.to_string(), "102030,ffffff,808000,800000");
+        assert!("ffffff".parse::<Palette>().is_err());
+        assert!("000000,fff".parse::<Palette>().is_err());
+    }
+}
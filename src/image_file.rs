@@ -0,0 +1,193 @@
+use chip8rust::display::{Display, HIRES_HEIGHT, HIRES_WIDTH};
+use chip8rust::image::{Frame, Gif, Palette};
+use std::fs::{self, File};
+use std::io::BufWriter;
+use std::path::{Path, PathBuf};
+
+/// How saved images look.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Style {
+    /// Image pixels per screen pixel, in both directions.
+    pub scale: usize,
+    pub palette: Palette,
+}
+
+impl Default for Style {
+    fn default() -> Self {
+        Style {
+            scale: 4,
+            palette: Palette::default(),
+        }
+    }
+}
+
+/// Image formats, picked by the file extension.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Format {
+    Png,
+    Pbm,
+}
+
+impl Format {
+    pub fn from_path(path: &Path) -> Result<Format, String> {
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some("png") => Ok(Format::Png),
+            Some("pbm") => Ok(Format::Pbm),
+            _ => Err(format!(
+                "'{}' needs a .png or .pbm extension",
+                path.display()
+            )),
+        }
+    }
+}
+
+/// Write the screen to `path`, as a PNG or PBM file depending on its extension.
+pub fn save(display: &Display, path: &Path, style: &Style) -> Result<(), String> {
+    let frame = Frame::capture(display, style.scale);
+    let bytes = match Format::from_path(path)? {
+        Format::Png => frame.png(&style.palette),
+        Format::Pbm => frame.pbm(),
+    };
+
+    fs::write(path, bytes).map_err(|e| format!("could not write '{}': {}", path.display(), e))
+}
+
+/// A recording of the screen, one image per 60 Hz frame.
+///
+/// Frames are always as large as the hi-res screen at the `Style`'s scale, lo-res
+/// pixels are doubled, so a program switching resolution doesn't change the size.
+pub struct Video {
+    path: PathBuf,
+    style: Style,
+    output: Output,
+}
+
+enum Output {
+    /// Unchanged frames are merged, see `Gif`.
+    Gif(Gif<BufWriter<File>>),
+
+    /// Every frame as `frame-000001.ppm` and so on, for turning into a video at 60 fps.
+    Ppm { frames: u64 },
+}
+
+impl Video {
+    /// Start recording to `path`, an animated GIF if it ends in `.gif` and a directory
+    /// of numbered PPM images otherwise.
+    pub fn start(path: &Path, style: &Style) -> Result<Video, String> {
+        let error = |e: std::io::Error| format!("could not write '{}': {}", path.display(), e);
+
+        let output = if path.extension().is_some_and(|ext| ext == "gif") {
+            let file = File::create(path).map_err(error)?;
+            let (width, height) = (HIRES_WIDTH * style.scale, HIRES_HEIGHT * style.scale);
+            let gif =
+                Gif::new(BufWriter::new(file), width, height, &style.palette).map_err(error)?;
+            Output::Gif(gif)
+        } else {
+            fs::create_dir_all(path).map_err(error)?;
+            Output::Ppm { frames: 0 }
+        };
+
+        Ok(Video {
+            path: path.to_path_buf(),
+            style: style.clone(),
+            output,
+        })
+    }
+
+    /// Add the screen as it is at the end of a frame.
+    pub fn frame(&mut self, display: &Display) -> Result<(), String> {
+        let frame = Frame::capture(display, hires_scale(display, &self.style));
+
+        match &mut self.output {
+            Output::Gif(gif) => gif
+                .frame(&frame)
+                .map_err(|e| format!("could not write '{}': {}", self.path.display(), e)),
+            Output::Ppm { frames } => {
+                *frames += 1;
+                let path = self.path.join(format!("frame-{:06}.ppm", frames));
+                fs::write(&path, frame.ppm(&self.style.palette))
+                    .map_err(|e| format!("could not write '{}': {}", path.display(), e))
+            }
+        }
+    }
+
+    /// Stop recording, returns where it went.
+    pub fn finish(self) -> Result<PathBuf, String> {
+        let path = self.path;
+        if let Output::Gif(gif) = self.output {
+            gif.finish()
+                .map_err(|e| format!("could not write '{}': {}", path.display(), e))?;
+        }
+
+        Ok(path)
+    }
+}
+
+/// Scale that makes `display` as large as the hi-res screen at `style`'s scale.
+fn hires_scale(display: &Display, style: &Style) -> usize {
+    style.scale * HIRES_WIDTH / display.width()
+}
+
+/// The first of `<rom>-1.<extension>`, `<rom>-2.<extension>`, ... that doesn't exist yet.
+pub fn next_path(rom: &Path, extension: &str) -> PathBuf {
+    let stem = rom
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_default();
+
+    (1..)
+        .map(|n| rom.with_file_name(format!("{}-{}.{}", stem, n, extension)))
+        .find(|path| !path.exists())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn picks_format_by_extension() {
+        assert_eq!(Format::from_path(Path::new("a.png")), Ok(Format::Png));
+        assert_eq!(Format::from_path(Path::new("a.pbm")), Ok(Format::Pbm));
+        assert_eq!(
+            Format::from_path(Path::new("a.jpg")),
+            Err("'a.jpg' needs a .png or .pbm extension".to_string())
+        );
+    }
+
+    #[test]
+    fn records_ppm_frames() {
+        let dir = std::env::temp_dir().join("chip8rust-video");
+        let _ = fs::remove_dir_all(&dir);
+        let style = Style {
+            scale: 1,
+            ..Style::default()
+        };
+
+        let mut video = Video::start(&dir, &style).unwrap();
+        let mut display = Display::new();
+        video.frame(&display).unwrap();
+        display.set_hires(true);
+        video.frame(&display).unwrap();
+        video.finish().unwrap();
+
+        for frame in ["frame-000001.ppm", "frame-000002.ppm"] {
+            let ppm = fs::read(dir.join(frame)).unwrap();
+            assert!(ppm.starts_with(b"P6\n128 64\n255\n"));
+        }
+        assert!(!dir.join("frame-000003.ppm").exists());
+    }
+
+    #[test]
+    fn numbers_screenshots() {
+        let dir = std::env::temp_dir().join("chip8rust-screenshots");
+        fs::create_dir_all(&dir).unwrap();
+        let rom = dir.join("pong.ch8");
+        let _ = fs::remove_file(dir.join("pong-1.png"));
+        let _ = fs::remove_file(dir.join("pong-2.png"));
+
+        assert_eq!(next_path(&rom, "png"), dir.join("pong-1.png"));
+        save(&Display::new(), &next_path(&rom, "png"), &Style::default()).unwrap();
+        assert_eq!(next_path(&rom, "png"), dir.join("pong-2.png"));
+    }
+}
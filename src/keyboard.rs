@@ -1,107 +1,56 @@
-use crossterm::event::KeyModifiers;
-use crossterm::event::{poll, read, Event, KeyCode, KeyEvent};
-
-use std::collections::HashMap;
-
-macro_rules! key_map {
-    ($($key:expr => $val: expr), *) => {
-        {
-            let mut map = HashMap::<KeyCode, u8>::new();
-            $(
-                map.insert(KeyCode::Char($key), $val);
-            )*
-            map
-        }
-    };
-}
-
+/// State of the 16-key hexadecimal keypad.
 #[derive(Debug)]
 pub struct Keyboard {
     keys: [bool; 0xF + 1],
-    pub mapping: HashMap<KeyCode, u8>,
 }
 
 impl Keyboard {
     pub fn new() -> Self {
         Keyboard {
             keys: [false; 0xF + 1],
-            mapping: key_map!(
-                '1' => 0x1,
-                '2' => 0x2,
-                '3' => 0x3,
-                '4' => 0xC,
-                'q' => 0x4,
-                'w' => 0x5,
-                'e' => 0x6,
-                'r' => 0xD,
-                'a' => 0x7,
-                's' => 0x8,
-                'd' => 0x9,
-                'f' => 0xe,
-                'z' => 0xA,
-                'x' => 0,
-                'c' => 0xB,
-                'v' => 0xF
-            ),
         }
     }
 
+    /// Only the low nibble of `index` selects the key, like on the original interpreter.
     pub fn set_state(&mut self, index: u8, state: bool) {
-        self.keys[index as usize] = state;
+        self.keys[(index & 0xF) as usize] = state;
     }
 
     pub fn is_pressed(&self, index: u8) -> bool {
-        self.keys[index as usize]
+        self.keys[(index & 0xF) as usize]
     }
 
-    pub fn press(&mut self, key: KeyCode, state: bool) {
-        if self.mapping.contains_key(&key) {
-            let index = self.mapping[&key];
-            self.keys[index as usize] = state
-        }
+    /// Lowest keypad key that is currently held down, if any.
+    pub fn pressed_key(&self) -> Option<u8> {
+        self.keys
+            .iter()
+            .position(|pressed| *pressed)
+            .map(|key| key as u8)
     }
 
-    pub fn wait_for_key(&self) -> u8 {
-        loop {
-            match read().unwrap() {
-                Event::Key(event) => {
-                    if event.modifiers.contains(KeyModifiers::CONTROL)
-                        && event.code == KeyCode::Char('c')
-                    {
-                        std::process::exit(0)
-                    } else {
-                        let key = self.mapping.get(&event.code);
-                        if let Some(key) = key {
-                            return key.clone();
-                        } else {
-                            continue;
-                        }
-                    }
-                }
-                _ => continue,
-            }
+    /// All keys as a bit set, bit `n` is key `n`.
+    pub fn mask(&self) -> u16 {
+        self.keys
+            .iter()
+            .enumerate()
+            .filter(|(_, pressed)| **pressed)
+            .fold(0, |mask, (key, _)| mask | 1 << key)
+    }
+
+    /// Press exactly the keys set in `mask`, see `mask`.
+    pub fn set_mask(&mut self, mask: u16) {
+        for (key, pressed) in self.keys.iter_mut().enumerate() {
+            *pressed = mask & 1 << key != 0;
         }
     }
 
-    pub fn register_key_press(&mut self) {
-        use std::time::Duration;
+    pub fn release_all(&mut self) {
+        self.keys = [false; 0xF + 1];
+    }
+}
 
-        if poll(Duration::from_millis(5)).unwrap() {
-            match read().unwrap() {
-                Event::Key(event) => {
-                    if event.modifiers.contains(KeyModifiers::CONTROL)
-                        && event.code == KeyCode::Char('c')
-                    {
-                        std::process::exit(0)
-                    } else {
-                        match self.mapping.get(&event.code) {
-                            Some(key) => self.keys[*key as usize] = true,
-                            None => self.keys = [false; 0xf + 1],
-                        }
-                    }
-                },
-                _ => ()
-            }
-        }
+impl Default for Keyboard {
+    fn default() -> Self {
+        Keyboard::new()
     }
 }
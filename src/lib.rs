@@ -0,0 +1,236 @@
+pub mod asm;
+pub mod audio;
+pub mod debugger;
+pub mod disasm;
+pub mod display;
+pub mod error;
+pub mod image;
+pub mod keyboard;
+pub mod mode;
+pub mod movie;
+pub mod opcode;
+pub mod program_counter;
+pub mod quirks;
+pub mod random;
+pub mod registers;
+pub mod rewind;
+pub mod scheduler;
+pub mod state;
+
+use display::Display;
+use error::Chip8Error;
+use keyboard::Keyboard;
+use mode::Mode;
+use opcode::{Opcode, OpcodeHandler};
+use program_counter::ProgramCounter;
+use quirks::Quirks;
+use random::Random;
+use registers::Registers;
+
+/// Sized by the `Mode`, 4 KiB or 64 KiB.
+pub type Memory = Vec<u8>;
+pub type Stack = [u16; 16];
+
+/// Address the program is loaded at, everything below is reserved for the interpreter.
+pub const PROGRAM_START: u16 = 0x200;
+
+/// XO-CHIP audio pattern a fresh machine plays, a square wave.
+pub const DEFAULT_AUDIO_PATTERN: [u8; 16] = [
+    0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0, 0, 0, 0, 0, 0, 0, 0,
+];
+
+/// XO-CHIP pitch that plays `audio_pattern` at 4000 bits per second.
+pub const DEFAULT_PITCH: u8 = 64;
+
+/// Progress of the `FX0A` the CPU is halted on.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct KeyWait {
+    /// V[`X`] of the `FX0A`, receives the key.
+    pub register: u8,
+
+    /// The key that went down, the wait is over once it's released.
+    pub pressed: Option<u8>,
+}
+
+pub struct Chip {
+    pub mode: Mode,
+
+    pub memory: Memory,
+
+    /// Main registers of the chip (V0 - VE), VF is used as carry flag.
+    pub v: Registers,
+
+    /// Special "I" register, generaly used to store memory addresses.
+    pub i: u16,
+
+    /// Index of the current instruction.
+    pub program_counter: ProgramCounter,
+
+    pub screen: Display,
+
+    pub delay_timer: u8,
+    pub sound_timer: u8,
+
+    /// Whether the sound timer was running during the last timer tick, which is
+    /// what an `audio::Sink` plays for that frame.
+    pub buzzer: bool,
+
+    pub stack: Stack,
+    pub stack_pointer: u8,
+
+    pub keyboard: Keyboard,
+
+    /// Set while the CPU is halted on an `FX0A`, timers and the frontend keep running.
+    pub key_wait: Option<KeyWait>,
+
+    /// Source of random numbers for `CXNN`, part of the saved state.
+    pub rng: Random,
+
+    /// Interpreter behaviours the program expects.
+    pub quirks: Quirks,
+
+    /// SUPER-CHIP user flags written by `FX75` and read back by `FX85`.
+    pub rpl_flags: [u8; 16],
+
+    /// Set by `00FD`, no further instructions are executed.
+    pub exited: bool,
+
+    /// XO-CHIP 1-bit sample loop played while the sound timer is active, loaded by `F002`.
+    pub audio_pattern: [u8; 16],
+
+    /// XO-CHIP playback rate of `audio_pattern`, set by `FX3A`.
+    pub pitch: u8,
+}
+
+impl Chip {
+    pub fn new() -> Chip {
+        Chip::with_mode(Mode::Chip8)
+    }
+
+    pub fn with_mode(mode: Mode) -> Chip {
+        let mut memory = vec![0; mode.memory_size()];
+
+        let font = display::FONT_START as usize;
+        memory[font..font + display::FONT.len()].copy_from_slice(&display::FONT);
+
+        let big_font = display::BIG_FONT_START as usize;
+        memory[big_font..big_font + display::BIG_FONT.len()].copy_from_slice(&display::BIG_FONT);
+
+        Chip {
+            mode,
+            program_counter: ProgramCounter::new(PROGRAM_START),
+            memory,
+
+            v: Registers::new(),
+
+            i: 0,
+            screen: Display::new(),
+            stack: [0; 16],
+
+            delay_timer: 0,
+            sound_timer: 0,
+            buzzer: false,
+
+            stack_pointer: 0,
+            keyboard: Keyboard::new(),
+            key_wait: None,
+            rng: Random::from_entropy(random::Algorithm::default()),
+            quirks: Quirks::default(),
+            rpl_flags: [0; 16],
+            exited: false,
+            audio_pattern: DEFAULT_AUDIO_PATTERN,
+            pitch: DEFAULT_PITCH,
+        }
+    }
+
+    /// Make `CXNN` produce the same sequence on every run.
+    pub fn seed(&mut self, seed: u64) {
+        self.rng = Random::new(self.rng.algorithm, seed);
+    }
+
+    pub fn load_program(&mut self, bytecode: &[u8]) -> Result<(), Chip8Error> {
+        if bytecode.len() > self.mode.max_program_size() {
+            return Err(Chip8Error::ProgramTooLarge {
+                size: bytecode.len(),
+            });
+        }
+
+        let start = PROGRAM_START as usize;
+        self.memory[start..start + bytecode.len()].copy_from_slice(bytecode);
+
+        Ok(())
+    }
+
+    /// Fetch the opcode at the program counter and execute it, does nothing once the program exited.
+    ///
+    /// While the CPU is halted on `FX0A` a step only checks the keypad.
+    pub fn step(&mut self) -> Result<(), Chip8Error> {
+        if self.exited {
+            return Ok(());
+        }
+
+        if self.key_wait.is_some() {
+            self.poll_key_wait();
+            return Ok(());
+        }
+
+        let opcode = self.decode_opcode()?;
+        OpcodeHandler::next(opcode, self)
+    }
+
+    /// Whether the CPU is halted on `FX0A`, waiting for a key to be pressed and released.
+    pub fn waiting_for_key(&self) -> bool {
+        self.key_wait.is_some()
+    }
+
+    /// Move the `FX0A` wait along: note a pressed key, and once it's released store it
+    /// and continue after the instruction.
+    pub fn poll_key_wait(&mut self) {
+        let wait = match &mut self.key_wait {
+            Some(wait) => wait,
+            None => return,
+        };
+
+        match wait.pressed {
+            Some(key) if !self.keyboard.is_pressed(key) => {
+                self.v[wait.register] = key;
+                self.key_wait = None;
+                self.program_counter.increment();
+            }
+            Some(_) => (),
+            None => wait.pressed = self.keyboard.pressed_key(),
+        }
+    }
+
+    /// Decrement both timers by one tick, called at 60 Hz.
+    pub fn update_timers(&mut self) {
+        if self.delay_timer > 0 {
+            self.delay_timer -= 1;
+        }
+
+        self.buzzer = self.sound_timer > 0;
+        if self.buzzer {
+            self.sound_timer -= 1;
+        }
+    }
+
+    /// Read the opcode the program counter points to, without executing it.
+    pub fn decode_opcode(&self) -> Result<Opcode, Chip8Error> {
+        let program_counter = self.program_counter.get() as usize;
+        if program_counter + 1 >= self.memory.len() {
+            return Err(Chip8Error::PcOutOfRange {
+                pc: self.program_counter.get(),
+            });
+        }
+
+        let first_byte = u16::from(self.memory[program_counter]);
+        let second_byte = u16::from(self.memory[program_counter + 1]);
+        Ok(first_byte << 8 | second_byte)
+    }
+}
+
+impl Default for Chip {
+    fn default() -> Self {
+        Chip::new()
+    }
+}
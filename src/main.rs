@@ -1,128 +1,258 @@
-mod keyboard;
-mod opcode;
-mod program_counter;
-mod registers;
-mod screen;
-
-use opcode::{Opcode, OpcodeHandler};
-use program_counter::ProgramCounter;
-use registers::Registers;
-
-use keyboard::Keyboard;
-
-pub type Memory = [u8; 4096];
-pub type Stack = [u16; 16];
-
-use screen::display::*;
-
-fn main() -> crossterm::Result<()> {
-    use std::fs;
-    use std::io::Read;
-
-    let filename = "race.ch8";
-
-    let mut size = fs::metadata(filename)?.len();
-
-    let mut buffer: Vec<u8> = Vec::with_capacity(size as usize);
-
-    fs::File::open(filename)?.read_to_end(&mut buffer)?;
-
-    let mut chip = Chip::new();
-
-    chip.load_program(&buffer);
-    chip.start();
-
-    Ok(())
-}
-
-pub struct Chip {
-    pub memory: Memory,
-
-    /// Main registers of the chip (V0 - VE), VF is used as carry flag.
-    pub v: Registers,
-
-    /// Special "I" register, generaly used to store memory addresses.
-    pub i: u16,
-
-    /// Index of the current instruction.
-    pub program_counter: ProgramCounter,
-
-    pub screen: Display,
-
-    pub delay_timer: u8,
-    pub sound_timer: u8,
-
-    pub stack: Stack,
-    pub stack_pointer: u8,
+mod cli;
+mod headless;
+mod image_file;
+mod movie_file;
+mod state_file;
+mod terminal;
+
+use chip8rust::asm;
+use chip8rust::audio::{self, Sink, Tone};
+use chip8rust::debugger::Debugger;
+use chip8rust::disasm;
+use chip8rust::movie::{self, Movie};
+use chip8rust::scheduler::Scheduler;
+use chip8rust::Chip;
+use cli::{Args, Audio, Command};
+use std::io::{self, BufWriter};
+use std::path::Path;
+use std::{fs, process};
+use terminal::input::KeyMap;
+use terminal::screen::Renderer;
+use terminal::{Frontend, MovieMode};
+
+fn main() {
+    let args = match Command::parse(std::env::args().skip(1)) {
+        Ok(Command::Run(args)) => args,
+        Ok(Command::Disasm { rom, mode }) => {
+            match cli::read_rom(&rom, mode) {
+                Ok(program) => print!("{}", disasm::disassemble(&program, mode)),
+                Err(e) => {
+                    eprintln!("error: {}", e);
+                    process::exit(1);
+                }
+            }
+            return;
+        }
+        Ok(Command::Asm { source, output }) => {
+            if let Err(e) = assemble(&source, &output) {
+                eprintln!("error: {}", e);
+                process::exit(1);
+            }
+            return;
+        }
+        Ok(Command::Keys { key_map, rom }) => {
+            match load_key_map(key_map.as_deref(), rom.as_deref()) {
+                Ok(key_map) => print!("{}", key_map.report()),
+                Err(e) => {
+                    eprintln!("error: {}", e);
+                    process::exit(1);
+                }
+            }
+            return;
+        }
+        Ok(Command::Help) => {
+            println!("{}", cli::USAGE);
+            return;
+        }
+        Err(e) => {
+            eprintln!("error: {}\n\n{}", e, cli::USAGE);
+            process::exit(2);
+        }
+    };
 
-    pub keyboard: Keyboard,
+    if let Err(e) = run(*args) {
+        eprintln!("error: {}", e);
+        process::exit(1);
+    }
 }
 
-impl Chip {
-    pub fn new() -> Chip {
-        let mut memory = [0; 4096];
-
-        for (i, val) in screen::display::FONT.iter().enumerate() {
-            memory[i] = *val;
+fn run(mut args: Args) -> Result<(), String> {
+    let played = match &args.play {
+        Some(path) => {
+            let movie = movie_file::load(path)?;
+            let header = &movie.header;
+            args.mode = header.mode;
+            args.quirks = header.quirks;
+            args.rng = header.rng;
+            args.seed = Some(header.seed);
+            args.instructions_per_frame = header.instructions_per_frame;
+            Some(movie)
         }
+        None => None,
+    };
+
+    let rom = cli::read_rom(&args.rom, args.mode)?;
+
+    if let (Some(movie), Some(path)) = (&played, &args.play) {
+        if movie.header.rom_hash != movie::rom_hash(&rom) {
+            return Err(format!(
+                "'{}' was recorded with a different ROM than '{}'",
+                path.display(),
+                args.rom.display()
+            ));
+        }
+    }
 
-        Chip {
-            program_counter: ProgramCounter::new(512),
-            memory,
+    // A movie only replays if CXNN does too.
+    if args.record.is_some() && args.seed.is_none() {
+        args.seed = Some(rand::random());
+    }
 
-            v: Registers::new(),
+    let mut key_map = load_key_map(args.key_map.as_deref(), Some(&args.rom))?;
+    key_map.hold_timeout = args.hold_timeout;
 
-            i: 0,
-            screen: Display::new(),
-            stack: [0; 16],
+    let mut chip = Chip::with_mode(args.mode);
+    chip.quirks = args.quirks.quirks();
+    chip.rng.algorithm = args.rng;
+    if let Some(seed) = args.seed {
+        chip.seed(seed);
+    }
+    chip.load_program(&rom).map_err(|e| e.to_string())?;
+    if let Some(path) = &args.load_state {
+        state_file::load(&mut chip, path)?;
+    }
 
-            delay_timer: 0,
-            sound_timer: 0,
+    if let Some(options) = &args.headless {
+        let mut stop = options.stop.clone();
+        let script = match (&options.input, &played) {
+            (Some(path), _) => headless::Script::load(path)?,
+            (None, Some(movie)) => {
+                stop.frames = stop.frames.or(Some(movie.frames.len() as u64));
+                headless::Script::from_movie(movie)
+            }
+            (None, None) => headless::Script::default(),
+        };
+
+        let output = args.audio.clone().unwrap_or(Audio::None);
+        let mut audio = open_audio(&output, args.tone)?;
+        let mut video = match &args.video {
+            Some(path) => Some(image_file::Video::start(path, &args.image_style)?),
+            None => None,
+        };
+
+        let outcome = headless::run(
+            &mut chip,
+            args.instructions_per_frame,
+            &stop,
+            &script,
+            |chip| {
+                audio.play(chip)?;
+                if let Some(video) = &mut video {
+                    video.frame(&chip.screen)?;
+                }
+                Ok(())
+            },
+        )
+        .map_err(|e| e.to_string())?;
+
+        audio
+            .finish()
+            .map_err(|e| format!("could not write audio: {}", e))?;
+        // Runs usually stop partway through a frame, the video ends on the final screen.
+        if let Some(mut video) = video {
+            video.frame(&chip.screen)?;
+            video.finish()?;
+        }
 
-            stack_pointer: 0,
-            keyboard: Keyboard::new(),
+        if let Some(path) = &options.screenshot {
+            image_file::save(&chip.screen, path, &args.image_style)?;
         }
-    }
 
-    pub fn load_program(&mut self, bytecode: &[u8]) {
-        for (i, item) in bytecode.iter().enumerate() {
-            self.memory[i + 512] = *item;
+        let report = if options.json {
+            headless::json(&chip, &outcome, &options.dumps)
+        } else {
+            headless::screen(&chip)
+        };
+
+        // Samples own stdout, the report moves out of their way.
+        if output == Audio::Pcm {
+            eprint!("{}", report);
+        } else {
+            print!("{}", report);
         }
+        Ok(())
+    } else if args.debug {
+        let mut debugger = Debugger::new();
+        debugger.breakpoints.extend(&args.breakpoints);
+        debugger.pause();
+
+        let scheduler = Scheduler::new(args.instructions_per_frame);
+        terminal::debugger::run(chip, key_map, scheduler, frontend(&args)?, debugger)
+    } else {
+        let movie = match (played, &args.record) {
+            (Some(movie), _) => MovieMode::Playing(movie),
+            (None, Some(path)) => MovieMode::Recording {
+                movie: Movie::new(movie::Header {
+                    rom_hash: movie::rom_hash(&rom),
+                    mode: args.mode,
+                    quirks: args.quirks,
+                    rng: args.rng,
+                    seed: args.seed.unwrap_or_default(),
+                    instructions_per_frame: args.instructions_per_frame,
+                }),
+                path: path.clone(),
+            },
+            (None, None) => MovieMode::Off,
+        };
+
+        let scheduler = Scheduler::new(args.instructions_per_frame);
+        terminal::run(chip, key_map, scheduler, frontend(&args)?, movie)
     }
+}
 
-    pub fn start(mut self) {
-        screen::screen::init().unwrap();
-
-        loop {
-            self.screen.should_redraw = false;
-            //get and decode opcode
-            let opcode = self.decode_opcode();
-
-            self.keyboard.register_key_press();
+/// Bindings from `path`, or the key map file in the config directory, or the default layout.
+/// Overrides for `rom` are picked by its file name.
+fn load_key_map(path: Option<&Path>, rom: Option<&Path>) -> Result<KeyMap, String> {
+    let rom = rom
+        .and_then(|rom| rom.file_name())
+        .and_then(|name| name.to_str());
+
+    match path
+        .map(Path::to_path_buf)
+        .or_else(terminal::input::default_path)
+    {
+        Some(path) => KeyMap::load(&path, rom),
+        None => Ok(KeyMap::new()),
+    }
+}
 
-            //execute opcode
-            OpcodeHandler::next(opcode, &mut self);
+/// Terminal output and hotkey files as picked by `args`.
+fn frontend(args: &Args) -> Result<Frontend, String> {
+    Ok(Frontend {
+        renderer: Renderer::new(args.render),
+        audio: open_audio(args.audio.as_ref().unwrap_or(&Audio::Bell), args.tone)?,
+        state_path: args.state_path(),
+        rom: args.rom.clone(),
+        image_style: args.image_style.clone(),
+        video: match &args.video {
+            Some(path) => Some(image_file::Video::start(path, &args.image_style)?),
+            None => None,
+        },
+    })
+}
 
-            //update timers
-            if self.delay_timer > 0 {
-                self.delay_timer -= 1;
-            }
+/// The sink for `output`, playing the buzzer at `tone` where it has one.
+fn open_audio(output: &Audio, tone: Tone) -> Result<Box<dyn Sink>, String> {
+    Ok(match output {
+        Audio::Bell => Box::new(audio::Bell::new(io::stdout())),
+        Audio::Pcm => Box::new(audio::Pcm::new(BufWriter::new(io::stdout()), tone)),
+        Audio::Wav(path) => {
+            let error = |e: io::Error| format!("could not write '{}': {}", path.display(), e);
+            let file = fs::File::create(path).map_err(error)?;
+            Box::new(audio::Wav::new(BufWriter::new(file), tone).map_err(error)?)
+        }
+        Audio::None => Box::new(audio::Null::default()),
+    })
+}
 
-            if self.sound_timer > 0 {
-                // todo: beep
-                self.sound_timer -= 1;
-            }
+/// Assemble `source` into `output`, with the symbol file next to it.
+fn assemble(source: &Path, output: &Path) -> Result<(), String> {
+    let assembly = asm::assemble_file(source).map_err(|e| e.to_string())?;
 
-            if self.screen.should_redraw {
-                screen::screen::redraw(&self.screen).unwrap()
-            }
-        }
-    }
+    fs::write(output, &assembly.program)
+        .map_err(|e| format!("could not write '{}': {}", output.display(), e))?;
 
-    fn decode_opcode(&self) -> Opcode {
-        let program_counter = self.program_counter.get() as usize;
-        let first_byte = u16::from(self.memory[program_counter]);
-        let second_byte = u16::from(self.memory[program_counter + 1]);
-        first_byte << 8 | second_byte
-    }
+    let symbols = output.with_extension("sym");
+    fs::write(&symbols, assembly.symbol_file())
+        .map_err(|e| format!("could not write '{}': {}", symbols.display(), e))
 }
@@ -0,0 +1,54 @@
+use super::PROGRAM_START;
+use std::fmt;
+use std::str::FromStr;
+
+/// Instruction set and memory layout the machine emulates.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum Mode {
+    /// CHIP-8 with the SUPER-CHIP 1.1 extensions, 4 KiB of memory.
+    #[default]
+    Chip8,
+    /// Octo's XO-CHIP: 64 KiB of memory, two bitplanes, audio patterns.
+    XoChip,
+}
+
+impl Mode {
+    pub const ALL: [Mode; 2] = [Mode::Chip8, Mode::XoChip];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Mode::Chip8 => "chip8",
+            Mode::XoChip => "xochip",
+        }
+    }
+
+    pub fn memory_size(self) -> usize {
+        match self {
+            Mode::Chip8 => 0x1000,
+            Mode::XoChip => 0x10000,
+        }
+    }
+
+    /// Largest program that fits in memory above `PROGRAM_START`.
+    pub fn max_program_size(self) -> usize {
+        self.memory_size() - PROGRAM_START as usize
+    }
+}
+
+impl fmt::Display for Mode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for Mode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Mode::ALL
+            .iter()
+            .copied()
+            .find(|mode| mode.name() == s)
+            .ok_or_else(|| format!("unknown mode '{}', expected chip8 or xochip", s))
+    }
+}
@@ -0,0 +1,278 @@
+use crate::keyboard::Keyboard;
+use crate::mode::Mode;
+use crate::quirks::Profile;
+use crate::random::Algorithm;
+use std::fmt;
+use std::str::FromStr;
+
+/// First line of every movie, followed by the format version.
+const MAGIC: &str = "chip8rust movie";
+
+/// Bumped whenever the format below changes, older movies are rejected.
+pub const VERSION: u32 = 1;
+
+/// A problem with a movie file, `line` counts from 1.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MovieError {
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for MovieError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}: {}", self.line, self.message)
+    }
+}
+
+impl std::error::Error for MovieError {}
+
+/// Everything a run depends on besides the keypad, so playback starts from the same machine.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Header {
+    /// `rom_hash` of the program the movie was recorded with.
+    pub rom_hash: u64,
+    pub mode: Mode,
+    pub quirks: Profile,
+    pub rng: Algorithm,
+    pub seed: u64,
+    pub instructions_per_frame: u32,
+}
+
+/// Keypad state of every frame of a run, starting from a freshly loaded program.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Movie {
+    pub header: Header,
+
+    /// `Keyboard::mask` before each frame ran.
+    pub frames: Vec<u16>,
+}
+
+impl Movie {
+    pub fn new(header: Header) -> Self {
+        Movie {
+            header,
+            frames: Vec::new(),
+        }
+    }
+
+    /// Append the keypad state of the next frame.
+    pub fn record(&mut self, keyboard: &Keyboard) {
+        self.frames.push(keyboard.mask());
+    }
+
+    /// Set the keypad to what it was in `frame`, returns `false` once the movie is over.
+    pub fn play(&self, frame: usize, keyboard: &mut Keyboard) -> bool {
+        match self.frames.get(frame) {
+            Some(mask) => {
+                keyboard.set_mask(*mask);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// The movie as text: a header of `<name> <value>` lines, then one
+    /// `<hex key mask> [<frames>]` line per run of frames with the same keys held.
+    pub fn to_text(&self) -> String {
+        let header = &self.header;
+        let mut out = format!(
+            "{} {}\nrom {:016x}\nmode {}\nquirks {}\nrng {}\nseed {}\nipf {}\n",
+            MAGIC,
+            VERSION,
+            header.rom_hash,
+            header.mode,
+            header.quirks,
+            header.rng,
+            header.seed,
+            header.instructions_per_frame
+        );
+
+        let mut frames = self.frames.iter().peekable();
+        while let Some(mask) = frames.next() {
+            let mut count = 1;
+            while frames.peek() == Some(&mask) {
+                frames.next();
+                count += 1;
+            }
+
+            if count == 1 {
+                out.push_str(&format!("{:04x}\n", mask));
+            } else {
+                out.push_str(&format!("{:04x} {}\n", mask, count));
+            }
+        }
+
+        out
+    }
+
+    /// Read a movie written by `to_text`.
+    pub fn parse(source: &str) -> Result<Self, MovieError> {
+        let mut lines = source
+            .lines()
+            .enumerate()
+            .map(|(idx, line)| (idx + 1, line));
+
+        let version = lines
+            .next()
+            .and_then(|(_, line)| line.strip_prefix(MAGIC))
+            .map(str::trim)
+            .ok_or_else(|| error(1, "not a movie".to_string()))?;
+        if version != VERSION.to_string() {
+            return Err(error(
+                1,
+                format!(
+                    "movie version {} is not supported, expected version {}",
+                    version, VERSION
+                ),
+            ));
+        }
+
+        let (line, rom) = field::<String>(&mut lines, "rom")?;
+        let rom_hash = u64::from_str_radix(&rom, 16)
+            .map_err(|_| error(line, format!("expected a hex hash, got '{}'", rom)))?;
+
+        let header = Header {
+            rom_hash,
+            mode: field(&mut lines, "mode")?.1,
+            quirks: field(&mut lines, "quirks")?.1,
+            rng: field(&mut lines, "rng")?.1,
+            seed: field(&mut lines, "seed")?.1,
+            instructions_per_frame: field(&mut lines, "ipf")?.1,
+        };
+
+        let mut frames = Vec::new();
+        for (line, text) in lines {
+            let mut parts = text.split_whitespace();
+            let mask = match parts.next() {
+                Some(mask) => u16::from_str_radix(mask, 16)
+                    .map_err(|_| error(line, format!("expected a hex key mask, got '{}'", mask)))?,
+                None => continue,
+            };
+            let count = match parts.next() {
+                Some(count) => count
+                    .parse()
+                    .map_err(|_| error(line, format!("expected a frame count, got '{}'", count)))?,
+                None => 1,
+            };
+
+            frames.extend(std::iter::repeat_n(mask, count));
+        }
+
+        Ok(Movie { header, frames })
+    }
+}
+
+fn error(line: usize, message: String) -> MovieError {
+    MovieError { line, message }
+}
+
+/// Read the `<name> <value>` header line that comes next.
+fn field<'a, T>(
+    lines: &mut impl Iterator<Item = (usize, &'a str)>,
+    name: &str,
+) -> Result<(usize, T), MovieError>
+where
+    T: FromStr,
+    T::Err: fmt::Display,
+{
+    let (line, text) = lines
+        .next()
+        .ok_or_else(|| error(1, format!("missing '{}' in the header", name)))?;
+
+    let mut parts = text.splitn(2, ' ');
+    match (parts.next(), parts.next()) {
+        (Some(found), Some(value)) if found == name => value
+            .trim()
+            .parse()
+            .map(|value| (line, value))
+            .map_err(|e| error(line, format!("bad '{}': {}", name, e))),
+        _ => Err(error(line, format!("expected '{} <value>'", name))),
+    }
+}
+
+/// FNV-1a hash of a ROM, identifies the program a movie belongs to.
+pub fn rom_hash(rom: &[u8]) -> u64 {
+    rom.iter().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+        (hash ^ u64::from(*byte)).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn header() -> Header {
+        Header {
+            rom_hash: rom_hash(b"PONG"),
+            mode: Mode::Chip8,
+            quirks: Profile::CosmacVip,
+            rng: Algorithm::SplitMix,
+            seed: 42,
+            instructions_per_frame: 10,
+        }
+    }
+
+    #[test]
+    fn records_and_plays_keys() {
+        let mut keyboard = Keyboard::new();
+        let mut movie = Movie::new(header());
+
+        movie.record(&keyboard);
+        keyboard.set_state(0x4, true);
+        keyboard.set_state(0xC, true);
+        movie.record(&keyboard);
+
+        let mut played = Keyboard::new();
+        assert!(movie.play(1, &mut played));
+        assert!(played.is_pressed(0x4) && played.is_pressed(0xC));
+        assert!(movie.play(0, &mut played));
+        assert_eq!(played.pressed_key(), None);
+        assert!(!movie.play(2, &mut played));
+    }
+
+    #[test]
+    fn text_round_trips() {
+        let mut movie = Movie::new(header());
+        movie.frames = vec![0, 0, 0, 0x10, 0x10, 0x8001, 0];
+
+        let text = movie.to_text();
+
+        assert!(text.starts_with("chip8rust movie 1\nrom "));
+        assert!(text
+            .ends_with("quirks vip\nrng splitmix\nseed 42\nipf 10\n0000 3\n0010 2\n8001\n0000\n"));
+        assert_eq!(Movie::parse(&text), Ok(movie));
+    }
+
+    #[test]
+    fn reports_bad_movies() {
+        let text = Movie::new(header()).to_text();
+
+        assert_eq!(
+            Movie::parse("chip8rust save").unwrap_err().message,
+            "not a movie"
+        );
+        assert_eq!(
+            Movie::parse("chip8rust movie 7").unwrap_err().message,
+            "movie version 7 is not supported, expected version 1"
+        );
+        assert_eq!(
+            Movie::parse(&text.replace("vip", "amiga")).unwrap_err(),
+            MovieError {
+                line: 4,
+                message: "bad 'quirks': unknown quirk profile 'amiga', expected one of classic, vip, chip48, schip, modern".to_string(),
+            }
+        );
+        assert_eq!(
+            Movie::parse(&text.replace("seed 42\n", ""))
+                .unwrap_err()
+                .to_string(),
+            "6: expected 'seed <value>'"
+        );
+        assert_eq!(
+            Movie::parse(&format!("{}0000 many\n", text))
+                .unwrap_err()
+                .to_string(),
+            "8: expected a frame count, got 'many'"
+        );
+    }
+}
@@ -0,0 +1,17 @@
+use chip8rust::movie::Movie;
+use std::fs;
+use std::path::Path;
+
+/// Write `movie` to `path`.
+pub fn save(movie: &Movie, path: &Path) -> Result<(), String> {
+    fs::write(path, movie.to_text())
+        .map_err(|e| format!("could not write '{}': {}", path.display(), e))
+}
+
+/// Read the movie at `path`.
+pub fn load(path: &Path) -> Result<Movie, String> {
+    let source = fs::read_to_string(path)
+        .map_err(|e| format!("could not read '{}': {}", path.display(), e))?;
+
+    Movie::parse(&source).map_err(|e| format!("{}:{}", path.display(), e))
+}
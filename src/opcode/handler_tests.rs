@@ -9,23 +9,106 @@ fn next_increases_program_counter() {
     let mut chip = prepare();
 
     chip.program_counter.set(0x2);
-    OpcodeHandler::next(0x6512, &mut chip);
+    OpcodeHandler::next(0x6512, &mut chip).unwrap();
 
     assert_eq!(chip.program_counter, 0x4);
 }
 
 #[test]
-fn ret_does_not_increase_pc() {
+fn ret_continues_after_call() {
     let mut chip = prepare();
     let sp = 5;
-    let new_pc = 0x6;
+    let call_pc = 0x6;
 
     chip.stack_pointer = sp;
-    chip.stack[sp as usize] = new_pc;
+    chip.stack[sp as usize - 1] = call_pc;
     chip.program_counter.set(0x2);
-    OpcodeHandler::next(0x00EE, &mut chip);
+    OpcodeHandler::next(0x00EE, &mut chip).unwrap();
 
-    assert_eq!(chip.program_counter, new_pc)
+    assert_eq!(chip.program_counter, call_pc + 2)
+}
+
+#[test]
+fn call_then_ret_returns_to_next_instruction() {
+    let mut chip = prepare();
+
+    chip.program_counter.set(0x200);
+    OpcodeHandler::next(0x2300, &mut chip).unwrap();
+    OpcodeHandler::next(0x00EE, &mut chip).unwrap();
+
+    assert_eq!(chip.program_counter, 0x202);
+    assert_eq!(chip.stack_pointer, 0);
+}
+
+#[test]
+fn unknown_opcode_is_reported() {
+    let mut chip = prepare();
+
+    chip.program_counter.set(0x204);
+
+    assert_eq!(
+        OpcodeHandler::next(0xE1FF, &mut chip),
+        Err(Chip8Error::UnknownOpcode {
+            pc: 0x204,
+            opcode: 0xE1FF
+        })
+    );
+}
+
+#[test]
+fn ret_underflow_is_reported() {
+    let mut chip = prepare();
+
+    chip.program_counter.set(0x200);
+
+    assert_eq!(
+        OpcodeHandler::next(0x00EE, &mut chip),
+        Err(Chip8Error::StackUnderflow {
+            pc: 0x200,
+            opcode: 0x00EE
+        })
+    );
+}
+
+#[test]
+fn call_overflow_is_reported() {
+    let mut chip = prepare();
+
+    chip.program_counter.set(0x200);
+    for _ in 0..16 {
+        OpcodeHandler::next(0x2200, &mut chip).unwrap();
+    }
+
+    assert_eq!(
+        OpcodeHandler::next(0x2200, &mut chip),
+        Err(Chip8Error::StackOverflow {
+            pc: 0x200,
+            opcode: 0x2200
+        })
+    );
+}
+
+#[test]
+fn memory_out_of_bounds_is_reported() {
+    let mut chip = prepare();
+
+    chip.program_counter.set(0x200);
+    chip.i = 0xFFE;
+
+    assert_eq!(
+        OpcodeHandler::next(0xF033, &mut chip),
+        Err(Chip8Error::MemoryOutOfBounds {
+            pc: 0x200,
+            opcode: 0xF033,
+            address: 0x1000
+        })
+    );
+
+    chip.i = 0xFFF;
+    assert!(OpcodeHandler::next(0xF155, &mut chip).is_err());
+    assert!(OpcodeHandler::next(0xF165, &mut chip).is_err());
+    assert!(OpcodeHandler::next(0xD002, &mut chip).is_err());
+    assert!(OpcodeHandler::next(0xD001, &mut chip).is_ok());
 }
 
 #[test]
@@ -34,7 +117,7 @@ fn jp_does_not_increase_pc() {
 
     chip.program_counter.set(2);
 
-    OpcodeHandler::next(0x1034, &mut chip);
+    OpcodeHandler::next(0x1034, &mut chip).unwrap();
 
     assert_eq!(chip.program_counter, 0x0034);
 }
@@ -45,7 +128,7 @@ fn call_does_not_increase_pc() {
 
     chip.program_counter.set(2);
 
-    OpcodeHandler::next(0x2012, &mut chip);
+    OpcodeHandler::next(0x2012, &mut chip).unwrap();
 
     assert_eq!(chip.program_counter, 0x0012)
 }
@@ -56,7 +139,7 @@ fn jmpv0_does_not_increase_pc() {
 
     chip.program_counter.set(2);
 
-    OpcodeHandler::next(0xB123, &mut chip);
+    OpcodeHandler::next(0xB123, &mut chip).unwrap();
 
     assert_eq!(chip.program_counter, 0x0123);
 }
@@ -0,0 +1,303 @@
+use super::Opcode;
+use std::fmt;
+
+/// A decoded opcode with its operands pulled out.
+///
+/// `x` and `y` are register indices, `n` a nibble, `nn` a byte and `nnn` an address.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Instruction {
+    /// `00CN`
+    ScrollDown { n: u8 },
+    /// `00DN` (XO-CHIP)
+    ScrollUp { n: u8 },
+    /// `00E0`
+    Clear,
+    /// `00EE`
+    Return,
+    /// `00FB`
+    ScrollRight,
+    /// `00FC`
+    ScrollLeft,
+    /// `00FD`
+    Exit,
+    /// `00FE`
+    LowRes,
+    /// `00FF`
+    HighRes,
+    /// `1NNN`
+    Jump { nnn: u16 },
+    /// `2NNN`
+    Call { nnn: u16 },
+    /// `3XNN`
+    SkipEqImm { x: u8, nn: u8 },
+    /// `4XNN`
+    SkipNeImm { x: u8, nn: u8 },
+    /// `5XY0`
+    SkipEqReg { x: u8, y: u8 },
+    /// `5XY2` (XO-CHIP)
+    SaveRange { x: u8, y: u8 },
+    /// `5XY3` (XO-CHIP)
+    LoadRange { x: u8, y: u8 },
+    /// `6XNN`
+    LoadImm { x: u8, nn: u8 },
+    /// `7XNN`
+    AddImm { x: u8, nn: u8 },
+    /// `8XY0`
+    LoadReg { x: u8, y: u8 },
+    /// `8XY1`
+    Or { x: u8, y: u8 },
+    /// `8XY2`
+    And { x: u8, y: u8 },
+    /// `8XY3`
+    Xor { x: u8, y: u8 },
+    /// `8XY4`
+    AddReg { x: u8, y: u8 },
+    /// `8XY5`
+    SubReg { x: u8, y: u8 },
+    /// `8XY6`
+    ShiftRight { x: u8, y: u8 },
+    /// `8XY7`
+    SubN { x: u8, y: u8 },
+    /// `8XYE`
+    ShiftLeft { x: u8, y: u8 },
+    /// `9XY0`
+    SkipNeReg { x: u8, y: u8 },
+    /// `ANNN`
+    LoadI { nnn: u16 },
+    /// `BNNN`
+    JumpV0 { nnn: u16 },
+    /// `CXNN`
+    Random { x: u8, nn: u8 },
+    /// `DXYN`
+    Draw { x: u8, y: u8, n: u8 },
+    /// `EX9E`
+    SkipPressed { x: u8 },
+    /// `EXA1`
+    SkipNotPressed { x: u8 },
+    /// `F000 NNNN` (XO-CHIP), the address is in the word after the opcode.
+    LoadILong,
+    /// `FN01` (XO-CHIP)
+    Planes { n: u8 },
+    /// `F002` (XO-CHIP)
+    LoadAudio,
+    /// `FX07`
+    LoadDelay { x: u8 },
+    /// `FX0A`
+    WaitKey { x: u8 },
+    /// `FX15`
+    SetDelay { x: u8 },
+    /// `FX18`
+    SetSound { x: u8 },
+    /// `FX1E`
+    AddI { x: u8 },
+    /// `FX29`
+    LoadFont { x: u8 },
+    /// `FX30`
+    LoadBigFont { x: u8 },
+    /// `FX33`
+    Bcd { x: u8 },
+    /// `FX3A` (XO-CHIP)
+    SetPitch { x: u8 },
+    /// `FX55`
+    Store { x: u8 },
+    /// `FX65`
+    Load { x: u8 },
+    /// `FX75`
+    StoreFlags { x: u8 },
+    /// `FX85`
+    LoadFlags { x: u8 },
+    /// Anything else, most likely sprite or other data.
+    Unknown(Opcode),
+}
+
+/// Split `opcode` into the instruction it encodes.
+pub fn decode(opcode: Opcode) -> Instruction {
+    use Instruction::*;
+
+    let x = ((opcode & 0x0F00) >> 8) as u8;
+    let y = ((opcode & 0x00F0) >> 4) as u8;
+    let n = (opcode & 0x000F) as u8;
+    let nn = (opcode & 0x00FF) as u8;
+    let nnn = opcode & 0x0FFF;
+
+    match opcode & 0xF000 {
+        0x0000 => match opcode {
+            0x00C0..=0x00CF => ScrollDown { n },
+            0x00D0..=0x00DF => ScrollUp { n },
+            0x00E0 => Clear,
+            0x00EE => Return,
+            0x00FB => ScrollRight,
+            0x00FC => ScrollLeft,
+            0x00FD => Exit,
+            0x00FE => LowRes,
+            0x00FF => HighRes,
+            _ => Unknown(opcode),
+        },
+        0x1000 => Jump { nnn },
+        0x2000 => Call { nnn },
+        0x3000 => SkipEqImm { x, nn },
+        0x4000 => SkipNeImm { x, nn },
+        0x5000 => match n {
+            0x0 => SkipEqReg { x, y },
+            0x2 => SaveRange { x, y },
+            0x3 => LoadRange { x, y },
+            _ => Unknown(opcode),
+        },
+        0x6000 => LoadImm { x, nn },
+        0x7000 => AddImm { x, nn },
+        0x8000 => match n {
+            0x0 => LoadReg { x, y },
+            0x1 => Or { x, y },
+            0x2 => And { x, y },
+            0x3 => Xor { x, y },
+            0x4 => AddReg { x, y },
+            0x5 => SubReg { x, y },
+            0x6 => ShiftRight { x, y },
+            0x7 => SubN { x, y },
+            0xE => ShiftLeft { x, y },
+            _ => Unknown(opcode),
+        },
+        0x9000 if n == 0 => SkipNeReg { x, y },
+        0xA000 => LoadI { nnn },
+        0xB000 => JumpV0 { nnn },
+        0xC000 => Random { x, nn },
+        0xD000 => Draw { x, y, n },
+        0xE000 => match nn {
+            0x9E => SkipPressed { x },
+            0xA1 => SkipNotPressed { x },
+            _ => Unknown(opcode),
+        },
+        0xF000 => match nn {
+            0x00 if x == 0 => LoadILong,
+            0x01 => Planes { n: x },
+            0x02 if x == 0 => LoadAudio,
+            0x07 => LoadDelay { x },
+            0x0A => WaitKey { x },
+            0x15 => SetDelay { x },
+            0x18 => SetSound { x },
+            0x1E => AddI { x },
+            0x29 => LoadFont { x },
+            0x30 => LoadBigFont { x },
+            0x33 => Bcd { x },
+            0x3A => SetPitch { x },
+            0x55 => Store { x },
+            0x65 => Load { x },
+            0x75 => StoreFlags { x },
+            0x85 => LoadFlags { x },
+            _ => Unknown(opcode),
+        },
+        _ => Unknown(opcode),
+    }
+}
+
+impl Instruction {
+    /// Only available in `Mode::XoChip`.
+    pub fn is_xo_chip(self) -> bool {
+        use Instruction::*;
+
+        matches!(
+            self,
+            ScrollUp { .. }
+                | SaveRange { .. }
+                | LoadRange { .. }
+                | LoadILong
+                | Planes { .. }
+                | LoadAudio
+                | SetPitch { .. }
+        )
+    }
+
+    /// Size in bytes, including the address word of `F000 NNNN`.
+    pub fn size(self) -> u16 {
+        match self {
+            Instruction::LoadILong => 4,
+            _ => 2,
+        }
+    }
+}
+
+impl fmt::Display for Instruction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        use Instruction::*;
+
+        match *self {
+            ScrollDown { n } => write!(f, "SCD {}", n),
+            ScrollUp { n } => write!(f, "SCU {}", n),
+            Clear => write!(f, "CLS"),
+            Return => write!(f, "RET"),
+            ScrollRight => write!(f, "SCR"),
+            ScrollLeft => write!(f, "SCL"),
+            Exit => write!(f, "EXIT"),
+            LowRes => write!(f, "LOW"),
+            HighRes => write!(f, "HIGH"),
+            Jump { nnn } => write!(f, "JP {:#05X}", nnn),
+            Call { nnn } => write!(f, "CALL {:#05X}", nnn),
+            SkipEqImm { x, nn } => write!(f, "SE V{:X}, {:#04X}", x, nn),
+            SkipNeImm { x, nn } => write!(f, "SNE V{:X}, {:#04X}", x, nn),
+            SkipEqReg { x, y } => write!(f, "SE V{:X}, V{:X}", x, y),
+            SaveRange { x, y } => write!(f, "SAVE V{:X}, V{:X}", x, y),
+            LoadRange { x, y } => write!(f, "LOAD V{:X}, V{:X}", x, y),
+            LoadImm { x, nn } => write!(f, "LD V{:X}, {:#04X}", x, nn),
+            AddImm { x, nn } => write!(f, "ADD V{:X}, {:#04X}", x, nn),
+            LoadReg { x, y } => write!(f, "LD V{:X}, V{:X}", x, y),
+            Or { x, y } => write!(f, "OR V{:X}, V{:X}", x, y),
+            And { x, y } => write!(f, "AND V{:X}, V{:X}", x, y),
+            Xor { x, y } => write!(f, "XOR V{:X}, V{:X}", x, y),
+            AddReg { x, y } => write!(f, "ADD V{:X}, V{:X}", x, y),
+            SubReg { x, y } => write!(f, "SUB V{:X}, V{:X}", x, y),
+            ShiftRight { x, y } => write!(f, "SHR V{:X}, V{:X}", x, y),
+            SubN { x, y } => write!(f, "SUBN V{:X}, V{:X}", x, y),
+            ShiftLeft { x, y } => write!(f, "SHL V{:X}, V{:X}", x, y),
+            SkipNeReg { x, y } => write!(f, "SNE V{:X}, V{:X}", x, y),
+            LoadI { nnn } => write!(f, "LD I, {:#05X}", nnn),
+            JumpV0 { nnn } => write!(f, "JP V0, {:#05X}", nnn),
+            Random { x, nn } => write!(f, "RND V{:X}, {:#04X}", x, nn),
+            Draw { x, y, n } => write!(f, "DRW V{:X}, V{:X}, {}", x, y, n),
+            SkipPressed { x } => write!(f, "SKP V{:X}", x),
+            SkipNotPressed { x } => write!(f, "SKNP V{:X}", x),
+            LoadILong => write!(f, "LD I, LONG"),
+            Planes { n } => write!(f, "PLANE {}", n),
+            LoadAudio => write!(f, "AUDIO"),
+            LoadDelay { x } => write!(f, "LD V{:X}, DT", x),
+            WaitKey { x } => write!(f, "LD V{:X}, K", x),
+            SetDelay { x } => write!(f, "LD DT, V{:X}", x),
+            SetSound { x } => write!(f, "LD ST, V{:X}", x),
+            AddI { x } => write!(f, "ADD I, V{:X}", x),
+            LoadFont { x } => write!(f, "LD F, V{:X}", x),
+            LoadBigFont { x } => write!(f, "LD HF, V{:X}", x),
+            Bcd { x } => write!(f, "LD B, V{:X}", x),
+            SetPitch { x } => write!(f, "PITCH V{:X}", x),
+            Store { x } => write!(f, "LD [I], V{:X}", x),
+            Load { x } => write!(f, "LD V{:X}, [I]", x),
+            StoreFlags { x } => write!(f, "LD R, V{:X}", x),
+            LoadFlags { x } => write!(f, "LD V{:X}, R", x),
+            Unknown(opcode) => write!(f, "DW {:#06X}", opcode),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decodes_operands() {
+        assert_eq!(decode(0x8344), Instruction::AddReg { x: 3, y: 4 });
+        assert_eq!(decode(0xD125), Instruction::Draw { x: 1, y: 2, n: 5 });
+        assert_eq!(decode(0x2ABC), Instruction::Call { nnn: 0xABC });
+        assert_eq!(decode(0x00C3), Instruction::ScrollDown { n: 3 });
+        assert_eq!(decode(0xF301), Instruction::Planes { n: 3 });
+        assert_eq!(decode(0x8008), Instruction::Unknown(0x8008));
+        assert_eq!(decode(0x9AB1), Instruction::Unknown(0x9AB1));
+    }
+
+    #[test]
+    fn prints_mnemonics() {
+        assert_eq!(decode(0x8344).to_string(), "ADD V3, V4");
+        assert_eq!(decode(0x6A02).to_string(), "LD VA, 0x02");
+        assert_eq!(decode(0xA2EA).to_string(), "LD I, 0x2EA");
+        assert_eq!(decode(0xF065).to_string(), "LD V0, [I]");
+        assert_eq!(decode(0xD01F).to_string(), "DRW V0, V1, 15");
+        assert_eq!(decode(0xFFFF).to_string(), "DW 0xFFFF");
+    }
+}
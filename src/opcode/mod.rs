@@ -1,172 +1,362 @@
-use super::registers::{Position, Registers};
-use super::Chip;
-use lazy_static::lazy_static;
-use std::collections::HashMap;
-
-macro_rules! opcode_func_map {
-    ($($opcode:expr => $func: expr), *) => {
-        {
-            let mut map = HashMap::<Opcode, OpcodeImpl>::new();
-            $(
-                map.insert($opcode, $func);
-            )*
-            map
-        }
-    };
-}
+use crate::display::{BIG_FONT_START, FONT_START};
+use crate::error::Chip8Error;
+use crate::mode::Mode;
+use crate::quirks::IndexIncrement;
+use crate::registers::{Position, Registers};
+use crate::{Chip, KeyWait};
+use std::ops::Range;
+
+mod instruction;
+
+pub use instruction::{decode, Instruction};
 
 pub type Opcode = u16;
-type OpcodeImpl = fn(Opcode, &mut Chip);
-
-lazy_static! {
-    pub static ref OPCODE_MAP: HashMap<Opcode, OpcodeImpl> = opcode_func_map!(
-        0x00E0 => OpcodeHandler::clear,
-        0x00EE => OpcodeHandler::ret,
-        0x1000 => OpcodeHandler::jp,
-        0x2000 => OpcodeHandler::call,
-        0x3000 => OpcodeHandler::se,
-        0x4000 => OpcodeHandler::sne,
-        0x5000 => OpcodeHandler::sre,
-        0x6000 => OpcodeHandler::ld,
-        0x7000 => OpcodeHandler::add,
-        0x8000 => OpcodeHandler::ldr,
-        0x8001 => OpcodeHandler::or,
-        0x8002 => OpcodeHandler::and,
-        0x8003 => OpcodeHandler::xor,
-        0x8004 => OpcodeHandler::addreg,
-        0x8005 => OpcodeHandler::subreg,
-        0x8006 => OpcodeHandler::shiftr,
-        0x8007 => OpcodeHandler::sub,
-        0x800E => OpcodeHandler::shiftl,
-        0x9000 => OpcodeHandler::srne,
-        0xA000 => OpcodeHandler::ldi,
-        0xB000 => OpcodeHandler::jmpv0,
-        0xC000 => OpcodeHandler::rand,
-        0xD000 => OpcodeHandler::print,
-        0xE09E => OpcodeHandler::skp,
-        0xE0A1 => OpcodeHandler::sknp,
-        0xF007 => OpcodeHandler::ldvxdt,
-        0xF00A => OpcodeHandler::ldvxkey,
-        0xF015 => OpcodeHandler::lddtvx,
-        0xF018 => OpcodeHandler::ldstvs,
-        0xF01E => OpcodeHandler::addivx,
-        0xF029 => OpcodeHandler::ldfvx,
-        0xF033 => OpcodeHandler::bcd,
-        0xF055 => OpcodeHandler::ldiv0vx,
-        0xF065 => OpcodeHandler::ldv0vxi
-    );
-}
+type OpcodeResult = Result<(), Chip8Error>;
+type OpcodeImpl = fn(Opcode, &mut Chip) -> OpcodeResult;
 
 pub struct OpcodeHandler;
 
 impl OpcodeHandler {
-    pub fn next(opcode: Opcode, chip: &mut Chip) {
-        let normalized_opcode = opcode & 0xF000;
-        let normalized_opcode = match normalized_opcode {
-            0x0000 => opcode & 0x00FF,
-            0x8000 => opcode & 0xF00F,
-            0xE000 | 0xF000 => opcode & 0xF0FF,
-            _ => normalized_opcode,
+    pub fn next(opcode: Opcode, chip: &mut Chip) -> OpcodeResult {
+        let instruction = decode(opcode);
+
+        let func = match OpcodeHandler::handler(instruction) {
+            Some(func) if !instruction.is_xo_chip() || chip.mode == Mode::XoChip => func,
+            _ => {
+                return Err(Chip8Error::UnknownOpcode {
+                    pc: chip.program_counter.get(),
+                    opcode,
+                })
+            }
         };
 
-        // println!("{:?}", normalized_opcode);
+        func(opcode, chip)?;
 
-        match OPCODE_MAP.get(&normalized_opcode) {
-            Some(func) => func(opcode, chip),
-            None => panic!("Wrong opcode! {}", opcode),
+        match instruction {
+            Instruction::Jump { .. }
+            | Instruction::Call { .. }
+            | Instruction::JumpV0 { .. }
+            | Instruction::WaitKey { .. } => (),
+            _ => chip.program_counter.increment(),
         };
 
-        match normalized_opcode {
-            0x1000 | 0x2000 | 0x00EE | 0xB000 => (), // JP, CALL, RET, JMPv0 opcodes
-            _ => chip.program_counter.increment(),
+        Ok(())
+    }
+
+    /// The function executing `instruction`, `None` for `Instruction::Unknown`.
+    fn handler(instruction: Instruction) -> Option<OpcodeImpl> {
+        use Instruction::*;
+
+        let func: OpcodeImpl = match instruction {
+            ScrollDown { .. } => OpcodeHandler::scroll_down,
+            ScrollUp { .. } => OpcodeHandler::scroll_up,
+            Clear => OpcodeHandler::clear,
+            Return => OpcodeHandler::ret,
+            ScrollRight => OpcodeHandler::scroll_right,
+            ScrollLeft => OpcodeHandler::scroll_left,
+            Exit => OpcodeHandler::exit,
+            LowRes => OpcodeHandler::lores,
+            HighRes => OpcodeHandler::hires,
+            Jump { .. } => OpcodeHandler::jp,
+            Call { .. } => OpcodeHandler::call,
+            SkipEqImm { .. } => OpcodeHandler::se,
+            SkipNeImm { .. } => OpcodeHandler::sne,
+            SkipEqReg { .. } => OpcodeHandler::sre,
+            SaveRange { .. } => OpcodeHandler::save_range,
+            LoadRange { .. } => OpcodeHandler::load_range,
+            LoadImm { .. } => OpcodeHandler::ld,
+            AddImm { .. } => OpcodeHandler::add,
+            LoadReg { .. } => OpcodeHandler::ldr,
+            Or { .. } => OpcodeHandler::or,
+            And { .. } => OpcodeHandler::and,
+            Xor { .. } => OpcodeHandler::xor,
+            AddReg { .. } => OpcodeHandler::addreg,
+            SubReg { .. } => OpcodeHandler::subreg,
+            ShiftRight { .. } => OpcodeHandler::shiftr,
+            SubN { .. } => OpcodeHandler::sub,
+            ShiftLeft { .. } => OpcodeHandler::shiftl,
+            SkipNeReg { .. } => OpcodeHandler::srne,
+            LoadI { .. } => OpcodeHandler::ldi,
+            JumpV0 { .. } => OpcodeHandler::jmpv0,
+            Random { .. } => OpcodeHandler::rand,
+            Draw { .. } => OpcodeHandler::print,
+            SkipPressed { .. } => OpcodeHandler::skp,
+            SkipNotPressed { .. } => OpcodeHandler::sknp,
+            LoadILong => OpcodeHandler::ldilong,
+            Planes { .. } => OpcodeHandler::planes,
+            LoadAudio => OpcodeHandler::audio,
+            LoadDelay { .. } => OpcodeHandler::ldvxdt,
+            WaitKey { .. } => OpcodeHandler::ldvxkey,
+            SetDelay { .. } => OpcodeHandler::lddtvx,
+            SetSound { .. } => OpcodeHandler::ldstvs,
+            AddI { .. } => OpcodeHandler::addivx,
+            LoadFont { .. } => OpcodeHandler::ldfvx,
+            LoadBigFont { .. } => OpcodeHandler::ldhfvx,
+            Bcd { .. } => OpcodeHandler::bcd,
+            SetPitch { .. } => OpcodeHandler::pitch,
+            Store { .. } => OpcodeHandler::ldiv0vx,
+            Load { .. } => OpcodeHandler::ldv0vxi,
+            StoreFlags { .. } => OpcodeHandler::ldrv0vx,
+            LoadFlags { .. } => OpcodeHandler::ldv0vxr,
+            Unknown(_) => return None,
         };
+
+        Some(func)
     }
-    fn clear(opcode: Opcode, chip: &mut Chip) {
+
+    /// Range of `len` bytes of memory starting at I, if it fits in memory.
+    fn memory_at_i(opcode: Opcode, chip: &Chip, len: usize) -> Result<Range<usize>, Chip8Error> {
+        let start = chip.i as usize;
+        let end = start + len;
+
+        if end > chip.memory.len() {
+            return Err(Chip8Error::MemoryOutOfBounds {
+                pc: chip.program_counter.get(),
+                opcode,
+                address: start.max(chip.memory.len()),
+            });
+        }
+
+        Ok(start..end)
+    }
+
+    /// Skip the next instruction, all four bytes of it if it's XO-CHIP's `F000 NNNN`.
+    fn skip(chip: &mut Chip) {
+        let next = chip.program_counter.get() as usize + 2;
+        let is_long = chip.mode == Mode::XoChip
+            && chip.memory.get(next) == Some(&0xF0)
+            && chip.memory.get(next + 1) == Some(&0x00);
+
+        chip.program_counter.increment();
+        if is_long {
+            chip.program_counter.increment();
+        }
+    }
+
+    ///`00E0` - Clear the screen
+    fn clear(_opcode: Opcode, chip: &mut Chip) -> OpcodeResult {
         chip.screen.clear();
+
+        Ok(())
+    }
+
+    ///`00CN` - Scroll the screen down by `N` pixels
+    fn scroll_down(opcode: Opcode, chip: &mut Chip) -> OpcodeResult {
+        chip.screen.scroll_down((opcode & 0x000F) as usize);
+
+        Ok(())
+    }
+
+    ///`00DN` - Scroll the screen up by `N` pixels (XO-CHIP)
+    fn scroll_up(opcode: Opcode, chip: &mut Chip) -> OpcodeResult {
+        chip.screen.scroll_up((opcode & 0x000F) as usize);
+
+        Ok(())
+    }
+
+    ///`00FB` - Scroll the screen right by 4 pixels
+    fn scroll_right(_opcode: Opcode, chip: &mut Chip) -> OpcodeResult {
+        chip.screen.scroll_right();
+
+        Ok(())
+    }
+
+    ///`00FC` - Scroll the screen left by 4 pixels
+    fn scroll_left(_opcode: Opcode, chip: &mut Chip) -> OpcodeResult {
+        chip.screen.scroll_left();
+
+        Ok(())
+    }
+
+    ///`00FD` - Exit the interpreter
+    fn exit(_opcode: Opcode, chip: &mut Chip) -> OpcodeResult {
+        chip.exited = true;
+
+        Ok(())
+    }
+
+    ///`00FE` - Switch to the 64x32 low resolution mode
+    fn lores(_opcode: Opcode, chip: &mut Chip) -> OpcodeResult {
+        chip.screen.set_hires(false);
+
+        Ok(())
     }
 
-    /// `00EE` - Return from a subroutine
-    fn ret(_opcode: Opcode, chip: &mut Chip) {
+    ///`00FF` - Switch to the 128x64 high resolution mode
+    fn hires(_opcode: Opcode, chip: &mut Chip) -> OpcodeResult {
+        chip.screen.set_hires(true);
+
+        Ok(())
+    }
+
+    /// `00EE` - Return from a subroutine, execution continues after the `2NNN` that called it
+    fn ret(opcode: Opcode, chip: &mut Chip) -> OpcodeResult {
+        if chip.stack_pointer == 0 {
+            return Err(Chip8Error::StackUnderflow {
+                pc: chip.program_counter.get(),
+                opcode,
+            });
+        }
+
+        chip.stack_pointer -= 1;
         chip.program_counter
             .set(chip.stack[chip.stack_pointer as usize]);
-        chip.stack_pointer -= 1;
+
+        Ok(())
     }
 
     ///`1NNN` - Jump to the address `NNN`
-    fn jp(opcode: Opcode, chip: &mut Chip) {
+    fn jp(opcode: Opcode, chip: &mut Chip) -> OpcodeResult {
         chip.program_counter.set(opcode & 0x0FFF);
+
+        Ok(())
     }
 
     ///`2NNN` - Call subroutine at `NNN`
-    fn call(opcode: Opcode, chip: &mut Chip) {
-        chip.stack_pointer += 1;
+    fn call(opcode: Opcode, chip: &mut Chip) -> OpcodeResult {
+        if chip.stack_pointer as usize >= chip.stack.len() {
+            return Err(Chip8Error::StackOverflow {
+                pc: chip.program_counter.get(),
+                opcode,
+            });
+        }
+
         chip.stack[chip.stack_pointer as usize] = chip.program_counter.get();
+        chip.stack_pointer += 1;
         chip.program_counter.set(opcode & 0x0FFF);
+
+        Ok(())
     }
 
     ///`3XNN` - Skip next instruction if V[`X`] equals `NN`
-    fn se(opcode: Opcode, chip: &mut Chip) {
+    fn se(opcode: Opcode, chip: &mut Chip) -> OpcodeResult {
         let compare = (opcode & 0x00FF) as u8;
         let register = chip.v[(opcode, Position::X)];
 
         if register == compare {
-            chip.program_counter.increment();
+            OpcodeHandler::skip(chip);
         }
+
+        Ok(())
     }
 
     ///`4XNN` - Skip next instruction if V[`X`] doesn't equal `NN`
-    fn sne(opcode: Opcode, chip: &mut Chip) {
+    fn sne(opcode: Opcode, chip: &mut Chip) -> OpcodeResult {
         let register = chip.v[(opcode, Position::X)];
         let to_compare = (opcode & 0x00FF) as u8;
 
         if register != to_compare {
-            chip.program_counter.increment();
+            OpcodeHandler::skip(chip);
         }
+
+        Ok(())
     }
 
     ///`5XY0` - Skip if V[`X`] equal V[`Y`]
-    fn sre(opcode: Opcode, chip: &mut Chip) {
+    fn sre(opcode: Opcode, chip: &mut Chip) -> OpcodeResult {
         let first_register = chip.v[(opcode, Position::X)];
         let second_register = chip.v[(opcode, Position::Y)];
 
         if first_register == second_register {
-            chip.program_counter.increment();
+            OpcodeHandler::skip(chip);
         }
+
+        Ok(())
+    }
+
+    /// Registers `5XY2`/`5XY3` cover, from V[`X`] to V[`Y`] in whichever direction that is.
+    fn register_range(opcode: Opcode) -> Vec<u8> {
+        let x = Registers::get_index(opcode, Position::X);
+        let y = Registers::get_index(opcode, Position::Y);
+
+        if x <= y {
+            (x..=y).collect()
+        } else {
+            (y..=x).rev().collect()
+        }
+    }
+
+    ///`5XY2` - Store registers V[`X`] through V[`Y`] in memory starting at I, I is not changed (XO-CHIP)
+    fn save_range(opcode: Opcode, chip: &mut Chip) -> OpcodeResult {
+        let registers = OpcodeHandler::register_range(opcode);
+        let range = OpcodeHandler::memory_at_i(opcode, chip, registers.len())?;
+
+        for (register, address) in registers.into_iter().zip(range) {
+            chip.memory[address] = chip.v[register];
+        }
+
+        Ok(())
+    }
+
+    ///`5XY3` - Read registers V[`X`] through V[`Y`] from memory starting at I, I is not changed (XO-CHIP)
+    fn load_range(opcode: Opcode, chip: &mut Chip) -> OpcodeResult {
+        let registers = OpcodeHandler::register_range(opcode);
+        let range = OpcodeHandler::memory_at_i(opcode, chip, registers.len())?;
+
+        for (register, address) in registers.into_iter().zip(range) {
+            chip.v[register] = chip.memory[address];
+        }
+
+        Ok(())
     }
 
     ///`6XNN` - Set V[`X`] to `NN`
-    fn ld(opcode: Opcode, chip: &mut Chip) {
+    fn ld(opcode: Opcode, chip: &mut Chip) -> OpcodeResult {
         chip.v[(opcode, Position::X)] = (opcode & 0x00FF) as u8;
+
+        Ok(())
     }
 
     ///`7XNN` - Add `NN` to V[`X`], carry flag not changed
-    fn add(opcode: Opcode, chip: &mut Chip) {
+    fn add(opcode: Opcode, chip: &mut Chip) -> OpcodeResult {
         let index = Registers::get_index(opcode, Position::X);
         chip.v.add_immediate(index, (opcode & 0x00FF) as u8);
+
+        Ok(())
     }
 
     ///`8XY0` Set V[`X`] to the value of V[`Y`]
-    fn ldr(opcode: Opcode, chip: &mut Chip) {
+    fn ldr(opcode: Opcode, chip: &mut Chip) -> OpcodeResult {
         chip.v[(opcode, Position::X)] = chip.v[(opcode, Position::Y)];
+
+        Ok(())
     }
 
     ///`8XY1` - Set V[`X`] to the result of bitwise OR with V[`Y`]
-    fn or(opcode: Opcode, chip: &mut Chip) {
+    fn or(opcode: Opcode, chip: &mut Chip) -> OpcodeResult {
         chip.v[(opcode, Position::X)] |= chip.v[(opcode, Position::Y)];
+
+        if chip.quirks.logic_resets_vf {
+            chip.v[0xF] = 0;
+        }
+
+        Ok(())
     }
 
     ///`8XY2` - Set V[`X`] to the result of bitwise AND with V[`Y`]
-    fn and(opcode: Opcode, chip: &mut Chip) {
+    fn and(opcode: Opcode, chip: &mut Chip) -> OpcodeResult {
         chip.v[(opcode, Position::X)] &= chip.v[(opcode, Position::Y)];
+
+        if chip.quirks.logic_resets_vf {
+            chip.v[0xF] = 0;
+        }
+
+        Ok(())
     }
 
     ///`8XY3` - Set V[`X`] to the result of bitwise XOR with V[`Y`]
-    fn xor(opcode: Opcode, chip: &mut Chip) {
+    fn xor(opcode: Opcode, chip: &mut Chip) -> OpcodeResult {
         chip.v[(opcode, Position::X)] ^= chip.v[(opcode, Position::Y)];
+
+        if chip.quirks.logic_resets_vf {
+            chip.v[0xF] = 0;
+        }
+
+        Ok(())
     }
 
     ///`8XY4` - Add V[`Y`] to V[`X`], change carry flag if there's a borrow
-    fn addreg(opcode: Opcode, chip: &mut Chip) {
+    fn addreg(opcode: Opcode, chip: &mut Chip) -> OpcodeResult {
         let left = chip.v[(opcode, Position::X)];
         let right = chip.v[(opcode, Position::Y)];
 
@@ -174,10 +364,12 @@ impl OpcodeHandler {
 
         chip.v[(opcode, Position::X)] = result;
         chip.v.set_carry(carried);
+
+        Ok(())
     }
 
     ///`8XY5` - Subtract V[`Y`] from V[`X`], change carry flag if there's a borrow
-    fn subreg(opcode: Opcode, chip: &mut Chip) {
+    fn subreg(opcode: Opcode, chip: &mut Chip) -> OpcodeResult {
         let x = chip.v[(opcode, Position::X)];
         let y = chip.v[(opcode, Position::Y)];
 
@@ -185,16 +377,33 @@ impl OpcodeHandler {
 
         chip.v[(opcode, Position::X)] = result;
         chip.v.set_carry(!carried);
+
+        Ok(())
     }
 
-    ///`8XY6` - Store least significant bit of V[`X`] in VF and then shift V[`X`] to the right by 1
-    fn shiftr(opcode: Opcode, chip: &mut Chip) {
-        chip.v[0xF] = chip.v[(opcode, Position::X)] & 1;
-        chip.v[(opcode, Position::X)] >>= 1;
+    ///`8XY6` - Shift V[`X`] to the right by 1 and store the bit shifted out in VF.
+    ///
+    /// With `shift_uses_vy` V[`Y`] is shifted and the result stored in V[`X`].
+    fn shiftr(opcode: Opcode, chip: &mut Chip) -> OpcodeResult {
+        let value = OpcodeHandler::shift_source(opcode, chip);
+
+        chip.v[(opcode, Position::X)] = value >> 1;
+        chip.v[0xF] = value & 1;
+
+        Ok(())
+    }
+
+    /// Register `8XY6` and `8XYE` shift, depending on the quirks.
+    fn shift_source(opcode: Opcode, chip: &Chip) -> u8 {
+        if chip.quirks.shift_uses_vy {
+            chip.v[(opcode, Position::Y)]
+        } else {
+            chip.v[(opcode, Position::X)]
+        }
     }
 
     ///`8XY7` - Sets V[`X`] to V[`Y`] minus V[`X`]. VF is set to 0 when there's a borrow, and 1 when there isn't.
-    fn sub(opcode: Opcode, chip: &mut Chip) {
+    fn sub(opcode: Opcode, chip: &mut Chip) -> OpcodeResult {
         let x = chip.v[(opcode, Position::X)];
         let y = chip.v[(opcode, Position::Y)];
         let (result, carried) = y.overflowing_sub(x);
@@ -202,139 +411,281 @@ impl OpcodeHandler {
         chip.v[(opcode, Position::X)] = result;
 
         chip.v.set_carry(!carried);
+
+        Ok(())
     }
 
-    ///`8XYE` - Stores the most significant bit of V[`X`] in VF and then shifts V[`X`] to the left by 1
-    fn shiftl(opcode: Opcode, chip: &mut Chip) {
-        let index = Registers::get_index(opcode, Position::X);
-        chip.v[0xF] = (chip.v[index] >= 128) as u8;
-        chip.v[index] <<= 1;
+    ///`8XYE` - Shift V[`X`] to the left by 1 and store the bit shifted out in VF.
+    ///
+    /// With `shift_uses_vy` V[`Y`] is shifted and the result stored in V[`X`].
+    fn shiftl(opcode: Opcode, chip: &mut Chip) -> OpcodeResult {
+        let value = OpcodeHandler::shift_source(opcode, chip);
+
+        chip.v[(opcode, Position::X)] = value << 1;
+        chip.v[0xF] = (value >= 128) as u8;
+
+        Ok(())
     }
 
     ///`9XY0` - Skips the next instruction if V[`X`] does not equal V[`Y`]
-    fn srne(opcode: Opcode, chip: &mut Chip) {
+    fn srne(opcode: Opcode, chip: &mut Chip) -> OpcodeResult {
         if chip.v[(opcode, Position::X)] != chip.v[(opcode, Position::Y)] {
-            chip.program_counter.increment();
+            OpcodeHandler::skip(chip);
         }
+
+        Ok(())
     }
 
     ///`ANNN` - Set I to address NNN
-    fn ldi(opcode: Opcode, chip: &mut Chip) {
+    fn ldi(opcode: Opcode, chip: &mut Chip) -> OpcodeResult {
         chip.i = opcode & 0x0FFF;
+
+        Ok(())
     }
 
-    ///`BNNN` - Jump to the address NNN plus V[0]
-    fn jmpv0(opcode: Opcode, chip: &mut Chip) {
-        let address = u16::from(chip.v[0]) + (opcode & 0x0FFF);
+    ///`BNNN` - Jump to the address NNN plus V[0], or plus V[`X`] with `jump_uses_vx`
+    fn jmpv0(opcode: Opcode, chip: &mut Chip) -> OpcodeResult {
+        let register = if chip.quirks.jump_uses_vx {
+            Registers::get_index(opcode, Position::X)
+        } else {
+            0
+        };
+
+        let address = u16::from(chip.v[register]) + (opcode & 0x0FFF);
         chip.program_counter.set(address);
+
+        Ok(())
     }
 
     ///`CXNN` - Set V[`X`] equal `random_number & NN`
-    fn rand(opcode: Opcode, chip: &mut Chip) {
-        use rand::Rng;
-        let mut rng = rand::thread_rng();
-
-        let random = rng.gen_range(0, 256) as u8;
+    fn rand(opcode: Opcode, chip: &mut Chip) -> OpcodeResult {
+        let random = chip.rng.next_byte();
         let nn = (opcode & 0x00FF) as u8;
 
         chip.v[(opcode, Position::X)] = random & nn;
+
+        Ok(())
     }
 
     ///`DXYN` - Display `N`-byte sprite, starting at I, at (V[`X`], V[`Y`]), set V[F] if collision occured
-    fn print(opcode: Opcode, chip: &mut Chip) {
+    ///
+    ///`DXY0` - Display a 16x16 sprite of 32 bytes instead
+    fn print(opcode: Opcode, chip: &mut Chip) -> OpcodeResult {
         let x = chip.v[(opcode, Position::X)];
         let y = chip.v[(opcode, Position::Y)];
 
-        let n = opcode & 0x000F;
-        let sprites: Vec<&u8> = chip
-            .memory
-            .iter()
-            .skip(chip.i as usize)
-            .take(n as usize)
-            .collect();
+        let n = (opcode & 0x000F) as usize;
+        let large = n == 0;
+        let per_plane = if large { 32 } else { n };
+
+        let len = per_plane * chip.screen.selected_plane_count();
+        let range = OpcodeHandler::memory_at_i(opcode, chip, len)?;
+        let collision =
+            chip.screen
+                .draw_sprite(x, y, &chip.memory[range], large, chip.quirks.clip_sprites);
+
+        chip.v[0xF] = collision as u8;
 
-        chip.v[0xF] = chip.screen.draw(x, y, &sprites) as u8;
+        Ok(())
     }
 
     ///`EX9E` - Skip the next instruction if the V[`X`] key is pressed.
-    fn skp(opcode: Opcode, chip: &mut Chip) {
+    fn skp(opcode: Opcode, chip: &mut Chip) -> OpcodeResult {
         let x = chip.v[(opcode, Position::X)];
 
         if chip.keyboard.is_pressed(x) {
-            chip.program_counter.increment();
+            OpcodeHandler::skip(chip);
         }
+
+        Ok(())
     }
 
     ///`EXA1` - Skip the next instruction if the V[`X`] key is not pressed.
-    fn sknp(opcode: Opcode, chip: &mut Chip) {
+    fn sknp(opcode: Opcode, chip: &mut Chip) -> OpcodeResult {
         let x = chip.v[(opcode, Position::X)];
 
         if !chip.keyboard.is_pressed(x) {
-            chip.program_counter.increment();
+            OpcodeHandler::skip(chip);
         }
+
+        Ok(())
+    }
+
+    ///`F000 NNNN` - Set I to the 16-bit address `NNNN` in the next word (XO-CHIP)
+    fn ldilong(opcode: Opcode, chip: &mut Chip) -> OpcodeResult {
+        let start = chip.program_counter.get() as usize + 2;
+        let address = match chip.memory.get(start..start + 2) {
+            Some(word) => u16::from(word[0]) << 8 | u16::from(word[1]),
+            None => {
+                return Err(Chip8Error::MemoryOutOfBounds {
+                    pc: chip.program_counter.get(),
+                    opcode,
+                    address: start,
+                })
+            }
+        };
+
+        chip.i = address;
+        chip.program_counter.increment();
+
+        Ok(())
+    }
+
+    ///`FN01` - Select the bitplanes drawn to, `N` being a bitmask (XO-CHIP)
+    fn planes(opcode: Opcode, chip: &mut Chip) -> OpcodeResult {
+        chip.screen.selected_planes = ((opcode >> 8) & 0x3) as u8;
+
+        Ok(())
+    }
+
+    ///`F002` - Load the 16-byte audio pattern from memory starting at I (XO-CHIP)
+    fn audio(opcode: Opcode, chip: &mut Chip) -> OpcodeResult {
+        let range = OpcodeHandler::memory_at_i(opcode, chip, chip.audio_pattern.len())?;
+        chip.audio_pattern.copy_from_slice(&chip.memory[range]);
+
+        Ok(())
+    }
+
+    ///`FX3A` - Set the audio pattern playback pitch to V[`X`] (XO-CHIP)
+    fn pitch(opcode: Opcode, chip: &mut Chip) -> OpcodeResult {
+        chip.pitch = chip.v[(opcode, Position::X)];
+
+        Ok(())
     }
 
     ///`FX07` - Set V[`X`] equal to delay timer.
-    fn ldvxdt(opcode: Opcode, chip: &mut Chip) {
+    fn ldvxdt(opcode: Opcode, chip: &mut Chip) -> OpcodeResult {
         chip.v[(opcode, Position::X)] = chip.delay_timer;
+
+        Ok(())
     }
 
-    ///`FX0A` - Wait for a key press, store the value of the key in V[`X`]
-    fn ldvxkey(opcode: Opcode, chip: &mut Chip) {
-        let key = chip.keyboard.wait_for_key();
+    ///`FX0A` - Wait for a key to be pressed and released again, store the value of the key in V[`X`]
+    ///
+    /// Halts the CPU on this instruction, see `Chip::key_wait`. Like on the COSMAC VIP,
+    /// holding a key doesn't make the program skip ahead.
+    fn ldvxkey(opcode: Opcode, chip: &mut Chip) -> OpcodeResult {
+        chip.key_wait = Some(KeyWait {
+            register: Registers::get_index(opcode, Position::X),
+            pressed: None,
+        });
+        chip.poll_key_wait();
 
-        chip.v[(opcode, Position::X)] = key;
+        Ok(())
     }
 
     ///`FX15` - Set delay timer equal to V[`X`]
-    fn lddtvx(opcode: Opcode, chip: &mut Chip) {
+    fn lddtvx(opcode: Opcode, chip: &mut Chip) -> OpcodeResult {
         chip.delay_timer = chip.v[(opcode, Position::X)];
+
+        Ok(())
     }
 
     ///`FX18` - Set sound timer equal to V[`X`]
-    fn ldstvs(opcode: Opcode, chip: &mut Chip) {
+    fn ldstvs(opcode: Opcode, chip: &mut Chip) -> OpcodeResult {
         chip.sound_timer = chip.v[(opcode, Position::X)];
+
+        Ok(())
     }
 
-    ///`FX1E` - Set I equal to V[`X`] + I
-    fn addivx(opcode: Opcode, chip: &mut Chip) {
+    ///`FX1E` - Set I equal to V[`X`] + I, with `add_i_sets_vf` VF is set if I leaves the address space
+    fn addivx(opcode: Opcode, chip: &mut Chip) -> OpcodeResult {
         let to_add = u16::from(chip.v[(opcode, Position::X)]);
-        let (result, carried) = chip.i.overflowing_add(to_add);
+        let result = chip.i.wrapping_add(to_add);
 
         chip.i = result;
-        chip.v.set_carry(carried);
+
+        if chip.quirks.add_i_sets_vf {
+            chip.v.set_carry(result > 0x0FFF);
+        }
+
+        Ok(())
     }
 
     ///`FX29` - Set I equal to sprite location for digit V[`X`]
-    fn ldfvx(opcode: Opcode, chip: &mut Chip) {
-        let num = 5 * chip.v[(opcode, Position::X)];
-        chip.i = num.into()
+    fn ldfvx(opcode: Opcode, chip: &mut Chip) -> OpcodeResult {
+        chip.i = FONT_START + 5 * u16::from(chip.v[(opcode, Position::X)] & 0xF);
+
+        Ok(())
+    }
+
+    ///`FX30` - Set I equal to the large 8x10 sprite location for digit V[`X`]
+    fn ldhfvx(opcode: Opcode, chip: &mut Chip) -> OpcodeResult {
+        chip.i = BIG_FONT_START + 10 * u16::from(chip.v[(opcode, Position::X)] & 0xF);
+
+        Ok(())
     }
 
     ///`FX33` - Store BCD representation of V[`X`] to I, I+1, I+2
-    fn bcd(opcode: Opcode, chip: &mut Chip) {
+    fn bcd(opcode: Opcode, chip: &mut Chip) -> OpcodeResult {
         let vx_val = chip.v[(opcode, Position::X)];
         let onemial = vx_val % 10;
         let decimal: u8 = (vx_val / 10) % 10;
         let hundred: u8 = (vx_val / 100) % 10;
 
-        chip.memory[chip.i as usize] = hundred;
-        chip.memory[(chip.i + 1) as usize] = decimal;
-        chip.memory[(chip.i + 2) as usize] = onemial;
+        let range = OpcodeHandler::memory_at_i(opcode, chip, 3)?;
+        chip.memory[range].copy_from_slice(&[hundred, decimal, onemial]);
+
+        Ok(())
+    }
+
+    /// Move I past the registers `FX55`/`FX65` just stored or loaded, depending on the quirks.
+    fn advance_i(x: u8, chip: &mut Chip) {
+        chip.i = chip.i.wrapping_add(match chip.quirks.load_store_increment {
+            IndexIncrement::Unchanged => 0,
+            IndexIncrement::ByX => u16::from(x),
+            IndexIncrement::ByXPlusOne => u16::from(x) + 1,
+        });
     }
 
     ///`FX55` - Store registers V0 through V[`X`] in memory starting at location I.`
-    fn ldiv0vx(opcode: Opcode, chip: &mut Chip) {
-        for idx in 0..=Registers::get_index(opcode, Position::X) {
-            chip.memory[(chip.i + u16::from(idx)) as usize] = chip.v[idx];
+    fn ldiv0vx(opcode: Opcode, chip: &mut Chip) -> OpcodeResult {
+        let x = Registers::get_index(opcode, Position::X);
+        let range = OpcodeHandler::memory_at_i(opcode, chip, x as usize + 1)?;
+
+        for (idx, address) in range.enumerate() {
+            chip.memory[address] = chip.v[idx as u8];
         }
+
+        OpcodeHandler::advance_i(x, chip);
+
+        Ok(())
     }
 
     ///`FX65` - Read registers V0 through V[`X`] from memory starting at location I.
-    fn ldv0vxi(opcode: Opcode, chip: &mut Chip) {
-        for idx in 0..=Registers::get_index(opcode, Position::X) {
-            chip.v[idx] = chip.memory[(chip.i + u16::from(idx)) as usize];
+    fn ldv0vxi(opcode: Opcode, chip: &mut Chip) -> OpcodeResult {
+        let x = Registers::get_index(opcode, Position::X);
+        let range = OpcodeHandler::memory_at_i(opcode, chip, x as usize + 1)?;
+
+        for (idx, address) in range.enumerate() {
+            chip.v[idx as u8] = chip.memory[address];
+        }
+
+        OpcodeHandler::advance_i(x, chip);
+
+        Ok(())
+    }
+
+    ///`FX75` - Store registers V0 through V[`X`] in the RPL user flags
+    fn ldrv0vx(opcode: Opcode, chip: &mut Chip) -> OpcodeResult {
+        let x = Registers::get_index(opcode, Position::X);
+
+        for idx in 0..=x {
+            chip.rpl_flags[idx as usize] = chip.v[idx];
         }
+
+        Ok(())
+    }
+
+    ///`FX85` - Read registers V0 through V[`X`] from the RPL user flags
+    fn ldv0vxr(opcode: Opcode, chip: &mut Chip) -> OpcodeResult {
+        let x = Registers::get_index(opcode, Position::X);
+
+        for idx in 0..=x {
+            chip.v[idx] = chip.rpl_flags[idx as usize];
+        }
+
+        Ok(())
     }
 }
 
@@ -1,4 +1,6 @@
 use super::*;
+use crate::display::BIG_FONT;
+use crate::PROGRAM_START;
 
 fn prepare() -> Chip {
     Chip::new()
@@ -9,9 +11,9 @@ fn ret_test() {
     let mut chip = prepare();
 
     chip.stack_pointer = 10;
-    chip.stack[chip.stack_pointer as usize] = 0x1234;
+    chip.stack[9] = 0x1234;
 
-    OpcodeHandler::ret(0x00EE, &mut chip);
+    OpcodeHandler::ret(0x00EE, &mut chip).unwrap();
 
     assert_eq!(chip.program_counter, 0x1234);
     assert_eq!(chip.stack_pointer, 9);
@@ -22,7 +24,7 @@ fn jp_test() {
     let mut chip = prepare();
     chip.program_counter.set(0xABC);
 
-    OpcodeHandler::jp(0x1DEA, &mut chip);
+    OpcodeHandler::jp(0x1DEA, &mut chip).unwrap();
 
     assert_eq!(chip.program_counter, 0xDEA)
 }
@@ -32,13 +34,11 @@ fn call_test() {
     let mut chip = prepare();
     chip.program_counter.set(12);
 
-    let opcode = 0x2DEA;
-
-    OpcodeHandler::call(0x2DEA, &mut chip);
+    OpcodeHandler::call(0x2DEA, &mut chip).unwrap();
 
     assert_eq!(chip.program_counter, 0xDEA);
     assert_eq!(chip.stack_pointer, 1);
-    assert_eq!(chip.stack[chip.stack_pointer as usize], 12);
+    assert_eq!(chip.stack[0], 12);
 }
 
 #[test]
@@ -47,7 +47,7 @@ fn se_test_neq() {
     chip.program_counter.set(12);
     chip.v[5] = 0x10;
 
-    OpcodeHandler::se(0x3512, &mut chip);
+    OpcodeHandler::se(0x3512, &mut chip).unwrap();
 
     assert_eq!(chip.program_counter, 12);
 }
@@ -58,7 +58,7 @@ fn se_test_eq() {
     chip.program_counter.set(12);
     chip.v[4] = 0x12;
 
-    OpcodeHandler::se(0x3412, &mut chip);
+    OpcodeHandler::se(0x3412, &mut chip).unwrap();
 
     assert_eq!(chip.program_counter, 14);
 }
@@ -69,7 +69,7 @@ fn sne_test_eq() {
     chip.program_counter.set(12);
     chip.v[4] = 0x12;
 
-    OpcodeHandler::sne(0x4412, &mut chip);
+    OpcodeHandler::sne(0x4412, &mut chip).unwrap();
 
     assert_eq!(chip.program_counter, 12);
 }
@@ -82,7 +82,7 @@ fn sne_test_neq() {
 
     chip.v[4] = 0x13;
 
-    OpcodeHandler::sne(0x4412, &mut chip);
+    OpcodeHandler::sne(0x4412, &mut chip).unwrap();
 
     assert_eq!(chip.program_counter, 14);
 }
@@ -96,7 +96,7 @@ fn sre_test() {
     chip.v[5] = 0x12;
     chip.v[6] = 0x13;
 
-    OpcodeHandler::sre(0x5460, &mut chip);
+    OpcodeHandler::sre(0x5460, &mut chip).unwrap();
 
     assert_eq!(chip.program_counter, 12)
 }
@@ -110,7 +110,7 @@ fn sre_test_eq() {
     chip.v[4] = 0x12;
     chip.v[5] = 0x12;
 
-    OpcodeHandler::sre(0x5450, &mut chip);
+    OpcodeHandler::sre(0x5450, &mut chip).unwrap();
 
     assert_eq!(chip.program_counter, 14);
 }
@@ -121,7 +121,7 @@ fn ld_test() {
 
     chip.v[0xE] = 0xAB;
 
-    OpcodeHandler::ld(0x6EAB, &mut chip);
+    OpcodeHandler::ld(0x6EAB, &mut chip).unwrap();
 
     assert_eq!(chip.v[0xE], 0xAB);
 }
@@ -132,7 +132,7 @@ fn add_test() {
 
     chip.v[0x5] = 0x1;
 
-    OpcodeHandler::add(0x75AB, &mut chip);
+    OpcodeHandler::add(0x75AB, &mut chip).unwrap();
 
     assert_eq!(chip.v[0x5], 0xAC);
 }
@@ -143,7 +143,7 @@ fn add_overflow_test() {
 
     chip.v[0x5] = 0xFF;
 
-    OpcodeHandler::add(0x7502, &mut chip);
+    OpcodeHandler::add(0x7502, &mut chip).unwrap();
 
     assert_eq!(chip.v[0x5], 0x1);
 }
@@ -154,7 +154,7 @@ fn ldr_test() {
     chip.v[1] = 111;
     chip.v[2] = 222;
 
-    OpcodeHandler::ldr(0x8120, &mut chip);
+    OpcodeHandler::ldr(0x8120, &mut chip).unwrap();
 
     assert_eq!(chip.v[1], 222);
 }
@@ -165,7 +165,7 @@ fn or_test() {
     chip.v[1] = 0x12;
     chip.v[2] = 0x34;
 
-    OpcodeHandler::or(0x8121, &mut chip);
+    OpcodeHandler::or(0x8121, &mut chip).unwrap();
     assert_eq!(chip.v[1], 0x36);
     assert_eq!(chip.v[2], 0x34);
 }
@@ -177,7 +177,7 @@ fn and_test() {
     chip.v[1] = 0x12;
     chip.v[2] = 0x34;
 
-    OpcodeHandler::and(0x8122, &mut chip);
+    OpcodeHandler::and(0x8122, &mut chip).unwrap();
 
     assert_eq!(chip.v[1], 0x10);
     assert_eq!(chip.v[2], 0x34);
@@ -190,7 +190,7 @@ fn xor_test() {
     chip.v[1] = 0x12;
     chip.v[2] = 0x34;
 
-    OpcodeHandler::xor(0x8123, &mut chip);
+    OpcodeHandler::xor(0x8123, &mut chip).unwrap();
 
     assert_eq!(chip.v[1], 0x26);
     assert_eq!(chip.v[2], 0x34);
@@ -204,7 +204,7 @@ fn addreg_test() {
     chip.v[1] = 0x01;
     chip.v[0xF] = 1;
 
-    OpcodeHandler::addreg(0x8014, &mut chip);
+    OpcodeHandler::addreg(0x8014, &mut chip).unwrap();
 
     assert_eq!(chip.v[0], 0x21);
     assert_eq!(chip.v[1], 0x01);
@@ -219,7 +219,7 @@ fn addreg_carry_test() {
     chip.v[1] = 0x02;
     chip.v[0xF] = 0;
 
-    OpcodeHandler::addreg(0x8014, &mut chip);
+    OpcodeHandler::addreg(0x8014, &mut chip).unwrap();
 
     assert_eq!(chip.v[0], 0x01);
     assert_eq!(chip.v[1], 0x02);
@@ -234,7 +234,7 @@ fn subreg_test() {
     chip.v[1] = 0x01;
     chip.v[0xF] = 1;
 
-    OpcodeHandler::subreg(0x8015, &mut chip);
+    OpcodeHandler::subreg(0x8015, &mut chip).unwrap();
 
     assert_eq!(chip.v[0], 0x1F);
     assert_eq!(chip.v[1], 0x01);
@@ -249,7 +249,7 @@ fn subreg_carry_test() {
     chip.v[1] = 0x01;
     chip.v[0xF] = 0;
 
-    OpcodeHandler::subreg(0x8015, &mut chip);
+    OpcodeHandler::subreg(0x8015, &mut chip).unwrap();
 
     assert_eq!(chip.v[0], 0xFF);
     assert_eq!(chip.v[1], 0x01);
@@ -263,7 +263,7 @@ fn shiftr_test() {
     chip.v[0] = 0x10;
     chip.v[0xF] = 1;
 
-    OpcodeHandler::shiftr(0x8016, &mut chip);
+    OpcodeHandler::shiftr(0x8016, &mut chip).unwrap();
 
     assert_eq!(chip.v[0], 0x8);
     assert_eq!(chip.v[0xF], 0);
@@ -277,7 +277,7 @@ fn sub_test() {
     chip.v[1] = 1;
     chip.v[0xF] = 1;
 
-    OpcodeHandler::sub(0x8107, &mut chip);
+    OpcodeHandler::sub(0x8107, &mut chip).unwrap();
 
     assert_eq!(chip.v[1], 255);
     assert_eq!(chip.v[0xF], 0);
@@ -291,7 +291,7 @@ fn sub_test_overflow() {
     chip.v[1] = 1;
     chip.v[0xF] = 1;
 
-    OpcodeHandler::sub(0x8017, &mut chip);
+    OpcodeHandler::sub(0x8017, &mut chip).unwrap();
 
     assert_eq!(chip.v[0], 1);
     assert_eq!(chip.v[0xF], 1);
@@ -300,15 +300,72 @@ fn sub_test_overflow() {
 #[test]
 fn shiftl_test_significant_one() {
     let mut chip = prepare();
-    chip.v[0] = 0b100_0100_1;
+    chip.v[0] = 0b1000_1001;
     chip.v[0xf] = 0;
 
-    OpcodeHandler::shiftl(0x801E, &mut chip);
+    OpcodeHandler::shiftl(0x801E, &mut chip).unwrap();
 
-    assert_eq!(chip.v[0], 0b0001_001_0);
+    assert_eq!(chip.v[0], 0b0001_0010);
     assert_eq!(chip.v[0xf], 1);
 }
 
+#[test]
+fn shiftr_test_uses_vy() {
+    let mut chip = prepare();
+    chip.quirks.shift_uses_vy = true;
+
+    chip.v[0] = 0x10;
+    chip.v[1] = 0x05;
+
+    OpcodeHandler::shiftr(0x8016, &mut chip).unwrap();
+
+    assert_eq!(chip.v[0], 0x2);
+    assert_eq!(chip.v[1], 0x5);
+    assert_eq!(chip.v[0xF], 1);
+}
+
+#[test]
+fn shiftl_test_uses_vy() {
+    let mut chip = prepare();
+    chip.quirks.shift_uses_vy = true;
+
+    chip.v[0] = 0x01;
+    chip.v[1] = 0x41;
+
+    OpcodeHandler::shiftl(0x801E, &mut chip).unwrap();
+
+    assert_eq!(chip.v[0], 0x82);
+    assert_eq!(chip.v[0xF], 0);
+}
+
+#[test]
+fn shift_into_vf_keeps_flag() {
+    let mut chip = prepare();
+    chip.quirks.shift_uses_vy = false;
+
+    chip.v[0xF] = 0x81;
+
+    OpcodeHandler::shiftl(0x8F0E, &mut chip).unwrap();
+
+    assert_eq!(chip.v[0xF], 1);
+}
+
+#[test]
+fn logic_test_resets_vf() {
+    let mut chip = prepare();
+
+    for &opcode in [0x8121, 0x8122, 0x8123].iter() {
+        chip.quirks.logic_resets_vf = false;
+        chip.v[0xF] = 5;
+        OpcodeHandler::next(opcode, &mut chip).unwrap();
+        assert_eq!(chip.v[0xF], 5);
+
+        chip.quirks.logic_resets_vf = true;
+        OpcodeHandler::next(opcode, &mut chip).unwrap();
+        assert_eq!(chip.v[0xF], 0);
+    }
+}
+
 #[test]
 fn srne_test_eq() {
     let mut chip = prepare();
@@ -318,7 +375,7 @@ fn srne_test_eq() {
     chip.v[1] = 0x12;
     chip.v[2] = 0x12;
 
-    OpcodeHandler::srne(0x9120, &mut chip);
+    OpcodeHandler::srne(0x9120, &mut chip).unwrap();
 
     assert_eq!(chip.program_counter, 12);
 }
@@ -332,7 +389,7 @@ fn srne_test_neq() {
     chip.v[1] = 0x12;
     chip.v[2] = 0x13;
 
-    OpcodeHandler::srne(0x9120, &mut chip);
+    OpcodeHandler::srne(0x9120, &mut chip).unwrap();
 
     assert_eq!(chip.program_counter, 14);
 }
@@ -343,11 +400,124 @@ fn jmpv0_test() {
     chip.v[0] = 0x0002;
     chip.program_counter.set(0x1);
 
-    OpcodeHandler::jmpv0(0xA123, &mut chip);
+    OpcodeHandler::jmpv0(0xA123, &mut chip).unwrap();
 
     assert_eq!(chip.program_counter, 0x0125)
 }
 
+#[test]
+fn jmpv0_test_uses_vx() {
+    let mut chip = prepare();
+    chip.quirks.jump_uses_vx = true;
+    chip.v[0] = 0x10;
+    chip.v[2] = 0x02;
+
+    OpcodeHandler::jmpv0(0xB234, &mut chip).unwrap();
+
+    assert_eq!(chip.program_counter, 0x0236)
+}
+
+#[test]
+fn print_test_wraps_or_clips() {
+    let mut chip = prepare();
+    chip.i = 0x300;
+    chip.memory[0x300] = 0xFF;
+    chip.v[0] = 60;
+
+    chip.quirks.clip_sprites = false;
+    OpcodeHandler::print(0xD011, &mut chip).unwrap();
+    assert_eq!(chip.screen.contents[0], 0xF000_0000_0000_000F);
+
+    chip.screen.clear();
+    chip.quirks.clip_sprites = true;
+    OpcodeHandler::print(0xD011, &mut chip).unwrap();
+    assert_eq!(chip.screen.contents[0], 0x0000_0000_0000_000F);
+}
+
+#[test]
+fn addivx_test() {
+    let mut chip = prepare();
+    chip.i = 0xFFE;
+    chip.v[1] = 0x3;
+    chip.v[0xF] = 5;
+
+    chip.quirks.add_i_sets_vf = false;
+    OpcodeHandler::addivx(0xF11E, &mut chip).unwrap();
+    assert_eq!(chip.i, 0x1001);
+    assert_eq!(chip.v[0xF], 5);
+
+    chip.i = 0xFFE;
+    chip.quirks.add_i_sets_vf = true;
+    OpcodeHandler::addivx(0xF11E, &mut chip).unwrap();
+    assert_eq!(chip.i, 0x1001);
+    assert_eq!(chip.v[0xF], 1);
+}
+
+#[test]
+fn ldiv0vx_test() {
+    let mut chip = prepare();
+    chip.i = 0x300;
+    chip.v[0] = 1;
+    chip.v[1] = 2;
+    chip.v[2] = 3;
+
+    let expected = [
+        (IndexIncrement::Unchanged, 0x300),
+        (IndexIncrement::ByX, 0x302),
+        (IndexIncrement::ByXPlusOne, 0x303),
+    ];
+
+    for &(increment, i) in expected.iter() {
+        chip.i = 0x300;
+        chip.quirks.load_store_increment = increment;
+
+        OpcodeHandler::ldiv0vx(0xF255, &mut chip).unwrap();
+
+        assert_eq!(chip.memory[0x300..0x304], [1, 2, 3, 0]);
+        assert_eq!(chip.i, i);
+    }
+}
+
+#[test]
+fn ldiv0vx_wraps_i_at_end_of_memory_test() {
+    let mut chip = Chip::with_mode(Mode::XoChip);
+    chip.quirks.load_store_increment = IndexIncrement::ByXPlusOne;
+    chip.i = 0xFFFF;
+    chip.v[0] = 7;
+
+    OpcodeHandler::ldiv0vx(0xF055, &mut chip).unwrap();
+    assert_eq!(chip.memory[0xFFFF], 7);
+    assert_eq!(chip.i, 0);
+
+    chip.i = 0xFFFF;
+    OpcodeHandler::ldv0vxi(0xF065, &mut chip).unwrap();
+    assert_eq!(chip.i, 0);
+}
+
+#[test]
+fn ldv0vxi_test() {
+    let mut chip = prepare();
+    chip.memory[0x300..0x303].copy_from_slice(&[1, 2, 3]);
+
+    let expected = [
+        (IndexIncrement::Unchanged, 0x300),
+        (IndexIncrement::ByX, 0x301),
+        (IndexIncrement::ByXPlusOne, 0x302),
+    ];
+
+    for &(increment, i) in expected.iter() {
+        chip.i = 0x300;
+        chip.quirks.load_store_increment = increment;
+
+        OpcodeHandler::ldv0vxi(0xF165, &mut chip).unwrap();
+
+        assert_eq!(chip.v[0], 1);
+        assert_eq!(chip.v[1], 2);
+        assert_eq!(chip.v[2], 0);
+        assert_eq!(chip.i, i);
+    }
+}
+
 #[test]
 fn skp_test() {
     let mut chip = prepare();
@@ -356,7 +526,7 @@ fn skp_test() {
     chip.keyboard.set_state(1, true);
     chip.program_counter.set(2);
 
-    OpcodeHandler::skp(0xE19E, &mut chip);
+    OpcodeHandler::skp(0xE19E, &mut chip).unwrap();
 
     assert_eq!(chip.program_counter, 4);
 }
@@ -369,7 +539,230 @@ fn sknp_test() {
     chip.keyboard.set_state(1, false);
     chip.program_counter.set(2);
 
-    OpcodeHandler::sknp(0xE1A1, &mut chip);
+    OpcodeHandler::sknp(0xE1A1, &mut chip).unwrap();
 
     assert_eq!(chip.program_counter, 4);
 }
+
+#[test]
+fn ldvxkey_test_pressed() {
+    let mut chip = prepare();
+
+    chip.keyboard.set_state(0xB, true);
+    chip.program_counter.set(2);
+
+    OpcodeHandler::ldvxkey(0xF30A, &mut chip).unwrap();
+    chip.step().unwrap();
+
+    assert!(chip.waiting_for_key());
+    assert_eq!(chip.program_counter, 2);
+
+    chip.keyboard.set_state(0xB, false);
+    chip.step().unwrap();
+
+    assert_eq!(chip.v[3], 0xB);
+    assert!(!chip.waiting_for_key());
+    assert_eq!(chip.program_counter, 4);
+}
+
+#[test]
+fn ldvxkey_test_not_pressed() {
+    let mut chip = prepare();
+
+    chip.program_counter.set(2);
+
+    OpcodeHandler::ldvxkey(0xF30A, &mut chip).unwrap();
+
+    assert_eq!(chip.v[3], 0);
+    assert_eq!(chip.program_counter, 2);
+    assert_eq!(
+        chip.key_wait,
+        Some(crate::KeyWait {
+            register: 3,
+            pressed: None
+        })
+    );
+
+    chip.delay_timer = 2;
+    chip.update_timers();
+    assert_eq!(chip.delay_timer, 1);
+}
+
+#[test]
+fn hires_and_lores_test() {
+    let mut chip = prepare();
+
+    OpcodeHandler::next(0x00FF, &mut chip).unwrap();
+    assert_eq!(chip.screen.width(), 128);
+    assert_eq!(chip.screen.height(), 64);
+
+    OpcodeHandler::next(0x00FE, &mut chip).unwrap();
+    assert_eq!(chip.screen.width(), 64);
+    assert_eq!(chip.screen.height(), 32);
+}
+
+#[test]
+fn scroll_test() {
+    let mut chip = prepare();
+    chip.screen.draw(8, 0, &[&0xFF]);
+
+    OpcodeHandler::next(0x00C3, &mut chip).unwrap();
+    assert!(chip.screen.is_lit(8, 3));
+
+    OpcodeHandler::next(0x00FB, &mut chip).unwrap();
+    assert!(chip.screen.is_lit(12, 3));
+
+    OpcodeHandler::next(0x00FC, &mut chip).unwrap();
+    OpcodeHandler::next(0x00FC, &mut chip).unwrap();
+    assert!(chip.screen.is_lit(4, 3));
+    assert!(!chip.screen.is_lit(12, 3));
+}
+
+#[test]
+fn exit_test() {
+    let mut chip = prepare();
+    chip.load_program(&[0x00, 0xFD, 0x60, 0x01]).unwrap();
+
+    chip.step().unwrap();
+    chip.step().unwrap();
+
+    assert!(chip.exited);
+    assert_eq!(chip.v[0], 0);
+}
+
+#[test]
+fn print_large_sprite_test() {
+    let mut chip = prepare();
+    chip.i = 0x300;
+    chip.memory[0x300] = 0xFF;
+    chip.memory[0x301] = 0xFF;
+    chip.memory[0x31F] = 0x01;
+
+    OpcodeHandler::print(0xD000, &mut chip).unwrap();
+
+    assert!(chip.screen.is_lit(15, 0));
+    assert!(chip.screen.is_lit(15, 15));
+    assert!(!chip.screen.is_lit(16, 0));
+    assert_eq!(chip.v[0xF], 0);
+}
+
+#[test]
+fn ldfvx_test() {
+    let mut chip = prepare();
+    chip.v[2] = 0x1A;
+
+    OpcodeHandler::ldfvx(0xF229, &mut chip).unwrap();
+
+    assert_eq!(chip.i, FONT_START + 50);
+}
+
+#[test]
+fn ldhfvx_test() {
+    let mut chip = prepare();
+    chip.v[2] = 3;
+
+    OpcodeHandler::ldhfvx(0xF230, &mut chip).unwrap();
+
+    assert_eq!(chip.i, BIG_FONT_START + 30);
+    assert_eq!(chip.memory[chip.i as usize..][..10], BIG_FONT[30..40]);
+}
+
+#[test]
+fn rpl_flags_test() {
+    let mut chip = prepare();
+    chip.v[0] = 1;
+    chip.v[1] = 2;
+    chip.v[2] = 3;
+
+    OpcodeHandler::ldrv0vx(0xF175, &mut chip).unwrap();
+    chip.v = Registers::new();
+    OpcodeHandler::ldv0vxr(0xF285, &mut chip).unwrap();
+
+    assert_eq!(chip.v[0], 1);
+    assert_eq!(chip.v[1], 2);
+    assert_eq!(chip.v[2], 0);
+}
+
+#[test]
+fn xo_opcodes_need_xo_mode() {
+    let mut chip = prepare();
+
+    assert!(OpcodeHandler::next(0xF001, &mut chip).is_err());
+    assert!(OpcodeHandler::next(0x5012, &mut chip).is_err());
+    assert!(OpcodeHandler::next(0x00D1, &mut chip).is_err());
+
+    let mut chip = Chip::with_mode(Mode::XoChip);
+    assert!(OpcodeHandler::next(0xF001, &mut chip).is_ok());
+}
+
+#[test]
+fn ldilong_test() {
+    let mut chip = Chip::with_mode(Mode::XoChip);
+    chip.load_program(&[0xF0, 0x00, 0xAB, 0xCD, 0x60, 0x01])
+        .unwrap();
+
+    chip.step().unwrap();
+
+    assert_eq!(chip.i, 0xABCD);
+    assert_eq!(chip.program_counter, PROGRAM_START + 4);
+}
+
+#[test]
+fn skip_jumps_over_ldilong_test() {
+    let mut chip = Chip::with_mode(Mode::XoChip);
+    chip.load_program(&[0x30, 0x00, 0xF0, 0x00, 0xAB, 0xCD])
+        .unwrap();
+
+    chip.step().unwrap();
+
+    assert_eq!(chip.program_counter, PROGRAM_START + 6);
+    assert_eq!(chip.i, 0);
+}
+
+#[test]
+fn save_load_range_test() {
+    let mut chip = Chip::with_mode(Mode::XoChip);
+    chip.i = 0x8000;
+    chip.v[2] = 1;
+    chip.v[3] = 2;
+    chip.v[4] = 3;
+
+    OpcodeHandler::save_range(0x5242, &mut chip).unwrap();
+    assert_eq!(chip.memory[0x8000..0x8003], [1, 2, 3]);
+    assert_eq!(chip.i, 0x8000);
+
+    OpcodeHandler::load_range(0x5A83, &mut chip).unwrap();
+    assert_eq!(chip.v[0xA], 1);
+    assert_eq!(chip.v[0x9], 2);
+    assert_eq!(chip.v[0x8], 3);
+}
+
+#[test]
+fn print_on_both_planes_test() {
+    let mut chip = Chip::with_mode(Mode::XoChip);
+    chip.i = 0x300;
+    chip.memory[0x300] = 0b1000_0000;
+    chip.memory[0x301] = 0b1100_0000;
+
+    OpcodeHandler::planes(0xF301, &mut chip).unwrap();
+    OpcodeHandler::print(0xD001, &mut chip).unwrap();
+
+    assert_eq!(chip.screen.pixel(0, 0), 3);
+    assert_eq!(chip.screen.pixel(1, 0), 2);
+}
+
+#[test]
+fn audio_and_pitch_test() {
+    let mut chip = Chip::with_mode(Mode::XoChip);
+    chip.i = 0x300;
+    for (idx, byte) in chip.memory[0x300..0x310].iter_mut().enumerate() {
+        *byte = idx as u8;
+    }
+    chip.v[5] = 112;
+
+    OpcodeHandler::audio(0xF002, &mut chip).unwrap();
+    OpcodeHandler::pitch(0xF53A, &mut chip).unwrap();
+
+    assert_eq!(chip.audio_pattern[15], 15);
+    assert_eq!(chip.pitch, 112);
+}
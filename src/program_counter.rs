@@ -15,7 +15,7 @@ impl ProgramCounter {
     }
 
     pub fn increment(&mut self) {
-        self.0 += 2;
+        self.0 = self.0.wrapping_add(2);
     }
 }
 
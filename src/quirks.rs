@@ -0,0 +1,187 @@
+use std::fmt;
+use std::str::FromStr;
+
+/// How `FX55` and `FX65` leave the I register after they're done.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum IndexIncrement {
+    /// I is left untouched (SUPER-CHIP).
+    Unchanged,
+    /// I ends up pointing at the last register's byte (CHIP-48).
+    ByX,
+    /// I ends up pointing past the last register's byte (COSMAC VIP).
+    ByXPlusOne,
+}
+
+/// Behaviours that differ between CHIP-8 interpreters.
+///
+/// ROMs are usually written against one interpreter, so pick the preset they expect.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Quirks {
+    /// `8XY6`/`8XYE` shift V[`Y`] into V[`X`] instead of shifting V[`X`] in place.
+    pub shift_uses_vy: bool,
+
+    /// What `FX55`/`FX65` do to I.
+    pub load_store_increment: IndexIncrement,
+
+    /// `BNNN` jumps to `NNN` plus V[`X`] (`X` being the highest nibble of `NNN`) instead of V0.
+    pub jump_uses_vx: bool,
+
+    /// `8XY1`, `8XY2` and `8XY3` set VF to 0.
+    pub logic_resets_vf: bool,
+
+    /// Sprites are cut off at the screen edges instead of wrapping to the other side.
+    pub clip_sprites: bool,
+
+    /// `FX1E` sets VF when I goes past the addressable memory.
+    pub add_i_sets_vf: bool,
+}
+
+/// Named quirk presets, as accepted on the command line.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Profile {
+    Classic,
+    CosmacVip,
+    Chip48,
+    SuperChip,
+    Modern,
+}
+
+impl Profile {
+    pub const ALL: [Profile; 5] = [
+        Profile::Classic,
+        Profile::CosmacVip,
+        Profile::Chip48,
+        Profile::SuperChip,
+        Profile::Modern,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Profile::Classic => "classic",
+            Profile::CosmacVip => "vip",
+            Profile::Chip48 => "chip48",
+            Profile::SuperChip => "schip",
+            Profile::Modern => "modern",
+        }
+    }
+
+    pub fn quirks(self) -> Quirks {
+        match self {
+            Profile::Classic => Quirks::classic(),
+            Profile::CosmacVip => Quirks::cosmac_vip(),
+            Profile::Chip48 => Quirks::chip48(),
+            Profile::SuperChip => Quirks::super_chip(),
+            Profile::Modern => Quirks::modern(),
+        }
+    }
+}
+
+impl fmt::Display for Profile {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for Profile {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Profile::ALL
+            .iter()
+            .copied()
+            .find(|profile| profile.name() == s)
+            .ok_or_else(|| {
+                let names: Vec<_> = Profile::ALL.iter().map(|p| p.name()).collect();
+                format!(
+                    "unknown quirk profile '{}', expected one of {}",
+                    s,
+                    names.join(", ")
+                )
+            })
+    }
+}
+
+impl Quirks {
+    /// What this emulator did before quirks were configurable, after Cowgod's technical
+    /// reference: shifts and `FX55`/`FX65` leave V[`Y`] and I alone and sprites wrap.
+    /// `FX1E` sets VF when I goes past 0xFFF like the Amiga interpreter, where it used to
+    /// clear VF unless I wrapped around at 0xFFFF.
+    pub fn classic() -> Self {
+        Quirks {
+            shift_uses_vy: false,
+            load_store_increment: IndexIncrement::Unchanged,
+            jump_uses_vx: false,
+            logic_resets_vf: false,
+            clip_sprites: false,
+            add_i_sets_vf: true,
+        }
+    }
+
+    /// The original interpreter on the RCA COSMAC VIP.
+    pub fn cosmac_vip() -> Self {
+        Quirks {
+            shift_uses_vy: true,
+            load_store_increment: IndexIncrement::ByXPlusOne,
+            jump_uses_vx: false,
+            logic_resets_vf: true,
+            clip_sprites: true,
+            add_i_sets_vf: false,
+        }
+    }
+
+    /// CHIP-48 on the HP-48 calculators.
+    pub fn chip48() -> Self {
+        Quirks {
+            shift_uses_vy: false,
+            load_store_increment: IndexIncrement::ByX,
+            jump_uses_vx: true,
+            logic_resets_vf: false,
+            clip_sprites: true,
+            add_i_sets_vf: false,
+        }
+    }
+
+    /// SUPER-CHIP 1.1.
+    pub fn super_chip() -> Self {
+        Quirks {
+            shift_uses_vy: false,
+            load_store_increment: IndexIncrement::Unchanged,
+            jump_uses_vx: true,
+            logic_resets_vf: false,
+            clip_sprites: true,
+            add_i_sets_vf: false,
+        }
+    }
+
+    /// What most present day interpreters and Octo do.
+    pub fn modern() -> Self {
+        Quirks {
+            shift_uses_vy: true,
+            load_store_increment: IndexIncrement::ByXPlusOne,
+            jump_uses_vx: false,
+            logic_resets_vf: false,
+            clip_sprites: false,
+            add_i_sets_vf: false,
+        }
+    }
+}
+
+impl Default for Quirks {
+    fn default() -> Self {
+        Quirks::classic()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_profile_names() {
+        for profile in Profile::ALL.iter() {
+            assert_eq!(profile.name().parse(), Ok(*profile));
+        }
+
+        assert!("amiga".parse::<Profile>().is_err());
+    }
+}
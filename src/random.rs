@@ -0,0 +1,121 @@
+use std::fmt;
+use std::str::FromStr;
+
+/// How `CXNN` comes up with its numbers.
+///
+/// The COSMAC VIP's own generator isn't one of them yet: it adds up bytes of the VIP
+/// interpreter's code page and a counter its interrupt routine advances, so it needs that
+/// page and a model of the interrupt to give the VIP's numbers.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum Algorithm {
+    /// SplitMix64, good quality and the same on every platform.
+    #[default]
+    SplitMix,
+}
+
+impl Algorithm {
+    pub const ALL: [Algorithm; 1] = [Algorithm::SplitMix];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Algorithm::SplitMix => "splitmix",
+        }
+    }
+}
+
+impl fmt::Display for Algorithm {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for Algorithm {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Algorithm::ALL
+            .iter()
+            .copied()
+            .find(|algorithm| algorithm.name() == s)
+            .ok_or_else(|| format!("unknown random number generator '{}', expected splitmix", s))
+    }
+}
+
+/// Deterministic random number generator, the whole state is `algorithm` and `state`
+/// so it can be saved and restored along with the machine.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Random {
+    pub algorithm: Algorithm,
+    pub state: u64,
+}
+
+impl Random {
+    pub fn new(algorithm: Algorithm, seed: u64) -> Self {
+        Random {
+            algorithm,
+            state: seed,
+        }
+    }
+
+    /// Generator with a seed nobody picked, runs won't repeat.
+    pub fn from_entropy(algorithm: Algorithm) -> Self {
+        Random::new(algorithm, rand::random())
+    }
+
+    /// Next random byte.
+    pub fn next_byte(&mut self) -> u8 {
+        match self.algorithm {
+            Algorithm::SplitMix => {
+                self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+
+                let mut z = self.state;
+                z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+                z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+                z ^= z >> 31;
+
+                (z >> 56) as u8
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sequence(random: &mut Random) -> Vec<u8> {
+        (0..8).map(|_| random.next_byte()).collect()
+    }
+
+    #[test]
+    fn same_seed_same_numbers() {
+        for algorithm in Algorithm::ALL.iter() {
+            let first = sequence(&mut Random::new(*algorithm, 42));
+            let second = sequence(&mut Random::new(*algorithm, 42));
+
+            assert_eq!(first, second);
+        }
+
+        assert_ne!(
+            sequence(&mut Random::new(Algorithm::SplitMix, 42)),
+            sequence(&mut Random::new(Algorithm::SplitMix, 43))
+        );
+    }
+
+    #[test]
+    fn splitmix_is_portable() {
+        let mut random = Random::new(Algorithm::SplitMix, 0);
+
+        assert_eq!(
+            sequence(&mut random),
+            [0xE2, 0x6E, 0x06, 0xF8, 0x1B, 0x53, 0x2C, 0xC5]
+        );
+    }
+
+    #[test]
+    fn parses_names() {
+        assert_eq!("splitmix".parse(), Ok(Algorithm::SplitMix));
+        assert!("mersenne".parse::<Algorithm>().is_err());
+        assert!("vip".parse::<Algorithm>().is_err());
+    }
+}
@@ -38,6 +38,12 @@ impl Registers {
     }
 }
 
+impl Default for Registers {
+    fn default() -> Self {
+        Registers::new()
+    }
+}
+
 impl Index<u8> for Registers {
     type Output = u8;
 
@@ -0,0 +1,197 @@
+use crate::state;
+use crate::Chip;
+use std::collections::VecDeque;
+
+/// Runs of unchanged bytes shorter than this don't end a run of changes in a delta,
+/// a new run header would cost more.
+const MIN_GAP: usize = 8;
+
+/// Ring buffer of machine snapshots for going back in time.
+///
+/// Only the newest snapshot is kept whole, every older one is stored as the difference
+/// to the one after it, so recording every frame stays cheap.
+#[derive(Debug)]
+pub struct Rewind {
+    capacity: usize,
+    newest: Option<Vec<u8>>,
+
+    /// `deltas[i]` turns snapshot `i + 1` back into snapshot `i`, the last one turns `newest`
+    /// into the snapshot before it.
+    deltas: VecDeque<Vec<u8>>,
+}
+
+impl Rewind {
+    /// Buffer keeping at most `capacity` snapshots, the oldest ones are dropped first.
+    pub fn new(capacity: usize) -> Self {
+        Rewind {
+            capacity,
+            newest: None,
+            deltas: VecDeque::new(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.deltas.len() + self.newest.is_some() as usize
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.newest.is_none()
+    }
+
+    pub fn clear(&mut self) {
+        self.newest = None;
+        self.deltas.clear();
+    }
+
+    /// Remember the current state of `chip`.
+    pub fn record(&mut self, chip: &Chip) {
+        let snapshot = state::save(chip);
+
+        match self.newest.take() {
+            Some(previous) if previous.len() == snapshot.len() => {
+                self.deltas.push_back(delta(&snapshot, &previous));
+            }
+            Some(_) => self.deltas.clear(),
+            None => (),
+        }
+        self.newest = Some(snapshot);
+
+        while self.len() > self.capacity.max(1) {
+            self.deltas.pop_front();
+        }
+    }
+
+    /// Restore `chip` to the last recorded snapshot and forget it,
+    /// returns `false` if there's nothing left to go back to.
+    pub fn rewind(&mut self, chip: &mut Chip) -> bool {
+        let snapshot = match self.newest.take() {
+            Some(snapshot) => snapshot,
+            None => return false,
+        };
+
+        state::load(chip, &snapshot).expect("snapshots are states of the same machine");
+
+        self.newest = self.deltas.pop_back().map(|delta| {
+            let mut older = snapshot;
+            apply(&mut older, &delta);
+            older
+        });
+
+        true
+    }
+}
+
+/// Changes turning `from` into `to`, which have the same length.
+///
+/// A list of runs, each a 4-byte offset from the end of the previous run, a 2-byte length and
+/// that many bytes to XOR in. XOR makes the same delta work in both directions.
+fn delta(from: &[u8], to: &[u8]) -> Vec<u8> {
+    let mut out = Vec::new();
+    let mut position = 0;
+    let mut idx = 0;
+
+    while idx < from.len() {
+        if from[idx] == to[idx] {
+            idx += 1;
+            continue;
+        }
+
+        let start = idx;
+        let mut end = idx + 1;
+        let mut unchanged = 0;
+        for scan in start + 1..from.len().min(start + u16::MAX as usize) {
+            if from[scan] != to[scan] {
+                unchanged = 0;
+                end = scan + 1;
+            } else {
+                unchanged += 1;
+                if unchanged == MIN_GAP {
+                    break;
+                }
+            }
+        }
+
+        out.extend(&((start - position) as u32).to_be_bytes());
+        out.extend(&((end - start) as u16).to_be_bytes());
+        out.extend(
+            from[start..end]
+                .iter()
+                .zip(&to[start..end])
+                .map(|(a, b)| a ^ b),
+        );
+
+        position = end;
+        idx = end;
+    }
+
+    out
+}
+
+fn apply(data: &mut [u8], delta: &[u8]) {
+    let mut position = 0;
+    let mut rest = delta;
+
+    while rest.len() >= 6 {
+        let skip = u32::from_be_bytes([rest[0], rest[1], rest[2], rest[3]]) as usize;
+        let len = u16::from_be_bytes([rest[4], rest[5]]) as usize;
+        let (changes, next) = rest[6..].split_at(len);
+
+        position += skip;
+        for (byte, change) in data[position..position + len].iter_mut().zip(changes) {
+            *byte ^= change;
+        }
+
+        position += len;
+        rest = next;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn delta_round_trips() {
+        let from: Vec<u8> = (0..200).map(|idx| idx as u8).collect();
+        let mut to = from.clone();
+        to[3] = 0xFF;
+        to[5] = 0xFF;
+        to[150] = 0;
+        to[199] = 7;
+
+        let delta = delta(&from, &to);
+        assert!(delta.len() < 30);
+
+        let mut restored = from.clone();
+        apply(&mut restored, &delta);
+        assert_eq!(restored, to);
+
+        apply(&mut restored, &delta);
+        assert_eq!(restored, from);
+    }
+
+    #[test]
+    fn rewinds_in_recording_order() {
+        let mut chip = Chip::new();
+        chip.load_program(&[0x70, 0x01, 0x12, 0x00]).unwrap();
+        let mut rewind = Rewind::new(3);
+
+        for _ in 0..5 {
+            rewind.record(&chip);
+            chip.step().unwrap();
+            chip.step().unwrap();
+        }
+
+        assert_eq!(chip.v[0], 5);
+        assert_eq!(rewind.len(), 3);
+
+        for expected in [4, 3, 2].iter() {
+            assert!(rewind.rewind(&mut chip));
+            assert_eq!(chip.v[0], *expected);
+            assert_eq!(chip.program_counter, 0x200);
+        }
+
+        assert!(!rewind.rewind(&mut chip));
+        assert_eq!(chip.v[0], 2);
+    }
+}
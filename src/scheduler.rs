@@ -0,0 +1,238 @@
+use super::error::Chip8Error;
+use super::Chip;
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Length of one 60 Hz frame, the rate timers count down at.
+pub const FRAME: Duration = Duration::from_nanos(1_000_000_000 / 60);
+
+/// Frames run back to back at most when the host falls behind, anything older is dropped.
+pub const MAX_CATCH_UP: u32 = 4;
+
+/// Source of wall-clock time, swapped out in tests.
+pub trait Clock {
+    /// Time elapsed since some fixed point in the past.
+    fn now(&self) -> Duration;
+
+    fn sleep(&mut self, duration: Duration);
+}
+
+#[derive(Debug)]
+pub struct SystemClock {
+    start: Instant,
+}
+
+impl SystemClock {
+    pub fn new() -> Self {
+        SystemClock {
+            start: Instant::now(),
+        }
+    }
+}
+
+impl Default for SystemClock {
+    fn default() -> Self {
+        SystemClock::new()
+    }
+}
+
+impl Clock for SystemClock {
+    fn now(&self) -> Duration {
+        self.start.elapsed()
+    }
+
+    fn sleep(&mut self, duration: Duration) {
+        thread::sleep(duration);
+    }
+}
+
+/// Paces execution: a fixed number of instructions and exactly one timer tick per 60 Hz frame.
+#[derive(Debug)]
+pub struct Scheduler<C: Clock = SystemClock> {
+    clock: C,
+    pub instructions_per_frame: u32,
+    next_frame: Duration,
+}
+
+impl Scheduler<SystemClock> {
+    pub fn new(instructions_per_frame: u32) -> Self {
+        Scheduler::with_clock(SystemClock::new(), instructions_per_frame)
+    }
+}
+
+impl<C: Clock> Scheduler<C> {
+    pub fn with_clock(clock: C, instructions_per_frame: u32) -> Self {
+        let next_frame = clock.now();
+
+        Scheduler {
+            clock,
+            instructions_per_frame,
+            next_frame,
+        }
+    }
+
+    /// Sleep until the next frame is due and return how many frames should run now.
+    ///
+    /// Usually that's one, but after a stall up to `MAX_CATCH_UP` frames are returned
+    /// so the timers keep up with the wall clock.
+    pub fn wait_for_frame(&mut self) -> u32 {
+        let now = self.clock.now();
+
+        if now < self.next_frame {
+            self.clock.sleep(self.next_frame - now);
+            self.next_frame += FRAME;
+            return 1;
+        }
+
+        let behind = ((now - self.next_frame).as_nanos() / FRAME.as_nanos()) as u32 + 1;
+
+        if behind > MAX_CATCH_UP {
+            self.next_frame = now + FRAME;
+            MAX_CATCH_UP
+        } else {
+            self.next_frame += FRAME * behind;
+            behind
+        }
+    }
+
+    /// Execute one frame worth of instructions, then tick the timers once.
+    ///
+    /// The rest of the frame is skipped while the CPU waits for a key.
+    pub fn run_frame(&self, chip: &mut Chip) -> Result<(), Chip8Error> {
+        for _ in 0..self.instructions_per_frame {
+            if chip.exited {
+                break;
+            }
+
+            chip.step()?;
+
+            if chip.waiting_for_key() {
+                break;
+            }
+        }
+
+        chip.update_timers();
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, Default)]
+    struct FakeClock {
+        now: Duration,
+        slept: Duration,
+    }
+
+    impl Clock for FakeClock {
+        fn now(&self) -> Duration {
+            self.now
+        }
+
+        fn sleep(&mut self, duration: Duration) {
+            self.now += duration;
+            self.slept += duration;
+        }
+    }
+
+    /// `7001` - add 1 to V0, in a loop.
+    fn counting_chip() -> Chip {
+        let mut chip = Chip::new();
+        chip.load_program(&[0x70, 0x01, 0x12, 0x00]).unwrap();
+        chip
+    }
+
+    #[test]
+    fn runs_configured_instructions_per_frame() {
+        let mut chip = counting_chip();
+        chip.delay_timer = 10;
+
+        let scheduler = Scheduler::with_clock(FakeClock::default(), 6);
+        scheduler.run_frame(&mut chip).unwrap();
+
+        assert_eq!(chip.v[0], 3);
+        assert_eq!(chip.delay_timer, 9);
+    }
+
+    #[test]
+    fn timers_tick_once_per_frame() {
+        let mut chip = counting_chip();
+        chip.delay_timer = 60;
+        chip.sound_timer = 30;
+
+        let mut scheduler = Scheduler::with_clock(FakeClock::default(), 100);
+        let mut frames = 0;
+        while frames < 30 {
+            for _ in 0..scheduler.wait_for_frame() {
+                scheduler.run_frame(&mut chip).unwrap();
+                frames += 1;
+            }
+        }
+
+        assert_eq!(chip.delay_timer, 30);
+        assert_eq!(chip.sound_timer, 0);
+    }
+
+    #[test]
+    fn keeps_timers_running_while_waiting_for_key() {
+        let mut chip = Chip::new();
+        // LD V2, K; ADD V0, 1; JP 0x204
+        chip.load_program(&[0xF2, 0x0A, 0x70, 0x01, 0x12, 0x04])
+            .unwrap();
+        chip.delay_timer = 10;
+        let scheduler = Scheduler::with_clock(FakeClock::default(), 10);
+
+        scheduler.run_frame(&mut chip).unwrap();
+        chip.keyboard.set_state(0x5, true);
+        scheduler.run_frame(&mut chip).unwrap();
+
+        assert!(chip.waiting_for_key());
+        assert_eq!(chip.delay_timer, 8);
+
+        chip.keyboard.set_state(0x5, false);
+        scheduler.run_frame(&mut chip).unwrap();
+
+        assert!(!chip.waiting_for_key());
+        assert_eq!(chip.v[2], 0x5);
+        assert_eq!(chip.v[0], 1);
+        assert_eq!(chip.delay_timer, 7);
+    }
+
+    #[test]
+    fn throttles_to_wall_clock() {
+        let mut scheduler = Scheduler::with_clock(FakeClock::default(), 10);
+
+        for _ in 0..61 {
+            assert_eq!(scheduler.wait_for_frame(), 1);
+        }
+
+        assert_eq!(scheduler.clock.now, FRAME * 60);
+    }
+
+    #[test]
+    fn catches_up_after_stall() {
+        let mut scheduler = Scheduler::with_clock(FakeClock::default(), 10);
+        scheduler.wait_for_frame();
+
+        scheduler.clock.now += FRAME * 3;
+
+        assert_eq!(scheduler.wait_for_frame(), 3);
+        assert_eq!(scheduler.clock.slept, Duration::from_secs(0));
+        assert_eq!(scheduler.wait_for_frame(), 1);
+        assert_eq!(scheduler.clock.slept, FRAME);
+    }
+
+    #[test]
+    fn drops_frames_after_long_stall() {
+        let mut scheduler = Scheduler::with_clock(FakeClock::default(), 10);
+
+        scheduler.clock.now += Duration::from_secs(5);
+
+        assert_eq!(scheduler.wait_for_frame(), MAX_CATCH_UP);
+        assert_eq!(scheduler.wait_for_frame(), 1);
+        assert_eq!(scheduler.clock.slept, FRAME);
+    }
+}
@@ -0,0 +1,404 @@
+use crate::display::HIRES_HEIGHT;
+use crate::mode::Mode;
+use crate::random::{Algorithm, Random};
+use crate::{Chip, KeyWait};
+use std::error::Error;
+use std::fmt;
+
+/// First bytes of every save state.
+const MAGIC: &[u8; 4] = b"C8ST";
+
+/// Bumped whenever the layout below changes, older states are rejected.
+pub const VERSION: u8 = 5;
+
+/// Stored for the parts of `Chip::key_wait` that aren't there.
+const NONE: u8 = 0xFF;
+
+/// Why a save state could not be restored.
+#[derive(Debug, Clone, PartialEq)]
+pub enum StateError {
+    /// The data doesn't start with the save state magic.
+    NotAState,
+
+    UnsupportedVersion {
+        found: u8,
+    },
+
+    /// The state was saved in a different `Mode` than the machine it's loaded into.
+    ModeMismatch {
+        state: Mode,
+        machine: Mode,
+    },
+
+    /// The data ends before the state does.
+    Truncated,
+
+    /// A value is out of range or the data goes on after the state, e.g. from a damaged
+    /// or hand-edited file.
+    Corrupt(&'static str),
+}
+
+impl fmt::Display for StateError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            StateError::NotAState => write!(f, "not a save state"),
+            StateError::UnsupportedVersion { found } => write!(
+                f,
+                "save state version {} is not supported, expected version {}",
+                found, VERSION
+            ),
+            StateError::ModeMismatch { state, machine } => write!(
+                f,
+                "save state is for {} but the machine runs {}",
+                state, machine
+            ),
+            StateError::Truncated => write!(f, "save state is truncated"),
+            StateError::Corrupt(problem) => write!(f, "save state is damaged: {}", problem),
+        }
+    }
+}
+
+impl Error for StateError {}
+
+/// Snapshot of everything that changes while a program runs.
+///
+/// Settings picked up front, like the quirks, are not part of the state.
+pub fn save(chip: &Chip) -> Vec<u8> {
+    let mut out = Vec::with_capacity(chip.memory.len() + 2 * 16 * HIRES_HEIGHT + 128);
+
+    out.extend(MAGIC);
+    out.push(VERSION);
+    out.push(mode_id(chip.mode));
+
+    out.extend(&chip.memory);
+    out.extend((0..16).map(|idx| chip.v[idx]));
+    out.extend(&chip.i.to_be_bytes());
+    out.extend(&chip.program_counter.get().to_be_bytes());
+    for entry in chip.stack.iter() {
+        out.extend(&entry.to_be_bytes());
+    }
+    out.push(chip.stack_pointer);
+    out.push(chip.delay_timer);
+    out.push(chip.sound_timer);
+
+    out.push(chip.screen.hires as u8);
+    out.push(chip.screen.selected_planes);
+    for row in chip
+        .screen
+        .contents
+        .iter()
+        .chain(chip.screen.second_plane.iter())
+    {
+        out.extend(&row.to_be_bytes());
+    }
+
+    out.extend((0..16).map(|key| chip.keyboard.is_pressed(key) as u8));
+    out.push(chip.key_wait.map_or(NONE, |wait| wait.register));
+    out.push(chip.key_wait.and_then(|wait| wait.pressed).unwrap_or(NONE));
+    out.extend(&chip.rpl_flags);
+    out.push(chip.exited as u8);
+    out.extend(&chip.audio_pattern);
+    out.push(chip.pitch);
+    out.push(algorithm_id(chip.rng.algorithm));
+    out.extend(&chip.rng.state.to_be_bytes());
+
+    out
+}
+
+/// Restore a state made by `save`, `chip` is left untouched if it can't be.
+pub fn load(chip: &mut Chip, state: &[u8]) -> Result<(), StateError> {
+    let mut reader = Reader(state);
+
+    if reader.take(MAGIC.len())? != MAGIC {
+        return Err(StateError::NotAState);
+    }
+
+    let version = reader.byte()?;
+    if version != VERSION {
+        return Err(StateError::UnsupportedVersion { found: version });
+    }
+
+    let mode = reader.byte()?;
+    if mode != mode_id(chip.mode) {
+        let state = Mode::ALL
+            .iter()
+            .copied()
+            .find(|other| mode_id(*other) == mode)
+            .ok_or(StateError::Corrupt("unknown machine mode"))?;
+
+        return Err(StateError::ModeMismatch {
+            state,
+            machine: chip.mode,
+        });
+    }
+
+    let memory = reader.take(chip.memory.len())?;
+    let v = reader.take(16)?;
+    let i = reader.word()?;
+    let pc = reader.word()?;
+    let mut stack = [0; 16];
+    for entry in stack.iter_mut() {
+        *entry = reader.word()?;
+    }
+    let stack_pointer = reader.byte()?;
+    if stack_pointer as usize > stack.len() {
+        return Err(StateError::Corrupt("stack pointer is out of range"));
+    }
+    let delay_timer = reader.byte()?;
+    let sound_timer = reader.byte()?;
+
+    let hires = reader.byte()? != 0;
+    let selected_planes = reader.byte()?;
+    if selected_planes > 0b11 {
+        return Err(StateError::Corrupt("selected planes are out of range"));
+    }
+    let mut planes = [[0; HIRES_HEIGHT]; 2];
+    for row in planes.iter_mut().flat_map(|plane| plane.iter_mut()) {
+        let mut bytes = [0; 16];
+        bytes.copy_from_slice(reader.take(16)?);
+        *row = u128::from_be_bytes(bytes);
+    }
+
+    let keys = reader.take(16)?;
+    let wait_register = reader.byte()?;
+    let wait_pressed = reader.byte()?;
+    if wait_register != NONE && wait_register > 0xF {
+        return Err(StateError::Corrupt("key wait register is out of range"));
+    }
+    if wait_pressed != NONE && wait_pressed > 0xF {
+        return Err(StateError::Corrupt("key wait key is out of range"));
+    }
+    let key_wait = Some(wait_register)
+        .filter(|register| *register != NONE)
+        .map(|register| KeyWait {
+            register,
+            pressed: Some(wait_pressed).filter(|key| *key != NONE),
+        });
+    let rpl_flags = reader.take(16)?;
+    let exited = reader.byte()? != 0;
+    let audio_pattern = reader.take(16)?;
+    let pitch = reader.byte()?;
+    let algorithm = reader.byte()?;
+    let algorithm = Algorithm::ALL
+        .iter()
+        .copied()
+        .find(|other| algorithm_id(*other) == algorithm)
+        .ok_or(StateError::Corrupt("unknown random number generator"))?;
+    let mut rng_state = [0; 8];
+    rng_state.copy_from_slice(reader.take(8)?);
+    if !reader.0.is_empty() {
+        return Err(StateError::Corrupt("data goes on after the state"));
+    }
+
+    chip.memory.copy_from_slice(memory);
+    for (idx, value) in v.iter().enumerate() {
+        chip.v[idx as u8] = *value;
+    }
+    chip.i = i;
+    chip.program_counter.set(pc);
+    chip.stack = stack;
+    chip.stack_pointer = stack_pointer;
+    chip.delay_timer = delay_timer;
+    chip.sound_timer = sound_timer;
+
+    let [first, second] = planes;
+    chip.screen.hires = hires;
+    chip.screen.selected_planes = selected_planes;
+    chip.screen.contents = first;
+    chip.screen.second_plane = second;
+    chip.screen.should_redraw = true;
+
+    for (key, pressed) in keys.iter().enumerate() {
+        chip.keyboard.set_state(key as u8, *pressed != 0);
+    }
+    chip.key_wait = key_wait;
+    chip.rpl_flags.copy_from_slice(rpl_flags);
+    chip.exited = exited;
+    chip.audio_pattern.copy_from_slice(audio_pattern);
+    chip.pitch = pitch;
+    chip.rng = Random::new(algorithm, u64::from_be_bytes(rng_state));
+
+    Ok(())
+}
+
+fn mode_id(mode: Mode) -> u8 {
+    match mode {
+        Mode::Chip8 => 0,
+        Mode::XoChip => 1,
+    }
+}
+
+fn algorithm_id(algorithm: Algorithm) -> u8 {
+    match algorithm {
+        Algorithm::SplitMix => 0,
+    }
+}
+
+struct Reader<'a>(&'a [u8]);
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8], StateError> {
+        if self.0.len() < len {
+            return Err(StateError::Truncated);
+        }
+
+        let (taken, rest) = self.0.split_at(len);
+        self.0 = rest;
+
+        Ok(taken)
+    }
+
+    fn byte(&mut self) -> Result<u8, StateError> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn word(&mut self) -> Result<u16, StateError> {
+        let bytes = self.take(2)?;
+        Ok(u16::from(bytes[0]) << 8 | u16::from(bytes[1]))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn restores_saved_state() {
+        let mut chip = Chip::new();
+        chip.load_program(&[0x60, 0x2A, 0xA3, 0x00, 0xD0, 0x05, 0x22, 0x00])
+            .unwrap();
+        for _ in 0..4 {
+            chip.step().unwrap();
+        }
+        chip.delay_timer = 30;
+        chip.keyboard.set_state(0xB, true);
+
+        let state = save(&chip);
+        let mut restored = Chip::new();
+        load(&mut restored, &state).unwrap();
+
+        assert_eq!(restored.memory, chip.memory);
+        assert_eq!(restored.v[0], 0x2A);
+        assert_eq!(restored.i, 0x300);
+        assert_eq!(restored.program_counter, 0x200);
+        assert_eq!(restored.stack_pointer, 1);
+        assert_eq!(restored.stack[0], 0x206);
+        assert_eq!(restored.delay_timer, 30);
+        assert_eq!(restored.screen.contents, chip.screen.contents);
+        assert!(restored.keyboard.is_pressed(0xB));
+        assert_eq!(save(&restored), state);
+    }
+
+    #[test]
+    fn restores_random_numbers() {
+        let mut chip = Chip::new();
+        chip.rng = Random::new(Algorithm::SplitMix, 7);
+        // RND V0, 0xFF; JP 0x200
+        chip.load_program(&[0xC0, 0xFF, 0x12, 0x00]).unwrap();
+        chip.step().unwrap();
+
+        let state = save(&chip);
+        let mut restored = Chip::new();
+        load(&mut restored, &state).unwrap();
+        assert_eq!(restored.rng, chip.rng);
+
+        for _ in 0..3 {
+            chip.step().unwrap();
+            chip.step().unwrap();
+            restored.step().unwrap();
+            restored.step().unwrap();
+            assert_eq!(restored.v[0], chip.v[0]);
+        }
+    }
+
+    #[test]
+    fn rejects_incompatible_states() {
+        let mut chip = Chip::new();
+        let mut state = save(&chip);
+
+        assert_eq!(load(&mut chip, b"PNG"), Err(StateError::Truncated));
+        assert_eq!(load(&mut chip, b"PNG-image"), Err(StateError::NotAState));
+        assert_eq!(load(&mut chip, &state[..100]), Err(StateError::Truncated));
+
+        state[4] = VERSION + 1;
+        assert_eq!(
+            load(&mut chip, &state),
+            Err(StateError::UnsupportedVersion { found: VERSION + 1 })
+        );
+
+        let mut longer = save(&chip);
+        longer.push(0);
+        assert_eq!(
+            load(&mut chip, &longer),
+            Err(StateError::Corrupt("data goes on after the state"))
+        );
+
+        let xo_state = save(&Chip::with_mode(Mode::XoChip));
+        assert_eq!(
+            load(&mut chip, &xo_state).unwrap_err().to_string(),
+            "save state is for xochip but the machine runs chip8"
+        );
+    }
+
+    #[test]
+    fn rejects_corrupt_states() {
+        let mut chip = Chip::new();
+        chip.load_program(&[0x22, 0x02, 0xF5, 0x0A]).unwrap();
+        chip.step().unwrap();
+        chip.step().unwrap();
+        let state = save(&chip);
+
+        // After the memory, registers, I, PC and stack, and before the RPL flags, exit
+        // flag, audio pattern, pitch and generator.
+        let mode = MAGIC.len() + 1;
+        let stack_pointer = mode + 1 + chip.memory.len() + 16 + 2 + 2 + 32;
+        let selected_planes = stack_pointer + 4;
+        let wait_register = state.len() - 2 - 16 - 1 - 16 - 1 - 1 - 8;
+        let algorithm = state.len() - 8 - 1;
+        assert_eq!(state[stack_pointer], 1);
+        assert_eq!(state[selected_planes], 1);
+        assert_eq!(state[wait_register], 5);
+
+        let mut corrupt = state.clone();
+        corrupt[mode] = 0xEE;
+        assert_eq!(
+            load(&mut chip, &corrupt),
+            Err(StateError::Corrupt("unknown machine mode"))
+        );
+
+        let mut corrupt = state.clone();
+        corrupt[algorithm] = 0xEE;
+        assert_eq!(
+            load(&mut chip, &corrupt),
+            Err(StateError::Corrupt("unknown random number generator"))
+        );
+
+        let mut corrupt = state.clone();
+        corrupt[selected_planes] = 4;
+        assert_eq!(
+            load(&mut chip, &corrupt),
+            Err(StateError::Corrupt("selected planes are out of range"))
+        );
+
+        let mut corrupt = state.clone();
+        corrupt[stack_pointer] = 17;
+        assert_eq!(
+            load(&mut chip, &corrupt).unwrap_err().to_string(),
+            "save state is damaged: stack pointer is out of range"
+        );
+
+        let mut corrupt = state.clone();
+        corrupt[wait_register] = 0x10;
+        assert_eq!(
+            load(&mut chip, &corrupt),
+            Err(StateError::Corrupt("key wait register is out of range"))
+        );
+
+        let mut corrupt = state;
+        corrupt[wait_register + 1] = 0x20;
+        assert_eq!(
+            load(&mut chip, &corrupt),
+            Err(StateError::Corrupt("key wait key is out of range"))
+        );
+    }
+}
@@ -0,0 +1,18 @@
+use chip8rust::state;
+use chip8rust::Chip;
+use std::fs;
+use std::path::Path;
+
+/// Write a save state of `chip` to `path`.
+pub fn save(chip: &Chip, path: &Path) -> Result<(), String> {
+    fs::write(path, state::save(chip))
+        .map_err(|e| format!("could not write '{}': {}", path.display(), e))
+}
+
+/// Restore `chip` from the save state at `path`.
+pub fn load(chip: &mut Chip, path: &Path) -> Result<(), String> {
+    let bytes =
+        fs::read(path).map_err(|e| format!("could not read '{}': {}", path.display(), e))?;
+
+    state::load(chip, &bytes).map_err(|e| format!("'{}': {}", path.display(), e))
+}
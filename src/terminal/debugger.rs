@@ -0,0 +1,359 @@
+use super::input::{self, KeyMap};
+use super::screen::{self, Renderer};
+use super::{
+    Frontend, KEYPAD_LAYOUT, LOAD_STATE_KEY, RENDER_MODE_KEY, SAVE_STATE_KEY, SCREENSHOT_KEY,
+    VIDEO_KEY,
+};
+use chip8rust::debugger::Debugger;
+use chip8rust::error::Chip8Error;
+use chip8rust::opcode::decode;
+use chip8rust::scheduler::Scheduler;
+use chip8rust::Chip;
+use crossterm::event::{KeyCode, KeyEvent};
+use crossterm::queue;
+use std::error::Error;
+use std::io::{stdout, Result, Write};
+
+const PANEL_WIDTH: usize = 40;
+
+/// Disassembly lines shown before the one at PC.
+const LINES_BEFORE_PC: u16 = 5;
+const DISASSEMBLY_LINES: u16 = 11;
+
+/// Lines `panel_lines` returns.
+const PANEL_LINES: usize = 20 + DISASSEMBLY_LINES as usize;
+
+/// Rows of stack entries, 5 to a row, enough for all 16.
+const STACK_ROWS: usize = 4;
+
+const HELP: &str = "F5 run/pause  F9 break at PC  F8 break at address  \
+                    F10 step over  F11 step  F7 step back  F2 save state  F4 load state  \
+                    F3 render mode  F12 screenshot  F1 record video  Ctrl-C quit";
+
+/// What the debugger keys asked for.
+enum Action {
+    TogglePause,
+    Step,
+    StepOver,
+    StepBack,
+    BreakAtPc,
+    Break(u16),
+    State(KeyCode),
+    CycleRenderMode,
+    Screenshot,
+    ToggleVideo,
+}
+
+/// Run the chip under the debugger, starting paused, until the user quits.
+pub fn run(
+    chip: Chip,
+    mut key_map: KeyMap,
+    scheduler: Scheduler,
+    mut frontend: Frontend,
+    debugger: Debugger,
+) -> std::result::Result<(), String> {
+    frontend.renderer.reserve(2 + PANEL_WIDTH as u16, 2);
+
+    screen::init().map_err(|e| e.to_string())?;
+    key_map.release_events = input::enable_release_events().map_err(|e| e.to_string())?;
+    let release_events = key_map.release_events;
+
+    let result = run_loop(chip, key_map, scheduler, &mut frontend, debugger);
+
+    if release_events {
+        input::disable_release_events().map_err(|e| e.to_string())?;
+    }
+    screen::restore().map_err(|e| e.to_string())?;
+    frontend.finish()?;
+
+    result.map_err(|e| e.to_string())
+}
+
+fn run_loop(
+    mut chip: Chip,
+    mut key_map: KeyMap,
+    mut scheduler: Scheduler,
+    frontend: &mut Frontend,
+    mut debugger: Debugger,
+) -> std::result::Result<(), Box<dyn Error>> {
+    let mut prompt = None;
+    let mut message = String::new();
+
+    loop {
+        let mut actions = Vec::new();
+        let handle = |event| handle_key(event, &mut prompt, &mut actions);
+        if !key_map.register_key_press(&mut chip.keyboard, handle)? {
+            break;
+        }
+
+        for action in actions {
+            message.clear();
+
+            let result = match action {
+                Action::Step => debugger.step(&mut chip),
+                Action::StepOver => debugger.step_over(&mut chip),
+                Action::StepBack => {
+                    if !debugger.step_back(&mut chip) {
+                        message = "no earlier instruction to go back to".to_string();
+                    }
+                    Ok(())
+                }
+                Action::TogglePause if debugger.paused => {
+                    debugger.resume();
+                    Ok(())
+                }
+                Action::TogglePause => {
+                    debugger.pause();
+                    Ok(())
+                }
+                Action::BreakAtPc => {
+                    debugger.toggle_breakpoint(chip.program_counter.get());
+                    Ok(())
+                }
+                Action::Break(address) => {
+                    debugger.toggle_breakpoint(address);
+                    Ok(())
+                }
+                Action::State(key) => {
+                    message = super::state_hotkey(key, &mut chip, &frontend.state_path);
+                    Ok(())
+                }
+                Action::CycleRenderMode => {
+                    frontend.renderer.cycle_mode();
+                    message = format!("{} rendering", frontend.renderer.mode);
+                    Ok(())
+                }
+                Action::Screenshot => {
+                    message = frontend.screenshot(&chip);
+                    Ok(())
+                }
+                Action::ToggleVideo => {
+                    message = frontend.toggle_video();
+                    Ok(())
+                }
+            };
+
+            report(result, &mut debugger, &mut message);
+        }
+
+        for _ in 0..scheduler.wait_for_frame() {
+            if debugger.paused {
+                continue;
+            }
+
+            let result = debugger.run(&mut chip, scheduler.instructions_per_frame);
+            chip.update_timers();
+            frontend.frame(&chip)?;
+
+            report(result, &mut debugger, &mut message);
+        }
+
+        if chip.exited && !debugger.paused {
+            debugger.pause();
+            message = "program exited".to_string();
+        }
+
+        let renderer = &mut frontend.renderer;
+        renderer.update(&chip.screen, chip.screen.should_redraw)?;
+        chip.screen.should_redraw = false;
+
+        draw_panels(&chip, renderer, &debugger, &prompt, &message)?;
+    }
+
+    Ok(())
+}
+
+/// Pause on errors instead of quitting, so the machine state can be inspected.
+fn report(
+    result: std::result::Result<(), Chip8Error>,
+    debugger: &mut Debugger,
+    message: &mut String,
+) {
+    if let Err(e) = result {
+        debugger.pause();
+        *message = e.to_string();
+    }
+}
+
+/// Returns `true` if the key was meant for the debugger rather than the keypad.
+fn handle_key(event: KeyEvent, prompt: &mut Option<String>, actions: &mut Vec<Action>) -> bool {
+    if let Some(input) = prompt {
+        match event.code {
+            KeyCode::Char(c) if c.is_ascii_hexdigit() && input.len() < 4 => input.push(c),
+            KeyCode::Backspace => {
+                input.pop();
+            }
+            KeyCode::Enter => {
+                if let Ok(address) = u16::from_str_radix(input, 16) {
+                    actions.push(Action::Break(address));
+                }
+                *prompt = None;
+            }
+            KeyCode::Esc => *prompt = None,
+            _ => (),
+        }
+
+        return true;
+    }
+
+    match event.code {
+        KeyCode::F(5) => actions.push(Action::TogglePause),
+        KeyCode::F(8) => *prompt = Some(String::new()),
+        KeyCode::F(9) => actions.push(Action::BreakAtPc),
+        KeyCode::F(10) => actions.push(Action::StepOver),
+        KeyCode::F(11) => actions.push(Action::Step),
+        KeyCode::F(7) => actions.push(Action::StepBack),
+        RENDER_MODE_KEY => actions.push(Action::CycleRenderMode),
+        SCREENSHOT_KEY => actions.push(Action::Screenshot),
+        VIDEO_KEY => actions.push(Action::ToggleVideo),
+        code if code == SAVE_STATE_KEY || code == LOAD_STATE_KEY => {
+            actions.push(Action::State(code))
+        }
+        _ => return false,
+    }
+
+    true
+}
+
+/// Row of the status line, below both the screen and the panels.
+fn status_row(chip: &Chip, renderer: &Renderer) -> usize {
+    renderer.size(&chip.screen).1.max(PANEL_LINES)
+}
+
+fn draw_panels(
+    chip: &Chip,
+    renderer: &Renderer,
+    debugger: &Debugger,
+    prompt: &Option<String>,
+    message: &str,
+) -> Result<()> {
+    let mut stdout = stdout();
+    let column = (renderer.size(&chip.screen).0 + 2) as u16;
+
+    for (row, line) in panel_lines(chip, debugger).iter().enumerate() {
+        queue!(
+            stdout,
+            crossterm::cursor::MoveTo(column, row as u16),
+            crossterm::style::Print(format!("{:<width$}", line, width = PANEL_WIDTH))
+        )?;
+    }
+
+    let state = if debugger.paused {
+        "PAUSED "
+    } else if chip.waiting_for_key() {
+        "WAITING FOR KEY"
+    } else {
+        "RUNNING"
+    };
+    let status = match prompt {
+        Some(input) => format!("{}  break at: {}_", state, input),
+        None => format!("{}  {}", state, message),
+    };
+
+    let row = status_row(chip, renderer) as u16;
+    let width = renderer.size(&chip.screen).0 + 2 + PANEL_WIDTH;
+    queue!(
+        stdout,
+        crossterm::cursor::MoveTo(0, row),
+        crossterm::style::Print(format!("{:<width$}", status, width = width)),
+        crossterm::cursor::MoveTo(0, row + 1),
+        crossterm::style::Print(HELP)
+    )?;
+
+    stdout.flush()
+}
+
+fn panel_lines(chip: &Chip, debugger: &Debugger) -> Vec<String> {
+    let pc = chip.program_counter.get();
+    let sp = chip.stack_pointer as usize;
+
+    let mut lines = vec![
+        format!("PC {:#06X}  I {:#06X}  SP {:>2}", pc, chip.i, sp),
+        format!("DT {:>3}     ST {:>3}", chip.delay_timer, chip.sound_timer),
+        String::new(),
+    ];
+
+    for row in 0..4u8 {
+        let registers: Vec<_> = (0..4u8)
+            .map(|col| row * 4 + col)
+            .map(|idx| format!("V{:X} {:02X}", idx, chip.v[idx]))
+            .collect();
+        lines.push(registers.join("  "));
+    }
+
+    lines.push(String::new());
+    lines.push("Stack".to_string());
+    let entries: Vec<_> = chip.stack[..sp]
+        .iter()
+        .map(|address| format!("{:#06X}", address))
+        .collect();
+    for row in 0..STACK_ROWS {
+        let chunk = entries.chunks(5).nth(row).unwrap_or_default();
+        lines.push(format!("  {}", chunk.join(" ")));
+    }
+
+    lines.push(String::new());
+    lines.push("Keypad".to_string());
+    for row in KEYPAD_LAYOUT.iter() {
+        let keys: String = row
+            .iter()
+            .map(|key| {
+                if chip.keyboard.is_pressed(*key) {
+                    format!("[{:X}]", key)
+                } else {
+                    format!(" {:X} ", key)
+                }
+            })
+            .collect();
+        lines.push(format!("  {}", keys));
+    }
+
+    lines.push(String::new());
+    let start = pc.saturating_sub(2 * LINES_BEFORE_PC);
+    for line in 0..DISASSEMBLY_LINES {
+        let address = match start.checked_add(2 * line) {
+            Some(address) => address,
+            None => break,
+        };
+        let (high, low) = match chip.memory.get(address as usize..address as usize + 2) {
+            Some(bytes) => (bytes[0], bytes[1]),
+            None => break,
+        };
+
+        let opcode = u16::from(high) << 8 | u16::from(low);
+        let current = if address == pc { '>' } else { ' ' };
+        let breakpoint = if debugger.breakpoints.contains(&address) {
+            '*'
+        } else {
+            ' '
+        };
+
+        lines.push(format!(
+            "{}{} {:#06X}  {:04X}  {}",
+            current,
+            breakpoint,
+            address,
+            opcode,
+            decode(opcode)
+        ));
+    }
+
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chip8rust::mode::Mode;
+
+    #[test]
+    fn lists_up_to_end_of_memory() {
+        let mut chip = Chip::with_mode(Mode::XoChip);
+        chip.program_counter.set(0xFFFE);
+
+        let lines = panel_lines(&chip, &Debugger::new());
+
+        assert!(lines.last().unwrap().starts_with(">  0xFFFE"));
+        assert_eq!(lines.len(), PANEL_LINES - DISASSEMBLY_LINES as usize + 6);
+    }
+}
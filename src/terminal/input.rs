@@ -0,0 +1,541 @@
+use super::{KEYPAD_LAYOUT, RESERVED_KEYS};
+use chip8rust::keyboard::Keyboard;
+use crossterm::event::{
+    poll, read, Event, KeyCode, KeyEvent, KeyEventKind, KeyEventState, KeyModifiers,
+    KeyboardEnhancementFlags, PopKeyboardEnhancementFlags, PushKeyboardEnhancementFlags,
+};
+use crossterm::execute;
+
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::io::{self, stdout};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+
+/// How long a key stays pressed after the terminal last reported it, for terminals
+/// that only report presses and key repeats. Longer than the usual delay of about half a
+/// second before a held key starts repeating.
+pub const DEFAULT_HOLD_TIMEOUT: Duration = Duration::from_millis(600);
+
+/// Once a key repeats, it's released after this many times the gap between its last two
+/// reports, so letting go doesn't take the whole hold timeout.
+const MISSED_REPEATS: u32 = 3;
+
+/// Bindings used without a key map file, the keypad laid over 1234/QWER/ASDF/ZXCV.
+const DEFAULT_BINDINGS: &str = "\
+1 = 1
+2 = 2
+3 = 3
+4 = C
+q = 4
+w = 5
+e = 6
+r = D
+a = 7
+s = 8
+d = 9
+f = E
+z = A
+x = 0
+c = B
+v = F
+";
+
+/// Names of the host keys that aren't a single character.
+const KEY_NAMES: [(&str, KeyCode); 14] = [
+    ("up", KeyCode::Up),
+    ("down", KeyCode::Down),
+    ("left", KeyCode::Left),
+    ("right", KeyCode::Right),
+    ("space", KeyCode::Char(' ')),
+    ("comma", KeyCode::Char(',')),
+    ("enter", KeyCode::Enter),
+    ("tab", KeyCode::Tab),
+    ("backspace", KeyCode::Backspace),
+    ("home", KeyCode::Home),
+    ("end", KeyCode::End),
+    ("pageup", KeyCode::PageUp),
+    ("pagedown", KeyCode::PageDown),
+    ("delete", KeyCode::Delete),
+];
+
+/// A key on the host keyboard, `keypad` tells numpad keys apart from the main block.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct HostKey {
+    pub code: KeyCode,
+    pub keypad: bool,
+}
+
+impl HostKey {
+    fn from_event(event: &KeyEvent) -> Self {
+        let code = match event.code {
+            KeyCode::Char(c) => KeyCode::Char(c.to_ascii_lowercase()),
+            code => code,
+        };
+
+        HostKey {
+            code,
+            keypad: event.state.contains(KeyEventState::KEYPAD),
+        }
+    }
+}
+
+impl FromStr for HostKey {
+    type Err = String;
+
+    /// A character like `q`, a name like `up` or `space`, or a numpad key like `kp5` or `kpenter`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let lower = s.to_ascii_lowercase();
+        let (name, keypad) = match lower.strip_prefix("kp") {
+            Some(name) if !name.is_empty() => (name, true),
+            _ => (lower.as_str(), false),
+        };
+
+        let mut chars = name.chars();
+        let code = match (chars.next(), chars.next()) {
+            (Some(c), None) => Some(KeyCode::Char(c)),
+            _ => KEY_NAMES
+                .iter()
+                .find(|(known, _)| *known == name)
+                .map(|(_, code)| *code)
+                .or_else(|| {
+                    name.strip_prefix('f')
+                        .and_then(|n| n.parse().ok())
+                        .filter(|n| (1..=12).contains(n))
+                        .map(KeyCode::F)
+                }),
+        };
+
+        code.map(|code| HostKey { code, keypad })
+            .ok_or_else(|| format!("unknown host key '{}'", s))
+    }
+}
+
+impl fmt::Display for HostKey {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.keypad {
+            f.write_str("kp")?;
+        }
+
+        match self.code {
+            KeyCode::F(n) => write!(f, "f{}", n),
+            code => match KEY_NAMES.iter().find(|(_, known)| *known == code) {
+                Some((name, _)) => f.write_str(name),
+                None => match code {
+                    KeyCode::Char(c) => write!(f, "{}", c),
+                    other => write!(f, "{:?}", other),
+                },
+            },
+        }
+    }
+}
+
+/// Translates terminal key events into keypad state.
+#[derive(Debug)]
+pub struct KeyMap {
+    pub mapping: HashMap<HostKey, u8>,
+
+    /// Problems worth knowing about that didn't stop the bindings from loading.
+    pub warnings: Vec<String>,
+
+    /// Used instead of release events when the terminal doesn't send them.
+    pub hold_timeout: Duration,
+
+    /// Whether the terminal reports key releases, see `enable_release_events`.
+    pub release_events: bool,
+
+    /// When each keypad key was last reported pressed.
+    last_pressed: [Option<Instant>; 16],
+
+    /// Time between the last two reports of each keypad key, once it repeats.
+    repeat_gap: [Option<Duration>; 16],
+}
+
+impl KeyMap {
+    pub fn new() -> Self {
+        KeyMap::parse(DEFAULT_BINDINGS, None).expect("default bindings are valid")
+    }
+
+    /// Load bindings from a file, see `parse`. `rom` is the file name of the ROM that runs.
+    pub fn load(path: &Path, rom: Option<&str>) -> Result<Self, String> {
+        let source = fs::read_to_string(path)
+            .map_err(|e| format!("could not read '{}': {}", path.display(), e))?;
+
+        KeyMap::parse(&source, rom).map_err(|e| format!("{}:{}", path.display(), e))
+    }
+
+    /// Bindings from a file with one `<host keys> = <keypad key>` line per binding, e.g. `q = 4`
+    /// or `w, up = 5` for several host keys. Empty lines and lines starting with `#` are ignored.
+    ///
+    /// Bindings after a `[<rom file name>]` line only apply to that ROM, on top of the ones
+    /// before the first such line.
+    pub fn parse(source: &str, rom: Option<&str>) -> Result<Self, String> {
+        let mut mapping = HashMap::new();
+        let mut warnings = Vec::new();
+
+        // Line each host key was bound on, per section.
+        let mut bound_on: HashMap<HostKey, usize> = HashMap::new();
+        let mut section: Option<&str> = None;
+
+        for (line_idx, line) in source.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let error = |message: &str| format!("{}: {}", line_idx + 1, message);
+
+            if let Some(name) = line.strip_prefix('[') {
+                let name = name
+                    .strip_suffix(']')
+                    .map(str::trim)
+                    .filter(|name| !name.is_empty())
+                    .ok_or_else(|| error("expected '[<rom file name>]'"))?;
+                section = Some(name);
+                bound_on.clear();
+                continue;
+            }
+
+            let mut parts = line.rsplitn(2, '=').map(str::trim);
+            let chip = parts.next().unwrap_or_default();
+            let hosts = parts
+                .next()
+                .filter(|hosts| !hosts.is_empty())
+                .ok_or_else(|| error("expected '<host keys> = <keypad key>'"))?;
+
+            let chip = u8::from_str_radix(chip, 16)
+                .ok()
+                .filter(|key| *key <= 0xF)
+                .ok_or_else(|| error("keypad key must be a hex digit 0-F"))?;
+
+            for host in hosts.split(',').map(str::trim) {
+                let host: HostKey = host.parse().map_err(|e: String| error(&e))?;
+
+                if let Some(first) = bound_on.insert(host, line_idx + 1) {
+                    warnings.push(format!(
+                        "{}: '{}' is already bound on line {}, the later binding wins",
+                        line_idx + 1,
+                        host,
+                        first
+                    ));
+                }
+
+                if let KeyCode::F(n) = host.code {
+                    if RESERVED_KEYS.contains(&host.code) {
+                        warnings.push(format!(
+                            "{}: 'f{}' is also a hotkey, it won't reach the keypad",
+                            line_idx + 1,
+                            n
+                        ));
+                    }
+                }
+
+                if section.is_none() || section == rom {
+                    mapping.insert(host, chip);
+                }
+            }
+        }
+
+        let unmapped: Vec<_> = (0..16u8)
+            .filter(|key| !mapping.values().any(|bound| bound == key))
+            .map(|key| format!("{:X}", key))
+            .collect();
+        if !unmapped.is_empty() {
+            warnings.push(format!(
+                "keypad keys {} have no host key",
+                unmapped.join(", ")
+            ));
+        }
+
+        Ok(KeyMap {
+            mapping,
+            warnings,
+            hold_timeout: DEFAULT_HOLD_TIMEOUT,
+            release_events: false,
+            last_pressed: [None; 16],
+            repeat_gap: [None; 16],
+        })
+    }
+
+    /// The keypad key `event` is bound to. Numpad keys fall back to the bindings of the
+    /// same key on the main block.
+    fn keypad_key(&self, event: &KeyEvent) -> Option<u8> {
+        let host = HostKey::from_event(event);
+
+        self.mapping
+            .get(&host)
+            .or_else(|| {
+                self.mapping.get(&HostKey {
+                    keypad: false,
+                    ..host
+                })
+            })
+            .copied()
+    }
+
+    /// The bindings of every keypad key in the VIP layout, followed by the warnings.
+    pub fn report(&self) -> String {
+        let mut out = String::new();
+
+        for key in KEYPAD_LAYOUT.iter().flatten() {
+            let mut hosts: Vec<_> = self
+                .mapping
+                .iter()
+                .filter(|(_, bound)| *bound == key)
+                .map(|(host, _)| host.to_string())
+                .collect();
+            hosts.sort();
+
+            if hosts.is_empty() {
+                hosts.push("-".to_string());
+            }
+
+            out.push_str(&format!("{:X}  {}\n", key, hosts.join(", ")));
+        }
+
+        for warning in &self.warnings {
+            out.push_str(&format!("warning: {}\n", warning));
+        }
+
+        out
+    }
+
+    /// Apply pending key events to the keyboard, returns `false` when the user asked to quit.
+    ///
+    /// Every key press is offered to `handle` first and only reaches the keypad if it returns `false`.
+    pub fn register_key_press<F>(
+        &mut self,
+        keyboard: &mut Keyboard,
+        mut handle: F,
+    ) -> io::Result<bool>
+    where
+        F: FnMut(KeyEvent) -> bool,
+    {
+        while poll(Duration::from_millis(0))? {
+            if let Event::Key(event) = read()? {
+                if event.kind == KeyEventKind::Release {
+                    if let Some(key) = self.keypad_key(&event) {
+                        keyboard.set_state(key, false);
+                        self.last_pressed[key as usize] = None;
+                        self.repeat_gap[key as usize] = None;
+                    }
+                    continue;
+                }
+
+                if event.modifiers.contains(KeyModifiers::CONTROL)
+                    && event.code == KeyCode::Char('c')
+                {
+                    return Ok(false);
+                }
+
+                if handle(event) {
+                    continue;
+                }
+
+                if let Some(key) = self.keypad_key(&event) {
+                    self.press(keyboard, key, Instant::now());
+                }
+            }
+        }
+
+        if !self.release_events {
+            self.release_stale(keyboard, Instant::now());
+        }
+
+        Ok(true)
+    }
+
+    fn press(&mut self, keyboard: &mut Keyboard, key: u8, now: Instant) {
+        let slot = key as usize;
+
+        keyboard.set_state(key, true);
+        self.repeat_gap[slot] = self.last_pressed[slot].map(|at| now.duration_since(at));
+        self.last_pressed[slot] = Some(now);
+    }
+
+    /// Release the keys the terminal hasn't reported for `hold_timeout`, or for a few
+    /// repeats once they repeat.
+    fn release_stale(&mut self, keyboard: &mut Keyboard, now: Instant) {
+        for key in 0..16 {
+            if let Some(at) = self.last_pressed[key] {
+                let timeout = self.repeat_gap[key].map_or(self.hold_timeout, |gap| {
+                    (gap * MISSED_REPEATS).min(self.hold_timeout)
+                });
+
+                if now.duration_since(at) >= timeout {
+                    keyboard.set_state(key as u8, false);
+                    self.last_pressed[key] = None;
+                    self.repeat_gap[key] = None;
+                }
+            }
+        }
+    }
+}
+
+/// Ask the terminal to report key releases and numpad keys, returns `false` if it can't.
+pub fn enable_release_events() -> io::Result<bool> {
+    if !crossterm::terminal::supports_keyboard_enhancement()? {
+        return Ok(false);
+    }
+
+    execute!(
+        stdout(),
+        PushKeyboardEnhancementFlags(
+            KeyboardEnhancementFlags::REPORT_EVENT_TYPES
+                | KeyboardEnhancementFlags::DISAMBIGUATE_ESCAPE_CODES
+        )
+    )?;
+
+    Ok(true)
+}
+
+pub fn disable_release_events() -> io::Result<()> {
+    execute!(stdout(), PopKeyboardEnhancementFlags)
+}
+
+/// The key map file used without `--keymap`, `$XDG_CONFIG_HOME/chip8rust/keys.conf`
+/// or `~/.config/chip8rust/keys.conf`, if it exists.
+pub fn default_path() -> Option<PathBuf> {
+    let config = std::env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))?;
+
+    Some(config.join("chip8rust").join("keys.conf")).filter(|path| path.is_file())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn host(code: KeyCode) -> HostKey {
+        HostKey {
+            code,
+            keypad: false,
+        }
+    }
+
+    #[test]
+    fn parses_key_map() {
+        let key_map = KeyMap::parse(
+            "# pong paddles\nq, up = 1\n\n  a=C \nkp5 = 5\n= = 0\n",
+            None,
+        )
+        .unwrap();
+
+        assert_eq!(key_map.mapping.len(), 5);
+        assert_eq!(key_map.mapping[&host(KeyCode::Char('q'))], 0x1);
+        assert_eq!(key_map.mapping[&host(KeyCode::Up)], 0x1);
+        assert_eq!(key_map.mapping[&host(KeyCode::Char('a'))], 0xC);
+        assert_eq!(key_map.mapping[&host(KeyCode::Char('='))], 0x0);
+        assert_eq!(
+            key_map.mapping[&HostKey {
+                code: KeyCode::Char('5'),
+                keypad: true
+            }],
+            0x5
+        );
+    }
+
+    #[test]
+    fn applies_rom_overrides() {
+        let source = "q = 4\nw = 5\n[pong.ch8]\nq = 1\nup = 1\n[race.ch8]\nw = 9\n";
+
+        let pong = KeyMap::parse(source, Some("pong.ch8")).unwrap();
+        assert_eq!(pong.mapping[&host(KeyCode::Char('q'))], 0x1);
+        assert_eq!(pong.mapping[&host(KeyCode::Char('w'))], 0x5);
+        assert_eq!(pong.mapping[&host(KeyCode::Up)], 0x1);
+
+        let other = KeyMap::parse(source, Some("tetris.ch8")).unwrap();
+        assert_eq!(other.mapping.len(), 2);
+        assert_eq!(other.mapping[&host(KeyCode::Char('q'))], 0x4);
+    }
+
+    #[test]
+    fn reports_duplicates_and_unmapped_keys() {
+        let key_map =
+            KeyMap::parse("q = 4\nw = 5\nq = 6\nf5 = 7\n[pong.ch8]\nq = 1\n", None).unwrap();
+
+        assert_eq!(
+            key_map.warnings,
+            [
+                "3: 'q' is already bound on line 1, the later binding wins",
+                "4: 'f5' is also a hotkey, it won't reach the keypad",
+                "keypad keys 0, 1, 2, 3, 4, 8, 9, A, B, C, D, E, F have no host key",
+            ]
+        );
+        assert!(KeyMap::new().warnings.is_empty());
+        assert!(KeyMap::new()
+            .report()
+            .starts_with("1  1\n2  2\n3  3\nC  4\n4  q\n"));
+    }
+
+    #[test]
+    fn names_host_keys() {
+        for name in ["q", "up", "space", "kp5", "kpenter", "f12", "comma"].iter() {
+            assert_eq!(name.parse::<HostKey>().unwrap().to_string(), *name);
+        }
+
+        assert!("f13".parse::<HostKey>().is_err());
+        assert!("hyper".parse::<HostKey>().is_err());
+    }
+
+    #[test]
+    fn releases_keys_after_hold_timeout() {
+        let mut key_map = KeyMap::new();
+        let mut keyboard = Keyboard::new();
+        let start = Instant::now();
+
+        keyboard.set_state(0x4, true);
+        key_map.last_pressed[0x4] = Some(start);
+        keyboard.set_state(0x5, true);
+        key_map.last_pressed[0x5] = Some(start + Duration::from_millis(200));
+
+        key_map.release_stale(&mut keyboard, start + DEFAULT_HOLD_TIMEOUT);
+
+        assert!(!keyboard.is_pressed(0x4));
+        assert!(keyboard.is_pressed(0x5));
+        assert_eq!(key_map.last_pressed[0x4], None);
+    }
+
+    #[test]
+    fn holds_keys_until_they_repeat() {
+        let mut key_map = KeyMap::new();
+        let mut keyboard = Keyboard::new();
+        let start = Instant::now();
+        let at = |ms| start + Duration::from_millis(ms);
+
+        // Most systems wait about 500 ms before repeating a held key.
+        key_map.press(&mut keyboard, 0x4, start);
+        key_map.release_stale(&mut keyboard, at(500));
+        assert!(keyboard.is_pressed(0x4));
+
+        key_map.press(&mut keyboard, 0x4, at(500));
+        key_map.press(&mut keyboard, 0x4, at(530));
+        key_map.release_stale(&mut keyboard, at(600));
+        assert!(keyboard.is_pressed(0x4));
+        key_map.release_stale(&mut keyboard, at(620));
+        assert!(!keyboard.is_pressed(0x4));
+    }
+
+    #[test]
+    fn reports_line_of_bad_binding() {
+        assert_eq!(
+            KeyMap::parse("q = 1\nw = 10", None).unwrap_err(),
+            "2: keypad key must be a hex digit 0-F"
+        );
+        assert_eq!(
+            KeyMap::parse("qq = 1", None).unwrap_err(),
+            "1: unknown host key 'qq'"
+        );
+        assert_eq!(
+            KeyMap::parse("q", None).unwrap_err(),
+            "1: expected '<host keys> = <keypad key>'"
+        );
+        assert_eq!(
+            KeyMap::parse("[pong.ch8", None).unwrap_err(),
+            "1: expected '[<rom file name>]'"
+        );
+    }
+}
@@ -0,0 +1,278 @@
+pub mod debugger;
+pub mod input;
+pub mod screen;
+
+use crate::{image_file, movie_file, state_file};
+use chip8rust::audio::Sink;
+use chip8rust::movie::Movie;
+use chip8rust::rewind::Rewind;
+use chip8rust::scheduler::Scheduler;
+use chip8rust::Chip;
+use crossterm::event::KeyCode;
+use input::KeyMap;
+use screen::Renderer;
+use std::error::Error;
+use std::path::{Path, PathBuf};
+
+/// Hotkeys shared by the plain frontend and the debugger.
+pub const SAVE_STATE_KEY: KeyCode = KeyCode::F(2);
+pub const LOAD_STATE_KEY: KeyCode = KeyCode::F(4);
+
+/// Switches to the next `screen::RenderMode`.
+pub const RENDER_MODE_KEY: KeyCode = KeyCode::F(3);
+
+/// Saves the screen as a PNG next to the ROM.
+pub const SCREENSHOT_KEY: KeyCode = KeyCode::F(12);
+
+/// Starts or stops recording the screen to a GIF next to the ROM.
+pub const VIDEO_KEY: KeyCode = KeyCode::F(1);
+
+/// Goes back `REWIND_FRAMES` frames per press.
+const REWIND_KEY: KeyCode = KeyCode::F(6);
+const REWIND_FRAMES: usize = 60;
+
+/// Frames kept for rewinding, 30 seconds.
+const REWIND_CAPACITY: usize = 30 * 60;
+
+/// Keys the frontend or the debugger take for themselves.
+pub const RESERVED_KEYS: [KeyCode; 12] = [
+    VIDEO_KEY,
+    SAVE_STATE_KEY,
+    RENDER_MODE_KEY,
+    LOAD_STATE_KEY,
+    KeyCode::F(5),
+    REWIND_KEY,
+    KeyCode::F(7),
+    KeyCode::F(8),
+    KeyCode::F(9),
+    KeyCode::F(10),
+    KeyCode::F(11),
+    SCREENSHOT_KEY,
+];
+
+/// Keypad keys as they are laid out on the COSMAC VIP.
+pub const KEYPAD_LAYOUT: [[u8; 4]; 4] = [
+    [0x1, 0x2, 0x3, 0xC],
+    [0x4, 0x5, 0x6, 0xD],
+    [0x7, 0x8, 0x9, 0xE],
+    [0xA, 0x0, 0xB, 0xF],
+];
+
+/// How the machine is shown and heard, and where the hotkeys save to.
+pub struct Frontend {
+    pub renderer: Renderer,
+    pub audio: Box<dyn Sink>,
+
+    /// Where the save state hotkeys write to and read from.
+    pub state_path: PathBuf,
+
+    /// Screenshots are numbered after this path, see `image_file::next_path`.
+    pub rom: PathBuf,
+    pub image_style: image_file::Style,
+
+    /// Screen recording in progress, if any.
+    pub video: Option<image_file::Video>,
+}
+
+impl Frontend {
+    /// Save the screen to the next free screenshot file, returns what happened for the
+    /// user to see.
+    pub fn screenshot(&self, chip: &Chip) -> String {
+        let path = image_file::next_path(&self.rom, "png");
+
+        match image_file::save(&chip.screen, &path, &self.image_style) {
+            Ok(()) => format!("saved screenshot to '{}'", path.display()),
+            Err(e) => e,
+        }
+    }
+
+    /// Start a screen recording next to the ROM, or stop the one in progress.
+    pub fn toggle_video(&mut self) -> String {
+        let result = match self.video.take() {
+            Some(video) => video
+                .finish()
+                .map(|path| format!("saved video to '{}'", path.display())),
+            None => {
+                let path = image_file::next_path(&self.rom, "gif");
+                image_file::Video::start(&path, &self.image_style).map(|video| {
+                    self.video = Some(video);
+                    format!("recording video to '{}'", path.display())
+                })
+            }
+        };
+
+        result.unwrap_or_else(|e| e)
+    }
+
+    /// Play and record the frame `chip` just finished.
+    pub fn frame(&mut self, chip: &Chip) -> Result<(), Box<dyn Error>> {
+        self.audio.play(chip)?;
+        if let Some(video) = &mut self.video {
+            video.frame(&chip.screen)?;
+        }
+        Ok(())
+    }
+
+    /// Write out whatever audio and video are still buffered.
+    pub fn finish(&mut self) -> Result<(), String> {
+        self.audio
+            .finish()
+            .map_err(|e| format!("could not write audio: {}", e))?;
+        if let Some(video) = self.video.take() {
+            video.finish()?;
+        }
+        Ok(())
+    }
+}
+
+/// Where the keypad state of a run comes from or goes to, besides the keyboard.
+pub enum MovieMode {
+    Off,
+
+    /// Every frame is added to `movie`, which is written to `path` when the run ends.
+    Recording {
+        movie: Movie,
+        path: PathBuf,
+    },
+
+    /// The keypad follows the movie and ignores the keyboard until the movie is over.
+    Playing(Movie),
+}
+
+/// Run the chip until the user quits, drawing to and reading keys from the terminal.
+///
+/// The terminal is restored before an error is returned, so it can be printed.
+pub fn run(
+    chip: Chip,
+    mut key_map: KeyMap,
+    scheduler: Scheduler,
+    mut frontend: Frontend,
+    mut movie: MovieMode,
+) -> Result<(), String> {
+    screen::init().map_err(|e| e.to_string())?;
+    key_map.release_events = input::enable_release_events().map_err(|e| e.to_string())?;
+    let release_events = key_map.release_events;
+
+    let result = run_loop(chip, key_map, scheduler, &mut frontend, &mut movie);
+
+    if release_events {
+        input::disable_release_events().map_err(|e| e.to_string())?;
+    }
+    screen::restore().map_err(|e| e.to_string())?;
+
+    // Keep what was recorded even if the program crashed, that's what bug reports need.
+    if let MovieMode::Recording { movie, path } = &movie {
+        movie_file::save(movie, path)?;
+    }
+    frontend.finish()?;
+
+    result.map_err(|e| e.to_string())
+}
+
+fn run_loop(
+    mut chip: Chip,
+    mut key_map: KeyMap,
+    mut scheduler: Scheduler,
+    frontend: &mut Frontend,
+    movie: &mut MovieMode,
+) -> Result<(), Box<dyn Error>> {
+    let mut rewind = Rewind::new(REWIND_CAPACITY);
+    let mut frame = 0;
+
+    while !chip.exited {
+        chip.screen.should_redraw = false;
+
+        let playing = matches!(movie, MovieMode::Playing(_));
+        let mut hotkey = None;
+        let keep_running = key_map.register_key_press(&mut chip.keyboard, |event| {
+            let is_hotkey = [
+                SAVE_STATE_KEY,
+                LOAD_STATE_KEY,
+                REWIND_KEY,
+                RENDER_MODE_KEY,
+                SCREENSHOT_KEY,
+                VIDEO_KEY,
+            ]
+            .contains(&event.code);
+            if is_hotkey {
+                hotkey = Some(event.code);
+            }
+            is_hotkey || playing
+        })?;
+
+        if !keep_running {
+            break;
+        }
+
+        match (hotkey, &mut *movie) {
+            (Some(RENDER_MODE_KEY), _) => {
+                frontend.renderer.cycle_mode();
+                screen::set_title(&format!("{} rendering", frontend.renderer.mode))?;
+            }
+            (Some(SCREENSHOT_KEY), _) => screen::set_title(&frontend.screenshot(&chip))?,
+            (Some(VIDEO_KEY), _) => screen::set_title(&frontend.toggle_video())?,
+            (Some(REWIND_KEY), MovieMode::Playing(_)) => {
+                screen::set_title("rewinding is off while a movie plays")?;
+            }
+            (Some(REWIND_KEY), _) => {
+                for _ in 0..REWIND_FRAMES {
+                    if rewind.rewind(&mut chip) {
+                        frame -= 1;
+                    }
+                }
+
+                // Re-recording: the rewound frames are played again, not kept.
+                if let MovieMode::Recording { movie, .. } = movie {
+                    movie.frames.truncate(frame);
+                }
+            }
+            (Some(_), MovieMode::Recording { .. }) | (Some(_), MovieMode::Playing(_)) => {
+                screen::set_title("save states are off while a movie records or plays")?;
+            }
+            (Some(key), MovieMode::Off) => {
+                let message = state_hotkey(key, &mut chip, &frontend.state_path);
+                screen::set_title(&message)?;
+            }
+            (None, _) => (),
+        }
+
+        for _ in 0..scheduler.wait_for_frame() {
+            match movie {
+                MovieMode::Recording { movie, .. } => movie.record(&chip.keyboard),
+                MovieMode::Playing(played) => {
+                    if !played.play(frame, &mut chip.keyboard) {
+                        *movie = MovieMode::Off;
+                        chip.keyboard.release_all();
+                        screen::set_title("movie finished, the keyboard has the keypad")?;
+                    }
+                }
+                MovieMode::Off => (),
+            }
+
+            rewind.record(&chip);
+            scheduler.run_frame(&mut chip)?;
+            frontend.frame(&chip)?;
+            frame += 1;
+        }
+
+        frontend
+            .renderer
+            .update(&chip.screen, chip.screen.should_redraw)?;
+    }
+
+    Ok(())
+}
+
+/// Save or load a state for `key`, returns what happened for the user to see.
+pub fn state_hotkey(key: KeyCode, chip: &mut Chip, state_path: &Path) -> String {
+    let result = if key == SAVE_STATE_KEY {
+        state_file::save(chip, state_path).map(|_| "saved state to")
+    } else {
+        state_file::load(chip, state_path).map(|_| "loaded state from")
+    };
+
+    match result {
+        Ok(done) => format!("{} '{}'", done, state_path.display()),
+        Err(e) => e,
+    }
+}
@@ -0,0 +1,502 @@
+use chip8rust::display::Display;
+use crossterm::style::Color;
+use crossterm::{execute, queue};
+use std::fmt;
+use std::io::{stdout, Result, Write};
+use std::str::FromStr;
+
+/// Colors of XO-CHIP pixel values 1 to 3, plain CHIP-8 only ever uses the first.
+const PALETTE: [Color; 3] = [Color::Reset, Color::DarkYellow, Color::DarkRed];
+
+/// Quadrant characters indexed by the lit quarters: top left 1, top right 2,
+/// bottom left 4, bottom right 8.
+const QUADRANTS: [char; 16] = [
+    ' ', '▘', '▝', '▀', '▖', '▌', '▞', '▛', '▗', '▚', '▐', '▜', '▄', '▙', '▟', '█',
+];
+
+/// Bit of each dot of a braille character, by row and column.
+const BRAILLE_DOTS: [[u32; 2]; 4] = [[0x01, 0x08], [0x02, 0x10], [0x04, 0x20], [0x40, 0x80]];
+
+/// How screen pixels are packed into terminal cells.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum RenderMode {
+    /// One `█` per pixel, looks squashed since cells are twice as high as wide.
+    Block,
+    /// Two pixels stacked in a cell using `▀` and `▄`, pixels come out square.
+    #[default]
+    HalfBlock,
+    /// 2x2 pixels per cell using quadrant characters like `▚`.
+    Quadrant,
+    /// 2x4 pixels per cell using braille dots, fits the hi-res screen in small terminals.
+    Braille,
+}
+
+impl RenderMode {
+    pub const ALL: [RenderMode; 4] = [
+        RenderMode::Block,
+        RenderMode::HalfBlock,
+        RenderMode::Quadrant,
+        RenderMode::Braille,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            RenderMode::Block => "block",
+            RenderMode::HalfBlock => "halfblock",
+            RenderMode::Quadrant => "quadrant",
+            RenderMode::Braille => "braille",
+        }
+    }
+
+    /// Pixels per cell, horizontally and vertically.
+    pub fn cell_size(self) -> (usize, usize) {
+        match self {
+            RenderMode::Block => (1, 1),
+            RenderMode::HalfBlock => (1, 2),
+            RenderMode::Quadrant => (2, 2),
+            RenderMode::Braille => (2, 4),
+        }
+    }
+
+    /// The mode after this one, wrapping around.
+    pub fn next(self) -> Self {
+        let idx = RenderMode::ALL.iter().position(|mode| *mode == self);
+        RenderMode::ALL[(idx.unwrap_or_default() + 1) % RenderMode::ALL.len()]
+    }
+}
+
+impl fmt::Display for RenderMode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for RenderMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        RenderMode::ALL
+            .iter()
+            .copied()
+            .find(|mode| mode.name() == s)
+            .ok_or_else(|| {
+                format!(
+                    "unknown render mode '{}', expected block, halfblock, quadrant or braille",
+                    s
+                )
+            })
+    }
+}
+
+/// One terminal cell, colors are pixel values 1 to 3 or `None` for the terminal's own.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Cell {
+    pub glyph: char,
+    pub foreground: Option<u8>,
+    pub background: Option<u8>,
+}
+
+const BLANK: Cell = Cell {
+    glyph: ' ',
+    foreground: None,
+    background: None,
+};
+
+/// Terminal size, screen resolution and mode a layout is made for.
+type Layout = ((u16, u16), (usize, usize), RenderMode);
+
+/// Draws the screen into the top left corner of the terminal, scaled to fit.
+#[derive(Debug)]
+pub struct Renderer {
+    pub mode: RenderMode,
+
+    /// Columns and rows kept free right of and below the screen, e.g. for the debugger.
+    reserved: (u16, u16),
+
+    laid_out_for: Option<Layout>,
+
+    /// Times each pixel is repeated horizontally and vertically.
+    scale: usize,
+
+    /// Cells as they are on the terminal right now, row by row, cut to the terminal's size.
+    presented: Vec<Vec<Cell>>,
+}
+
+impl Renderer {
+    pub fn new(mode: RenderMode) -> Self {
+        Renderer {
+            mode,
+            reserved: (0, 0),
+            laid_out_for: None,
+            scale: 1,
+            presented: Vec::new(),
+        }
+    }
+
+    /// Keep `columns` and `rows` of the terminal free for other output.
+    pub fn reserve(&mut self, columns: u16, rows: u16) {
+        self.reserved = (columns, rows);
+        self.laid_out_for = None;
+    }
+
+    /// Switch to the next `RenderMode`.
+    pub fn cycle_mode(&mut self) {
+        self.mode = self.mode.next();
+    }
+
+    /// Cells the screen takes up, horizontally and vertically.
+    pub fn size(&self, display: &Display) -> (usize, usize) {
+        let (cell_width, cell_height) = self.mode.cell_size();
+
+        (
+            (display.width() * self.scale).div_ceil(cell_width),
+            (display.height() * self.scale).div_ceil(cell_height),
+        )
+    }
+
+    /// Present `display` if it `changed`, or everything after the terminal was resized,
+    /// the resolution or the mode changed. Returns `true` if the layout changed, so
+    /// whatever is drawn around the screen needs drawing again.
+    ///
+    /// Only cells that differ from what's on the terminal are written, all in one go,
+    /// so this is meant to be called once per frame rather than after every sprite.
+    pub fn update(&mut self, display: &Display, changed: bool) -> Result<bool> {
+        let terminal = crossterm::terminal::size()?;
+        let key = (terminal, (display.width(), display.height()), self.mode);
+        let mut out = Vec::new();
+
+        let relayout = self.laid_out_for != Some(key);
+        if relayout {
+            self.laid_out_for = Some(key);
+            self.scale = fit(
+                (display.width(), display.height()),
+                self.mode.cell_size(),
+                (
+                    terminal.0.saturating_sub(self.reserved.0) as usize,
+                    terminal.1.saturating_sub(self.reserved.1) as usize,
+                ),
+            );
+
+            // Anything past the terminal's edges is cut off rather than wrapped.
+            let (columns, rows) = self.size(display);
+            self.presented =
+                vec![vec![BLANK; columns.min(terminal.0 as usize)]; rows.min(terminal.1 as usize)];
+
+            queue!(
+                out,
+                crossterm::terminal::Clear(crossterm::terminal::ClearType::All)
+            )?;
+        }
+
+        if relayout || changed {
+            self.draw(display, &mut out)?;
+        }
+
+        if !out.is_empty() {
+            let mut stdout = stdout();
+            stdout.write_all(&out)?;
+            stdout.flush()?;
+        }
+
+        Ok(relayout)
+    }
+
+    /// Write the cells of `display` that differ from `presented` to `out`.
+    fn draw(&mut self, display: &Display, out: &mut impl Write) -> Result<()> {
+        // Where the terminal's cursor and colors are, `None` until something was written.
+        let mut cursor = None;
+        let mut colors = None;
+
+        for (y, row) in self.presented.iter_mut().enumerate() {
+            for (x, presented) in row.iter_mut().enumerate() {
+                let cell = cell(display, self.mode, self.scale, x, y);
+                if *presented == cell {
+                    continue;
+                }
+                *presented = cell;
+
+                match cursor {
+                    Some(at) if at == (x, y) => (),
+                    Some((_, row)) if row == y => {
+                        queue!(out, crossterm::cursor::MoveToColumn(x as u16))?
+                    }
+                    _ => queue!(out, crossterm::cursor::MoveTo(x as u16, y as u16))?,
+                }
+
+                let (foreground, background) = (color(cell.foreground), color(cell.background));
+                if colors != Some((foreground, background)) {
+                    colors = Some((foreground, background));
+                    queue!(
+                        out,
+                        crossterm::style::SetForegroundColor(foreground),
+                        crossterm::style::SetBackgroundColor(background)
+                    )?;
+                }
+
+                queue!(out, crossterm::style::Print(cell.glyph))?;
+                cursor = Some((x + 1, y));
+            }
+        }
+
+        if colors.is_some() {
+            queue!(out, crossterm::style::ResetColor)?;
+        }
+
+        Ok(())
+    }
+}
+
+fn color(pixel: Option<u8>) -> Color {
+    pixel.map_or(Color::Reset, |pixel| PALETTE[pixel as usize - 1])
+}
+
+/// Largest integer scale that fits a `resolution` screen into `space` cells, at least 1.
+fn fit(resolution: (usize, usize), cell_size: (usize, usize), space: (usize, usize)) -> usize {
+    let horizontal = space.0 * cell_size.0 / resolution.0;
+    let vertical = space.1 * cell_size.1 / resolution.1;
+
+    horizontal.min(vertical).max(1)
+}
+
+/// The cell at column `x` and row `y` of `display` drawn in `mode` at `scale`.
+pub fn cell(display: &Display, mode: RenderMode, scale: usize, x: usize, y: usize) -> Cell {
+    let (cell_width, cell_height) = mode.cell_size();
+
+    let pixel = |dx: usize, dy: usize| {
+        let px = (x * cell_width + dx) / scale;
+        let py = (y * cell_height + dy) / scale;
+        if px < display.width() && py < display.height() {
+            display.pixel(px, py)
+        } else {
+            0
+        }
+    };
+
+    match mode {
+        RenderMode::Block => match pixel(0, 0) {
+            0 => BLANK,
+            value => Cell {
+                glyph: '█',
+                foreground: Some(value),
+                background: None,
+            },
+        },
+        RenderMode::HalfBlock => match (pixel(0, 0), pixel(0, 1)) {
+            (0, 0) => BLANK,
+            (top, 0) => Cell {
+                glyph: '▀',
+                foreground: Some(top),
+                background: None,
+            },
+            (0, bottom) => Cell {
+                glyph: '▄',
+                foreground: Some(bottom),
+                background: None,
+            },
+            (top, bottom) if top == bottom => Cell {
+                glyph: '█',
+                foreground: Some(top),
+                background: None,
+            },
+            // Value 1 is the terminal's own foreground, which can't be a background.
+            (top, 1) => Cell {
+                glyph: '▄',
+                foreground: Some(1),
+                background: Some(top),
+            },
+            (top, bottom) => Cell {
+                glyph: '▀',
+                foreground: Some(top),
+                background: Some(bottom),
+            },
+        },
+        RenderMode::Quadrant | RenderMode::Braille => {
+            // One color per cell, the first lit pixel picks it.
+            let mut bits = 0;
+            let mut foreground = None;
+
+            // Quadrant cells are the top left of a braille cell.
+            for (dy, dots) in BRAILLE_DOTS.iter().enumerate().take(cell_height) {
+                for (dx, dot) in dots.iter().enumerate().take(cell_width) {
+                    let value = pixel(dx, dy);
+                    if value == 0 {
+                        continue;
+                    }
+
+                    foreground = foreground.or(Some(value));
+                    bits |= match mode {
+                        RenderMode::Quadrant => 1 << (dy * 2 + dx),
+                        _ => *dot,
+                    };
+                }
+            }
+
+            let glyph = match mode {
+                RenderMode::Quadrant => QUADRANTS[bits as usize],
+                _ if bits == 0 => ' ',
+                _ => std::char::from_u32(0x2800 + bits).unwrap_or(' '),
+            };
+
+            Cell {
+                glyph,
+                foreground,
+                background: None,
+            }
+        }
+    }
+}
+
+/// Show a short message in the terminal's title bar, out of the way of the screen.
+pub fn set_title(title: &str) -> Result<()> {
+    execute!(stdout(), crossterm::terminal::SetTitle(title))
+}
+
+pub fn init() -> Result<()> {
+    let mut stdout = stdout();
+
+    crossterm::terminal::enable_raw_mode()?;
+
+    execute!(
+        stdout,
+        crossterm::cursor::Hide,
+        crossterm::terminal::Clear(crossterm::terminal::ClearType::All),
+    )
+}
+
+pub fn restore() -> Result<()> {
+    let mut stdout = stdout();
+
+    execute!(
+        stdout,
+        crossterm::terminal::Clear(crossterm::terminal::ClearType::All),
+        crossterm::cursor::MoveTo(0, 0),
+        crossterm::cursor::Show
+    )?;
+
+    crossterm::terminal::disable_raw_mode()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Display with the top left 2x4 pixels set to `pattern`, row by row.
+    fn display(pattern: [[u8; 2]; 4]) -> Display {
+        let mut display = Display::new();
+        for (y, row) in pattern.iter().enumerate() {
+            for (x, lit) in row.iter().enumerate() {
+                if *lit != 0 {
+                    display.contents[y] |= 1 << (display.width() - 1 - x);
+                }
+            }
+        }
+        display
+    }
+
+    fn glyphs(display: &Display, mode: RenderMode, scale: usize) -> String {
+        (0..2)
+            .map(|x| cell(display, mode, scale, x, 0).glyph)
+            .collect()
+    }
+
+    #[test]
+    fn packs_pixels_into_cells() {
+        let display = display([[1, 0], [1, 1], [0, 1], [0, 0]]);
+
+        assert_eq!(glyphs(&display, RenderMode::Block, 1), "█ ");
+        assert_eq!(glyphs(&display, RenderMode::HalfBlock, 1), "█▄");
+        assert_eq!(glyphs(&display, RenderMode::Quadrant, 1), "▙ ");
+        assert_eq!(glyphs(&display, RenderMode::Braille, 1), "⠳ ");
+    }
+
+    #[test]
+    fn scales_pixels() {
+        let display = display([[1, 0], [0, 0], [0, 0], [0, 0]]);
+
+        assert_eq!(glyphs(&display, RenderMode::HalfBlock, 2), "██");
+        assert_eq!(glyphs(&display, RenderMode::Quadrant, 2), "█ ");
+        assert_eq!(glyphs(&display, RenderMode::Braille, 2), "⠛ ");
+    }
+
+    #[test]
+    fn colors_stacked_pixels() {
+        let mut display = display([[1, 0], [0, 0], [0, 0], [0, 0]]);
+        display.second_plane[1] = 1 << (display.width() - 1);
+
+        assert_eq!(
+            cell(&display, RenderMode::HalfBlock, 1, 0, 0),
+            Cell {
+                glyph: '▀',
+                foreground: Some(1),
+                background: Some(2),
+            }
+        );
+    }
+
+    /// Renderer laid out for a lo-res screen in a terminal of `columns` by `rows`.
+    fn renderer(columns: usize, rows: usize) -> Renderer {
+        let mut renderer = Renderer::new(RenderMode::HalfBlock);
+        renderer.presented = vec![vec![BLANK; columns]; rows];
+        renderer
+    }
+
+    fn draw(renderer: &mut Renderer, display: &Display) -> String {
+        let mut out = Vec::new();
+        renderer.draw(display, &mut out).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn draws_only_changed_cells() {
+        let mut renderer = renderer(64, 16);
+        let mut display = display([[1, 0], [0, 0], [0, 0], [0, 0]]);
+        display.contents[31] = 1;
+
+        assert_eq!(
+            draw(&mut renderer, &display),
+            "\x1b[1;1H\x1b[39m\x1b[49m▀\x1b[16;64H▄\x1b[0m"
+        );
+        assert_eq!(draw(&mut renderer, &display), "");
+
+        display.contents[31] = 0;
+        assert_eq!(
+            draw(&mut renderer, &display),
+            "\x1b[16;64H\x1b[39m\x1b[49m \x1b[0m"
+        );
+    }
+
+    #[test]
+    fn moves_cursor_only_when_needed() {
+        let mut renderer = renderer(64, 16);
+        let display = display([[1, 1], [0, 0], [0, 0], [0, 0]]);
+        draw(&mut renderer, &display);
+
+        let mut wider = Display::new();
+        wider.contents[0] = 0b1011 << 60;
+
+        assert_eq!(
+            draw(&mut renderer, &wider),
+            "\x1b[1;2H\x1b[39m\x1b[49m ▀▀\x1b[0m"
+        );
+        assert_eq!(
+            draw(&mut renderer, &Display::new()),
+            "\x1b[1;1H\x1b[39m\x1b[49m \x1b[3G  \x1b[0m"
+        );
+    }
+
+    #[test]
+    fn fits_screen_into_terminal() {
+        assert_eq!(fit((64, 32), (1, 2), (80, 24)), 1);
+        assert_eq!(fit((64, 32), (1, 2), (200, 60)), 3);
+        assert_eq!(fit((128, 64), (2, 4), (80, 24)), 1);
+        assert_eq!(fit((128, 64), (1, 1), (80, 24)), 1);
+    }
+
+    #[test]
+    fn cycles_modes() {
+        assert_eq!(RenderMode::Block.next(), RenderMode::HalfBlock);
+        assert_eq!(RenderMode::Braille.next(), RenderMode::Block);
+        assert_eq!("braille".parse(), Ok(RenderMode::Braille));
+        assert!("ascii".parse::<RenderMode>().is_err());
+    }
+}
@@ -0,0 +1,235 @@
+//! Golden-frame harness shared by the integration tests: ROMs run headlessly through the
+//! `chip8rust` binary with scripted input, and the screen they end on is compared to a
+//! file checked in under `tests/golden`.
+//!
+//! Run the tests with `CHIP8RUST_BLESS=1` to write the golden files from what the ROMs
+//! draw now, then review the changes with `git diff`.
+
+// Each test crate compiles this module on its own and uses only part of it.
+#![allow(dead_code)]
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// Set to anything to write golden files instead of comparing with them.
+const BLESS_VAR: &str = "CHIP8RUST_BLESS";
+
+/// A headless run of a ROM from the repository's root directory.
+pub struct Run {
+    pub rom: &'static str,
+
+    /// `--mode` name.
+    pub mode: &'static str,
+
+    /// `--quirks` profile name.
+    pub quirks: &'static str,
+    pub instructions_per_frame: u32,
+
+    /// Stop after this many frames.
+    pub frames: u64,
+
+    /// Keypad input in the `--input` format.
+    pub input: &'static str,
+}
+
+impl Run {
+    pub fn new(rom: &'static str, frames: u64) -> Self {
+        Run {
+            rom,
+            mode: "chip8",
+            quirks: "modern",
+            instructions_per_frame: 10,
+            frames,
+            input: "",
+        }
+    }
+
+    /// Run the ROM, seeded so `CXNN` gives the same numbers every time, and return the
+    /// screen in the format of text golden files. A screenshot goes to `pbm` if given.
+    fn screen(&self, name: &str, pbm: Option<&Path>) -> String {
+        let input = temp_path(name).with_extension("script");
+        fs::write(&input, self.input).unwrap();
+
+        let mut command = Command::new(env!("CARGO_BIN_EXE_chip8rust"));
+        command.current_dir(root()).args([
+            "--json",
+            "--seed",
+            "0",
+            "--mode",
+            self.mode,
+            "--quirks",
+            self.quirks,
+            "--ipf",
+            &self.instructions_per_frame.to_string(),
+            "--frames",
+            &self.frames.to_string(),
+        ]);
+        command.arg("--input").arg(&input);
+        if let Some(pbm) = pbm {
+            command.args(["--scale", "1", "--screenshot"]).arg(pbm);
+        }
+        let output = command.arg(self.rom).output().unwrap();
+        assert!(
+            output.status.success(),
+            "{} failed: {}",
+            self.rom,
+            String::from_utf8_lossy(&output.stderr)
+        );
+
+        text_from_json(&String::from_utf8(output.stdout).unwrap())
+    }
+
+    /// The screen the run ends on in the format of text golden files, for checking it
+    /// some other way. `name` keeps the run's files apart from other runs'.
+    pub fn text(&self, name: &str) -> String {
+        self.screen(name, None)
+    }
+}
+
+fn root() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+}
+
+/// Where a run for the golden file `name` keeps its files.
+fn temp_path(name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("chip8rust-golden-{}", name.replace('/', "-")))
+}
+
+/// Run `run` and compare its screen with `tests/golden/<name>`, a `.txt` or a `.pbm`
+/// file, panicking with the rows that differ if it doesn't match. Returns the screen as
+/// text, for comparing runs with each other.
+pub fn assert_golden(run: &Run, name: &str) -> String {
+    let path = root().join("tests").join("golden").join(name);
+    let pbm = path.extension().is_some_and(|ext| ext == "pbm");
+
+    let (text, actual) = if pbm {
+        let image = temp_path(name);
+        let text = run.screen(name, Some(&image));
+        (text, fs::read(&image).unwrap())
+    } else {
+        let text = run.screen(name, None);
+        (text.clone(), text.into_bytes())
+    };
+
+    if std::env::var_os(BLESS_VAR).is_some() {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(&path, &actual).unwrap();
+        return text;
+    }
+
+    let expected = fs::read(&path).unwrap_or_else(|e| {
+        panic!(
+            "could not read '{}': {}, run with {}=1 to create it",
+            path.display(),
+            e,
+            BLESS_VAR
+        )
+    });
+    if expected == actual {
+        return text;
+    }
+
+    let as_text = |bytes: &[u8]| {
+        if pbm {
+            text_from_pbm(bytes)
+        } else {
+            String::from_utf8_lossy(bytes).into_owned()
+        }
+    };
+    panic!(
+        "screen doesn't match '{}', run with {}=1 to accept it\n{}",
+        path.display(),
+        BLESS_VAR,
+        diff(&as_text(&expected), &as_text(&actual))
+    );
+}
+
+/// The screen rows of `--json` output with one line per row, `.` for dark pixels, `#`
+/// for lit ones and the color number for XO-CHIP's other colors.
+pub fn text_from_json(json: &str) -> String {
+    let rows = json
+        .split("\"rows\": [")
+        .nth(1)
+        .and_then(|rest| rest.split(']').next())
+        .expect("JSON output has screen rows");
+
+    let mut text = String::new();
+    for row in rows.split(',').map(|row| row.trim().trim_matches('"')) {
+        text.extend(row.chars().map(|color| match color {
+            '0' => '.',
+            '1' => '#',
+            color => color,
+        }));
+        text.push('\n');
+    }
+    text
+}
+
+/// A binary PBM file in the format of text golden files, or a note that it isn't one.
+pub fn text_from_pbm(pbm: &[u8]) -> String {
+    let header: Vec<&[u8]> = pbm.splitn(4, |byte| byte.is_ascii_whitespace()).collect();
+    let size = |field: &[u8]| std::str::from_utf8(field).ok()?.parse::<usize>().ok();
+
+    let (width, height, pixels) = match header.as_slice() {
+        [b"P4", width, height, pixels] => match (size(width), size(height)) {
+            (Some(width), Some(height)) => (width, height, pixels),
+            _ => return "(not a PBM file)\n".to_string(),
+        },
+        _ => return "(not a PBM file)\n".to_string(),
+    };
+
+    let row_bytes = width.div_ceil(8);
+    let mut text = String::new();
+    for row in pixels.chunks(row_bytes).take(height) {
+        for x in 0..width {
+            let lit = row
+                .get(x / 8)
+                .is_some_and(|byte| byte & 0x80 >> (x % 8) != 0);
+            text.push(if lit { '#' } else { '.' });
+        }
+        text.push('\n');
+    }
+    text
+}
+
+/// The lines that differ, numbered from 0 like screen rows, with `^` under each
+/// differing column.
+pub fn diff(expected: &str, actual: &str) -> String {
+    let expected: Vec<&str> = expected.lines().collect();
+    let actual: Vec<&str> = actual.lines().collect();
+    let mut out = String::new();
+
+    for row in 0..expected.len().max(actual.len()) {
+        let (want, got) = (
+            expected.get(row).copied().unwrap_or(""),
+            actual.get(row).copied().unwrap_or(""),
+        );
+        if want == got {
+            continue;
+        }
+
+        let marks: String = (0..want.chars().count().max(got.chars().count()))
+            .map(|x| {
+                if want.chars().nth(x) == got.chars().nth(x) {
+                    ' '
+                } else {
+                    '^'
+                }
+            })
+            .collect();
+        out.push_str(&format!(
+            "row {:>2} expected {}\n       actual   {}\n                {}\n",
+            row, want, got, marks
+        ));
+    }
+
+    if expected.len() != actual.len() {
+        out.push_str(&format!(
+            "expected {} rows, got {}\n",
+            expected.len(),
+            actual.len()
+        ));
+    }
+    out
+}
@@ -0,0 +1,214 @@
+//! Test ROMs run under every quirk profile, their result screens checked against
+//! `tests/golden/conformance` and what they report checked by name.
+//!
+//! `BC_test.ch8` and corax's `test_opcode.ch8` come with the repository. The IBM logo,
+//! flags, quirks and keypad tests in `tests/roms` are written for this suite in the
+//! repository's assembler, after the community ROMs of the same names, and checked in
+//! assembled.
+
+mod common;
+
+use chip8rust::asm;
+use chip8rust::display::FONT;
+use common::{assert_golden, Run};
+use std::fs;
+use std::ops::Range;
+use std::path::Path;
+
+const PROFILES: [&str; 5] = ["classic", "vip", "chip48", "schip", "modern"];
+
+/// All the ROMs are done long before this and then wait on their result screen.
+const FRAMES: u64 = 120;
+
+/// Sources of the ROMs in `tests/roms`, each assembled next to it.
+const SOURCES: [&str; 4] = ["ibm_logo", "flags", "quirks", "keypad"];
+
+fn run(rom: &'static str, quirks: &'static str, input: &'static str) -> String {
+    let run = Run {
+        quirks,
+        input,
+        ..Run::new(rom, FRAMES)
+    };
+    let name = Path::new(rom)
+        .file_stem()
+        .unwrap()
+        .to_string_lossy()
+        .to_lowercase();
+
+    assert_golden(&run, &format!("conformance/{}-{}.txt", name, quirks))
+}
+
+/// Which checks of a `tests/roms/report.asm` screen show a tick rather than a cross.
+/// Each check is 16 pixels wide, four to a row of 6, and only a cross lights the top
+/// left pixel of its mark, 5 pixels in.
+fn ticks(screen: &str, checks: usize) -> Vec<bool> {
+    let rows: Vec<&[u8]> = screen.lines().map(str::as_bytes).collect();
+
+    (0..checks)
+        .map(|check| rows[check / 4 * 6][check % 4 * 16 + 5] == b'.')
+        .collect()
+}
+
+/// Rows and columns of the `BC_test.ch8` error screen holding the error number.
+const BC_TEST_ERROR_ROWS: Range<usize> = 11..16;
+const BC_TEST_ERROR_COLUMNS: Range<usize> = 28..64;
+
+/// The error number `BC_test.ch8` shows, read back from the font's digits, or `None`
+/// if it shows no digits.
+fn bc_test_error(screen: &str) -> Option<u32> {
+    let rows: Vec<&[u8]> = screen
+        .lines()
+        .map(str::as_bytes)
+        .skip(BC_TEST_ERROR_ROWS.start)
+        .take(BC_TEST_ERROR_ROWS.len())
+        .collect();
+    let column = |x: usize| -> Vec<bool> { rows.iter().map(|row| row[x] == b'#').collect() };
+    let blank = |column: &[bool]| column.iter().all(|lit| !lit);
+
+    // A digit's lit columns, without the empty ones around it.
+    let glyph = |digit: usize| -> Vec<Vec<bool>> {
+        (0..4)
+            .map(|x| {
+                FONT[digit * 5..][..5]
+                    .iter()
+                    .map(|row| row & 0x80 >> x != 0)
+                    .collect::<Vec<_>>()
+            })
+            .filter(|column| !blank(column))
+            .collect()
+    };
+
+    let mut digits = Vec::new();
+    let mut current = Vec::new();
+    for x in BC_TEST_ERROR_COLUMNS {
+        let lit = column(x);
+        if !blank(&lit) {
+            current.push(lit);
+        } else if !current.is_empty() {
+            digits.push(std::mem::take(&mut current));
+        }
+    }
+
+    if digits.is_empty() {
+        return None;
+    }
+    digits
+        .iter()
+        .map(|columns| (0..10).find(|digit| glyph(*digit) == *columns))
+        .try_fold(0, |number, digit| Some(number * 10 + digit? as u32))
+}
+
+/// The ROMs in `tests/roms` are what their sources assemble to.
+#[test]
+fn assembled_roms_are_up_to_date() {
+    for name in SOURCES {
+        let dir = Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("tests")
+            .join("roms");
+        let assembly = asm::assemble_file(&dir.join(name).with_extension("asm")).unwrap();
+        let rom = fs::read(dir.join(name).with_extension("ch8")).unwrap();
+
+        assert!(
+            assembly.program == rom,
+            "{}.ch8 is out of date, run 'cargo run -- asm tests/roms/{}.asm'",
+            name,
+            name
+        );
+    }
+}
+
+/// Draws the IBM logo with nothing but `00E0`, `ANNN`, `6XNN`, `7XNN`, `DXYN` and
+/// `1NNN`, the same on every profile.
+#[test]
+fn ibm_logo() {
+    let screens: Vec<String> = PROFILES
+        .iter()
+        .map(|quirks| run("tests/roms/ibm_logo.ch8", quirks, ""))
+        .collect();
+
+    assert!(screens.iter().all(|screen| *screen == screens[0]));
+}
+
+/// Shows "OK" next to each opcode that passed. None of its checks depend on a
+/// quirk, so every profile passes with the same screen.
+#[test]
+fn corax_opcode_test() {
+    let screens: Vec<String> = PROFILES
+        .iter()
+        .map(|quirks| run("test_opcode.ch8", quirks, ""))
+        .collect();
+
+    assert!(screens.iter().all(|screen| *screen == screens[0]));
+}
+
+/// Shows "BON" when everything passed and an error number otherwise. It was written
+/// for SUPER-CHIP and passes with the classic profile too: the profiles shifting V[`Y`]
+/// stop at error 12, the shifts, and CHIP-48 at error 16 as `FX55`/`FX65` move I.
+#[test]
+fn bc_test() {
+    for quirks in ["classic", "schip"] {
+        let screen = run("BC_test.ch8", quirks, "");
+        assert_eq!(bc_test_error(&screen), None, "{}", quirks);
+    }
+
+    for (quirks, error) in [("vip", 12), ("modern", 12), ("chip48", 16)] {
+        let run = Run {
+            quirks,
+            ..Run::new("BC_test.ch8", FRAMES)
+        };
+        let screen = run.text(&format!("conformance/bc_test-{}", quirks));
+
+        assert_eq!(bc_test_error(&screen), Some(error), "{}", quirks);
+    }
+}
+
+/// Arithmetic and shift results and flags, including with VF as an operand. Every
+/// profile passes all 12 checks.
+#[test]
+fn flags_test() {
+    for quirks in PROFILES {
+        let screen = run("tests/roms/flags.ch8", quirks, "");
+
+        assert_eq!(ticks(&screen, 12), [true; 12], "{}", quirks);
+    }
+}
+
+/// Each profile's quirks, in the order the ROM checks them.
+#[test]
+fn quirks_test() {
+    const QUIRKS: [&str; 7] = [
+        "jump uses VX",
+        "logic resets VF",
+        "load/store moves I",
+        "load/store moves I by X + 1",
+        "shift uses VY",
+        "sprites clip",
+        "FX1E sets VF",
+    ];
+    let expected = [
+        ("classic", [false, false, false, false, false, false, true]),
+        ("vip", [false, true, true, true, true, true, false]),
+        ("chip48", [true, false, true, false, false, true, false]),
+        ("schip", [true, false, false, false, false, true, false]),
+        ("modern", [false, false, true, true, true, false, false]),
+    ];
+
+    for (quirks, on) in expected {
+        let screen = run("tests/roms/quirks.ch8", quirks, "");
+
+        for ((name, on), found) in QUIRKS.iter().zip(on).zip(ticks(&screen, QUIRKS.len())) {
+            assert_eq!(found, on, "{}: {}", quirks, name);
+        }
+    }
+}
+
+/// `FX0A`, `EX9E` and `EXA1` with key 5 pressed and released while the ROM waits for
+/// a key, then key A held. Every profile passes all 4 checks.
+#[test]
+fn keypad_test() {
+    for quirks in PROFILES {
+        let screen = run("tests/roms/keypad.ch8", quirks, "10 5\n15 -\n30 A\n80 -\n");
+
+        assert_eq!(ticks(&screen, 4), [true; 4], "{}", quirks);
+    }
+}
@@ -0,0 +1,41 @@
+mod common;
+
+use common::{assert_golden, diff, text_from_json, text_from_pbm, Run};
+
+#[test]
+fn pong_serves() {
+    assert_golden(&Run::new("pong.ch8", 150), "pong.txt");
+}
+
+#[test]
+fn guess_reads_keys() {
+    let run = Run {
+        input: "30 5\n40 -\n",
+        ..Run::new("guess.ch8", 90)
+    };
+
+    assert_golden(&run, "guess.pbm");
+}
+
+#[test]
+fn reads_screens() {
+    let json = "{\n  \"rows\": [\n    \"1023\",\n    \"0000\"\n  ]\n}\n";
+    assert_eq!(text_from_json(json), "#.23\n....\n");
+
+    // 10 pixels wide, two bytes a row.
+    let pbm = b"P4\n10 2\n\x80\x40\x00\x00";
+    assert_eq!(text_from_pbm(pbm), "#........#\n..........\n");
+    assert_eq!(text_from_pbm(b"P6\n1 1\n255\n"), "(not a PBM file)\n");
+}
+
+#[test]
+fn shows_differing_rows() {
+    assert_eq!(
+        diff("....\n.##.\n", "....\n.#.#\n"),
+        "row  1 expected .##.\n       actual   .#.#\n                  ^^\n"
+    );
+    assert_eq!(
+        diff("..\n", "..\n..\n"),
+        "row  1 expected \n       actual   ..\n                ^^\nexpected 1 rows, got 2\n"
+    );
+}
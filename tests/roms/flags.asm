@@ -0,0 +1,127 @@
+; Checks the result and VF of the arithmetic and shift instructions, with and without a
+; carry or borrow and with VF itself as an operand, where the flag has to be written
+; after the result. None of it depends on a quirk, every check should show a tick.
+;
+;   0-1  ADD Vx, Vy without and with a carry
+;   2-3  SUB Vx, Vy without and with a borrow
+;   4-5  SUBN Vx, Vy without and with a borrow
+;   6-7  SHR and SHL of a register by itself, shifting out a 1
+;   8-9  ADD and SUB into VF, which ends up holding the flag
+;   10   SHR of VF, which ends up holding the bit shifted out
+;   11   ADD with VF as the source, read before the flag is set
+;
+; Assemble with `cargo run -- asm tests/roms/flags.asm`.
+
+        CLS
+        LD VA, 0
+        LD VB, 0
+        LD VE, 0
+
+        LD V1, 0x10
+        LD V2, 0x20
+        ADD V1, V2
+        LD V5, VF
+        LD V3, 0x30
+        LD V4, 0
+        CALL check
+
+        LD V1, 0xF0
+        LD V2, 0x20
+        ADD V1, V2
+        LD V5, VF
+        LD V3, 0x10
+        LD V4, 1
+        CALL check
+
+        LD V1, 0x30
+        LD V2, 0x10
+        SUB V1, V2
+        LD V5, VF
+        LD V3, 0x20
+        LD V4, 1
+        CALL check
+
+        LD V1, 0x10
+        LD V2, 0x30
+        SUB V1, V2
+        LD V5, VF
+        LD V3, 0xE0
+        LD V4, 0
+        CALL check
+
+        LD V1, 0x10
+        LD V2, 0x30
+        SUBN V1, V2
+        LD V5, VF
+        LD V3, 0x20
+        LD V4, 1
+        CALL check
+
+        LD V1, 0x30
+        LD V2, 0x10
+        SUBN V1, V2
+        LD V5, VF
+        LD V3, 0xE0
+        LD V4, 0
+        CALL check
+
+        LD V1, 0x05
+        SHR V1, V1
+        LD V5, VF
+        LD V3, 0x02
+        LD V4, 1
+        CALL check
+
+        LD V1, 0x81
+        SHL V1, V1
+        LD V5, VF
+        LD V3, 0x02
+        LD V4, 1
+        CALL check
+
+        LD VF, 0xF0
+        LD V2, 0x20
+        ADD VF, V2
+        LD V5, VF
+        LD V1, VF
+        LD V3, 1
+        LD V4, 1
+        CALL check
+
+        LD VF, 0x10
+        LD V2, 0x30
+        SUB VF, V2
+        LD V5, VF
+        LD V1, VF
+        LD V3, 0
+        LD V4, 0
+        CALL check
+
+        LD VF, 0x05
+        SHR VF, VF
+        LD V5, VF
+        LD V1, VF
+        LD V3, 1
+        LD V4, 1
+        CALL check
+
+        LD V1, 0x10
+        LD VF, 0x20
+        ADD V1, VF
+        LD V5, VF
+        LD V3, 0x30
+        LD V4, 0
+        CALL check
+
+done:   JP done
+
+; Report whether V1 and the flag copied to V5 are V3 and V4.
+check:
+        LD VD, 0
+        SE V1, V3
+        LD VD, 1
+        SE V5, V4
+        LD VD, 1
+        JP report
+
+include "report.asm"
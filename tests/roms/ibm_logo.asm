@@ -0,0 +1,40 @@
+; The striped IBM logo, drawn like the well-known IBM logo test ROM: clear the screen,
+; then 15-row sprites placed with LD I, LD and ADD and drawn with DRW, then a jump to
+; itself. Only those instructions are used, so every interpreter should draw it.
+;
+; Assemble with `cargo run -- asm tests/roms/ibm_logo.asm`.
+
+        CLS
+        LD V0, 8            ; x
+        LD V1, 8            ; y
+
+        LD I, letter_i
+        DRW V0, V1, 15
+        ADD V0, 12
+
+        LD I, letter_b_left
+        DRW V0, V1, 15
+        ADD V0, 8
+        LD I, letter_b_right
+        DRW V0, V1, 15
+        ADD V0, 12
+
+        LD I, letter_m_left
+        DRW V0, V1, 15
+        ADD V0, 8
+        LD I, letter_m_right
+        DRW V0, V1, 15
+
+done:   JP done
+
+; Every other row is empty, which gives the logo its stripes.
+letter_i:
+        db 0xFF, 0, 0xFF, 0, 0x3C, 0, 0x3C, 0, 0x3C, 0, 0x3C, 0, 0xFF, 0, 0xFF
+letter_b_left:
+        db 0xFF, 0, 0xFF, 0, 0x3C, 0, 0x3F, 0, 0x3F, 0, 0x3C, 0, 0xFF, 0, 0xFF
+letter_b_right:
+        db 0xF0, 0, 0xFC, 0, 0x3C, 0, 0xF0, 0, 0xF0, 0, 0x3C, 0, 0xFC, 0, 0xF0
+letter_m_left:
+        db 0xFC, 0, 0xFE, 0, 0x3F, 0, 0x37, 0, 0x33, 0, 0x31, 0, 0xF8, 0, 0xF8
+letter_m_right:
+        db 0x3F, 0, 0x7F, 0, 0xFC, 0, 0xEC, 0, 0xCC, 0, 0x8C, 0, 0x1F, 0, 0x1F
@@ -0,0 +1,50 @@
+; Checks the keypad instructions against scripted input: key 5 pressed and released while
+; LD Vx, K waits, then key A held down from about half a second on. None of it depends
+; on a quirk, every check should show a tick.
+;
+;   0  LD Vx, K gives the key that was pressed
+;   1  it only continues once the key is released again
+;   2  SKP skips for a key that is held down
+;   3  SKP doesn't skip for a key that isn't
+;
+; Assemble with `cargo run -- asm tests/roms/keypad.asm`.
+
+        CLS
+        LD VA, 0
+        LD VB, 0
+        LD VE, 0
+
+        LD V1, K
+        LD VD, 0
+        SKNP V1
+        LD VD, 1
+        LD V6, VD
+        LD VD, 0
+        SE V1, 5
+        LD VD, 1
+        CALL report
+        LD VD, V6
+        CALL report
+
+        ; Give key A time to go down.
+        LD V0, 30
+        LD DT, V0
+wait:   LD V0, DT
+        SE V0, 0
+        JP wait
+
+        LD V2, 0xA
+        LD VD, 1
+        SKNP V2
+        LD VD, 0
+        CALL report
+
+        LD V3, 0xB
+        LD VD, 1
+        SKP V3
+        LD VD, 0
+        CALL report
+
+done:   JP done
+
+include "report.asm"
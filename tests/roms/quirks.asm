@@ -0,0 +1,86 @@
+; Shows which quirks the interpreter has, a tick for each one that is on:
+;
+;   0  JP V0, NNN jumps to NNN plus VX, X being the top digit of NNN
+;   1  OR, AND and XOR reset VF
+;   2  LD [I], Vx and LD Vx, [I] move I
+;   3  they move I past the last register, by X + 1
+;   4  SHR and SHL shift VY into VX
+;   5  sprites are clipped at the screen edges instead of wrapping around
+;   6  ADD I, Vx sets VF when I goes past 0xFFF
+;
+; Assemble with `cargo run -- asm tests/roms/quirks.asm`.
+
+        CLS
+        LD VA, 0
+        LD VB, 0
+        LD VE, 0
+
+        ; First, so jump_target stays below 0x300 and X is 2.
+        LD V0, 0
+        LD V2, 4
+        LD VD, 1
+        JP V0, jump_target
+jump_target:
+        JP jumped
+        db 0, 0
+        LD VD, 0
+jumped:
+        CALL report
+
+        LD VF, 5
+        LD V1, 0
+        OR V1, V1
+        LD VD, VF
+        CALL report
+
+        LD I, markers
+        LD V1, [I]
+        LD V0, [I]
+        LD V6, V0
+        LD VD, 0
+        SNE V6, 0xA0
+        LD VD, 1
+        CALL report
+        LD VD, 0
+        SE V6, 0xA2
+        LD VD, 1
+        CALL report
+
+        LD V1, 0x01
+        LD V2, 0x80
+        SHR V1, V2
+        LD VD, 0
+        SE V1, 0x40
+        LD VD, 1
+        CALL report
+
+        ; A line drawn across the right edge only collides with one at the left edge
+        ; if it wraps around, both are drawn again to erase them.
+        LD V6, 60
+        LD V7, 26
+        LD V8, 0
+        LD I, line
+        DRW V6, V7, 1
+        DRW V8, V7, 1
+        LD VD, VF
+        DRW V8, V7, 1
+        DRW V6, V7, 1
+        CALL report
+
+        LD I, 0xFFF
+        LD V1, 1
+        LD VF, 0
+        ADD I, V1
+        LD V6, VF
+        LD VD, 1
+        SUB VD, V6
+        CALL report
+
+done:   JP done
+
+markers:
+        db 0xA0, 0xA1, 0xA2, 0xA3
+line:
+        db 0xFF
+
+include "report.asm"
@@ -0,0 +1,31 @@
+; Result screen shared by the test ROMs: every check shows its number in the font and
+; a mark after it, four checks to a row.
+;
+; Call `report` with VD 0 to show a tick and anything else to show a cross, the marks
+; differ in their top left pixel, which only the cross lights. Checks are numbered from
+; VE, and VA and VB hold where the next one goes, all four start at 0. VC and VF are
+; overwritten.
+
+report:
+        LD F, VE
+        DRW VA, VB, 5
+        ADD VA, 5
+        LD I, tick
+        SE VD, 0
+        LD I, cross
+        DRW VA, VB, 5
+        ADD VA, 11
+
+        ADD VE, 1
+        LD VC, 3
+        AND VC, VE
+        SE VC, 0
+        RET
+        LD VA, 0
+        ADD VB, 6
+        RET
+
+tick:
+        db 0x08, 0x10, 0xA0, 0x40, 0x00
+cross:
+        db 0x88, 0x50, 0x20, 0x50, 0x88